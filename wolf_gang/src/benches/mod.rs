@@ -0,0 +1 @@
+pub mod octree;
\ No newline at end of file
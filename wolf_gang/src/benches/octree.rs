@@ -0,0 +1,128 @@
+use nalgebra::Vector3;
+use test::Bencher;
+
+use crate::collections::octree::{Octree, Execution};
+use crate::collections::octree;
+use crate::geometry::aabb;
+use crate::systems::level_map::TileData;
+
+type Point = Vector3<i32>;
+type AABB = aabb::AABB<i32>;
+
+// Sizes are taken from level_map::Map. A single tile change, the default chunk dimensions, and the ranges that span several chunks
+// when painting with a large selection box.
+const SMALL: i32 = 4;
+const CHUNK: i32 = 10;
+const LARGE: i32 = 20;
+const HUGE: i32 = 40;
+// Far past anything the editor makes, these are only here to find where Execution::Parallel starts paying for itself, which is what
+// octree::PARALLEL_VOLUME_THRESHOLD is set from.
+const GIANT: i32 = 64;
+const COLOSSAL: i32 = 100;
+
+fn cube(size: i32) -> AABB {
+    AABB::from_extents(Point::zeros(), Point::new(size - 1, size - 1, size - 1))
+}
+
+fn filled_octree(size: i32, execution: Execution) -> Octree<i32, TileData> {
+    let aabb = cube(size);
+    let mut octree = Octree::with_execution(aabb, octree::DEFAULT_MAX, execution);
+
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                octree.insert(TileData::new(0, Point::new(x, y, z))).unwrap();
+            }
+        }
+    }
+
+    octree
+}
+
+fn bench_fill(b: &mut Bencher, size: i32, execution: Execution) {
+    b.iter(|| filled_octree(size, execution));
+}
+
+fn bench_query_range(b: &mut Bencher, size: i32, execution: Execution) {
+    let octree = filled_octree(size, execution);
+    let half = size / 2;
+    let range = AABB::from_extents(Point::new(1, 1, 1), Point::new(half, half, half));
+
+    b.iter(|| octree.query_range(range));
+}
+
+fn bench_query_point(b: &mut Bencher, size: i32, execution: Execution) {
+    let octree = filled_octree(size, execution);
+    let point = Point::new(size - 1, size / 2, 0);
+
+    b.iter(|| octree.query_point(point));
+}
+
+fn bench_query_sphere(b: &mut Bencher, size: i32, execution: Execution) {
+    let octree = filled_octree(size, execution);
+    let half = size / 2;
+
+    b.iter(|| octree.query_sphere(Point::new(half, half, half), half / 2));
+}
+
+fn bench_remove_range(b: &mut Bencher, size: i32, execution: Execution) {
+    let octree = filled_octree(size, execution);
+    let half = size / 2;
+    let range = AABB::from_extents(Point::new(1, 1, 1), Point::new(half, half, half));
+
+    b.iter(|| {
+        let mut octree = octree.clone();
+        octree.remove_range(range);
+        octree
+    });
+}
+
+#[bench] fn fill_small_sequential(b: &mut Bencher) { bench_fill(b, SMALL, Execution::Sequential) }
+#[bench] fn fill_small_parallel(b: &mut Bencher) { bench_fill(b, SMALL, Execution::Parallel) }
+#[bench] fn fill_chunk_sequential(b: &mut Bencher) { bench_fill(b, CHUNK, Execution::Sequential) }
+#[bench] fn fill_chunk_parallel(b: &mut Bencher) { bench_fill(b, CHUNK, Execution::Parallel) }
+#[bench] fn fill_large_sequential(b: &mut Bencher) { bench_fill(b, LARGE, Execution::Sequential) }
+#[bench] fn fill_large_parallel(b: &mut Bencher) { bench_fill(b, LARGE, Execution::Parallel) }
+#[bench] fn fill_huge_sequential(b: &mut Bencher) { bench_fill(b, HUGE, Execution::Sequential) }
+#[bench] fn fill_huge_parallel(b: &mut Bencher) { bench_fill(b, HUGE, Execution::Parallel) }
+#[bench] fn fill_huge_automatic(b: &mut Bencher) { bench_fill(b, HUGE, Execution::Automatic) }
+
+#[bench] fn query_range_small_sequential(b: &mut Bencher) { bench_query_range(b, SMALL, Execution::Sequential) }
+#[bench] fn query_range_small_parallel(b: &mut Bencher) { bench_query_range(b, SMALL, Execution::Parallel) }
+#[bench] fn query_range_chunk_sequential(b: &mut Bencher) { bench_query_range(b, CHUNK, Execution::Sequential) }
+#[bench] fn query_range_chunk_parallel(b: &mut Bencher) { bench_query_range(b, CHUNK, Execution::Parallel) }
+#[bench] fn query_range_large_sequential(b: &mut Bencher) { bench_query_range(b, LARGE, Execution::Sequential) }
+#[bench] fn query_range_large_parallel(b: &mut Bencher) { bench_query_range(b, LARGE, Execution::Parallel) }
+#[bench] fn query_range_huge_sequential(b: &mut Bencher) { bench_query_range(b, HUGE, Execution::Sequential) }
+#[bench] fn query_range_huge_parallel(b: &mut Bencher) { bench_query_range(b, HUGE, Execution::Parallel) }
+#[bench] fn query_range_huge_automatic(b: &mut Bencher) { bench_query_range(b, HUGE, Execution::Automatic) }
+
+#[bench] fn query_range_giant_sequential(b: &mut Bencher) { bench_query_range(b, GIANT, Execution::Sequential) }
+#[bench] fn query_range_giant_parallel(b: &mut Bencher) { bench_query_range(b, GIANT, Execution::Parallel) }
+#[bench] fn query_range_colossal_sequential(b: &mut Bencher) { bench_query_range(b, COLOSSAL, Execution::Sequential) }
+#[bench] fn query_range_colossal_parallel(b: &mut Bencher) { bench_query_range(b, COLOSSAL, Execution::Parallel) }
+
+#[bench] fn query_sphere_chunk_sequential(b: &mut Bencher) { bench_query_sphere(b, CHUNK, Execution::Sequential) }
+#[bench] fn query_sphere_chunk_parallel(b: &mut Bencher) { bench_query_sphere(b, CHUNK, Execution::Parallel) }
+#[bench] fn query_sphere_huge_sequential(b: &mut Bencher) { bench_query_sphere(b, HUGE, Execution::Sequential) }
+#[bench] fn query_sphere_huge_parallel(b: &mut Bencher) { bench_query_sphere(b, HUGE, Execution::Parallel) }
+#[bench] fn query_sphere_giant_sequential(b: &mut Bencher) { bench_query_sphere(b, GIANT, Execution::Sequential) }
+#[bench] fn query_sphere_giant_parallel(b: &mut Bencher) { bench_query_sphere(b, GIANT, Execution::Parallel) }
+#[bench] fn query_sphere_colossal_sequential(b: &mut Bencher) { bench_query_sphere(b, COLOSSAL, Execution::Sequential) }
+#[bench] fn query_sphere_colossal_parallel(b: &mut Bencher) { bench_query_sphere(b, COLOSSAL, Execution::Parallel) }
+
+#[bench] fn query_point_chunk_sequential(b: &mut Bencher) { bench_query_point(b, CHUNK, Execution::Sequential) }
+#[bench] fn query_point_chunk_parallel(b: &mut Bencher) { bench_query_point(b, CHUNK, Execution::Parallel) }
+#[bench] fn query_point_huge_sequential(b: &mut Bencher) { bench_query_point(b, HUGE, Execution::Sequential) }
+#[bench] fn query_point_huge_parallel(b: &mut Bencher) { bench_query_point(b, HUGE, Execution::Parallel) }
+
+#[bench] fn remove_range_chunk_sequential(b: &mut Bencher) { bench_remove_range(b, CHUNK, Execution::Sequential) }
+#[bench] fn remove_range_chunk_parallel(b: &mut Bencher) { bench_remove_range(b, CHUNK, Execution::Parallel) }
+#[bench] fn remove_range_large_sequential(b: &mut Bencher) { bench_remove_range(b, LARGE, Execution::Sequential) }
+#[bench] fn remove_range_large_parallel(b: &mut Bencher) { bench_remove_range(b, LARGE, Execution::Parallel) }
+#[bench] fn remove_range_huge_sequential(b: &mut Bencher) { bench_remove_range(b, HUGE, Execution::Sequential) }
+#[bench] fn remove_range_huge_parallel(b: &mut Bencher) { bench_remove_range(b, HUGE, Execution::Parallel) }
+#[bench] fn remove_range_giant_sequential(b: &mut Bencher) { bench_remove_range(b, GIANT, Execution::Sequential) }
+#[bench] fn remove_range_giant_parallel(b: &mut Bencher) { bench_remove_range(b, GIANT, Execution::Parallel) }
+#[bench] fn remove_range_colossal_sequential(b: &mut Bencher) { bench_remove_range(b, COLOSSAL, Execution::Sequential) }
+#[bench] fn remove_range_colossal_parallel(b: &mut Bencher) { bench_remove_range(b, COLOSSAL, Execution::Parallel) }
@@ -4,22 +4,38 @@ use serde::{Serialize, Deserialize};
 use std::ops::{AddAssign, SubAssign, DivAssign};
 
 use std::sync::mpsc;
-use std::sync::Arc;
 
 use rayon::prelude::*;
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::error;
 use std::fmt;
 use std::iter::FromIterator;
 
 use nalgebra::{Scalar, Vector3};
 use num::{Num, NumCast, Signed, traits::Bounded};
-use crate::geometry::aabb::AABB;
+use crate::geometry::{
+    aabb::{AABB, partial_min, partial_max},
+    ray::Ray,
+};
 
 pub static DEFAULT_MAX: usize = 32;
 
+/// Octrees with a smaller volume than this are worked on sequentially when using Execution::Automatic. Going by benches/octree.rs, run
+/// on a single core, the parallel queries were still 4 to 6 times slower at 100 tiles across and only remove_range had caught up, so the
+/// threshold sits at the largest size the benches cover. It should come down if running them with more cores shows an earlier crossover.
+pub static PARALLEL_VOLUME_THRESHOLD: usize = 1_000_000;
+
 pub trait PointData<N: Scalar> : Copy {
     fn get_point(&self) -> Vector3<N>;
+    /// Returns a copy of self at point, used to produce the elements of a uniform fill
+    fn with_point(&self, point: Vector3<N>) -> Self;
+    /// The space taken up by the element, elements that have a size other than a single point should override this
+    fn get_bounds(&self) -> AABB<N> where N: Signed + Num + NumCast + PartialOrd + Copy {
+        AABB::from_extents(self.get_point(), self.get_point())
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
@@ -29,6 +45,31 @@ enum Paternity {
     ChildFree
 }
 
+/// Whether the work done by an octree gets spread across rayon's thread pool or stays on the calling thread. Children always use the 
+/// same Execution as their parent.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub enum Execution {
+    /// Sequential for any node with a volume smaller than PARALLEL_VOLUME_THRESHOLD, parallel otherwise
+    Automatic,
+    Sequential,
+    Parallel,
+}
+
+impl Default for Execution {
+    fn default() -> Self {
+        Execution::Automatic
+    }
+}
+
+/// How the root of an octree deals with elements that fall outside of its AABB
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub enum Growth<N: Scalar> {
+    /// Elements outside of the AABB are rejected as OutOfBounds
+    Fixed,
+    /// The root re-parents itself under a larger root until it covers the element, as long as the element is inside of the limit
+    Growable(Option<AABB<N>>),
+}
+
 pub struct OctreeIter <N: Scalar, T: PointData<N>> {
     elements: std::vec::IntoIter<T>,
     phantom: std::marker::PhantomData<N>
@@ -44,7 +85,67 @@ impl<'a, N: Scalar, T: PointData<N>> Iterator for OctreeIter<N, T> {
     }
 }
 
-impl<'de, N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAssign + DivAssign + Copy + Clone + Serialize + Deserialize<'de>, T: PointData<N> + Hash + Eq + PartialEq + Debug + Sync + Send> IntoIterator for Octree<N, T> {
+/// Iterates over the elements of an octree that fall within range without consuming it, skipping any children that don't intersect the
+/// range. Stored elements are borrowed. Uniform fills don't store an element for each of their points, so those are made one point at a 
+/// time as they are reached and handed out owned, which costs a copy of the template each.
+pub struct OctreeRefIter<'a, N: Scalar, T: PointData<N>> {
+    range: AABB<N>,
+    elements: std::slice::Iter<'a, T>,
+    nodes: Vec<&'a Octree<N, T>>,
+    uniform: Option<(T, AABB<N>)>,
+    uniform_index: usize,
+    uniform_volume: usize,
+}
+
+impl<'a, N: Signed + Scalar + Num + NumCast + PartialOrd + Copy + Clone, T: PointData<N>> Iterator for OctreeRefIter<'a, N, T> {
+    type Item = Cow<'a, T>;
+    fn next(&mut self) -> Option<Cow<'a, T>> {
+        loop {
+            let range = self.range;
+
+            if let Some((template, uniform_range)) = self.uniform {
+                if self.uniform_index < self.uniform_volume {
+                    let point = uniform_range.get_point_at_index(self.uniform_index);
+                    self.uniform_index += 1;
+
+                    return Some(Cow::Owned(template.with_point(point)));
+                }
+
+                self.uniform = None;
+            }
+
+            if let Some(element) = self.elements.by_ref().find(|element| range.contains_point(element.get_point())) {
+                return Some(Cow::Borrowed(element));
+            }
+
+            let node = self.nodes.pop()?;
+
+            self.elements = node.elements.iter();
+
+            if let Some(template) = node.uniform {
+                let uniform_range = node.aabb.get_intersection(range);
+
+                self.uniform = Some((template, uniform_range));
+                self.uniform_index = 0;
+                self.uniform_volume = uniform_range.get_volume();
+            }
+
+            if let Paternity::ProudParent = node.paternity {
+                self.nodes.extend(node.children.iter().filter(|child| child.aabb.intersects_bounds(range)));
+            }
+        }
+    }
+}
+
+impl<'a, N: Sync + Send + Signed + Scalar + Num + NumCast + PartialOrd + AddAssign + SubAssign + DivAssign + Copy + Clone, T: PointData<N> + PartialEq + Debug + Sync + Send> IntoIterator for &'a Octree<N, T> {
+    type Item = Cow<'a, T>;
+    type IntoIter = OctreeRefIter<'a, N, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'de, N: Sync + Send + Signed + Scalar + Num + NumCast + PartialOrd + AddAssign + SubAssign + DivAssign + Copy + Clone + Serialize + Deserialize<'de>, T: PointData<N> + PartialEq + Debug + Sync + Send> IntoIterator for Octree<N, T> {
     type Item = T;
     type IntoIter = OctreeIter<N, T>;
     fn into_iter(self) -> Self::IntoIter {
@@ -56,7 +157,7 @@ impl<'de, N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + S
     }
 }
 
-impl <'de, N: Sync + Send + Bounded + Signed + Scalar + NumCast + Ord + AddAssign + SubAssign + DivAssign + Copy + Clone + Serialize + Deserialize<'de>, T: PointData<N> + Hash + Eq + PartialEq + Debug + Sync + Send> FromIterator<T> for Octree<N, T> {
+impl <'de, N: Sync + Send + Bounded + Signed + Scalar + NumCast + PartialOrd + AddAssign + SubAssign + DivAssign + Copy + Clone + Serialize + Deserialize<'de>, T: PointData<N> + PartialEq + Debug + Sync + Send> FromIterator<T> for Octree<N, T> {
     fn from_iter<A: IntoIterator<Item=T>>(iter: A) -> Self {
 
         let mut smallest = Vector3::<N>::new(Bounded::max_value(), Bounded::max_value(), Bounded::max_value());
@@ -71,13 +172,13 @@ impl <'de, N: Sync + Send + Bounded + Signed + Scalar + NumCast + Ord + AddAssig
             for item in items.iter() {
                 let pt = item.get_point();
 
-                smallest.x = Ord::min(pt.x, smallest.x);
-                smallest.y = Ord::min(pt.y, smallest.y);
-                smallest.z = Ord::min(pt.z, smallest.z);
+                smallest.x = partial_min(pt.x, smallest.x);
+                smallest.y = partial_min(pt.y, smallest.y);
+                smallest.z = partial_min(pt.z, smallest.z);
 
-                largest.x = Ord::max(pt.x, largest.x);
-                largest.y = Ord::max(pt.y, largest.y);
-                largest.z = Ord::max(pt.z, largest.z);
+                largest.x = partial_max(pt.x, largest.x);
+                largest.y = partial_max(pt.y, largest.y);
+                largest.z = partial_max(pt.z, largest.z);
             }
         }
 
@@ -91,7 +192,7 @@ impl <'de, N: Sync + Send + Bounded + Signed + Scalar + NumCast + Ord + AddAssig
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(dead_code)]
 pub struct Octree <N: Scalar, T: PointData<N>>{
     aabb: AABB<N>,
@@ -99,13 +200,78 @@ pub struct Octree <N: Scalar, T: PointData<N>>{
     elements: Vec<T>,
     children: Vec<Octree<N, T>>,
     paternity: Paternity,
+    #[serde(skip)]
+    execution: Execution,
+    /// When set, this node is a leaf where every point inside of aabb holds a copy of this element, and nothing is stored in elements
+    uniform: Option<T>,
+    /// Encloses aabb along with the bounds of every element stored in this node or its children, which can stick out of aabb when 
+    /// elements have a size. It grows as elements get inserted but never shrinks.
+    loose_bounds: AABB<N>,
+    /// Only ever anything other than Fixed for the root
+    growth: Growth<N>,
     // phantom: PhantomData<&'a Octree<'a, N, T>>
 }
 
+/// Octrees are equal if they hold the same elements in the same places, the Execution they were created with doesn't matter
+impl<N: Scalar, T: PointData<N> + PartialEq> PartialEq for Octree<N, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.aabb == other.aabb
+            && self.max_elements == other.max_elements
+            && self.paternity == other.paternity
+            && self.elements == other.elements
+            && self.children == other.children
+            && self.uniform == other.uniform
+    }
+}
+
+/// The layout octrees were saved with before uniform fills, loose bounds and growth were added, so that files saved back then can 
+/// still be read. The elements are left as whatever they were saved as, since they can have changed since too.
+#[derive(Deserialize, Debug)]
+pub struct LegacyOctree<N: Scalar, T> {
+    aabb: AABB<N>,
+    max_elements: usize,
+    elements: Vec<T>,
+    children: Vec<LegacyOctree<N, T>>,
+    paternity: Paternity,
+}
+
+impl<N: Scalar, T> LegacyOctree<N, T> {
+
+    /// Returns every element held by the octree and its children
+    pub fn into_elements(self) -> Vec<T> {
+        let mut elements = self.elements;
+
+        self.children.into_iter().for_each(|child| elements.extend(child.into_elements()));
+
+        elements
+    }
+}
+
+impl<N: Sync + Send + Signed + Scalar + Num + NumCast + PartialOrd + AddAssign + SubAssign + DivAssign + Copy + Clone, T> LegacyOctree<N, T> {
+
+    /// Returns an octree covering the same range with each element converted by f, any that end up outside of the range are dropped
+    pub fn into_octree<U: PointData<N> + PartialEq + Debug + Sync + Send, F: Fn(T) -> U>(self, f: F) -> Octree<N, U> {
+        let mut octree = Octree::new(self.aabb, self.max_elements);
+
+        self.into_elements().into_iter().for_each(|element| {
+            octree.insert(f(element)).ok();
+        });
+
+        octree.compress();
+
+        octree
+    }
+}
+
 #[allow(dead_code)]
-impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAssign + DivAssign + Copy + Clone, T: PointData<N> + Hash + Eq + PartialEq + Debug + Sync + Send> Octree<N, T> {
+impl<N: Sync + Send + Signed + Scalar + Num + NumCast + PartialOrd + AddAssign + SubAssign + DivAssign + Copy + Clone, T: PointData<N> + PartialEq + Debug + Sync + Send> Octree<N, T> {
 
     pub fn new(aabb: AABB<N>, max_elements: usize) -> Octree<N, T> {
+        Octree::with_execution(aabb, max_elements, Execution::default())
+    }
+
+    /// Creates an octree that always uses the given Execution strategy rather than choosing one by its size
+    pub fn with_execution(aabb: AABB<N>, max_elements: usize, execution: Execution) -> Octree<N, T> {
         #[cfg(test)]
         println!("Creating new Octree with a min of {:?} and a max of {:?}", aabb.get_min(), aabb.get_max());
 
@@ -115,6 +281,10 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
             elements: Vec::with_capacity(max_elements),
             children: Vec::with_capacity(8),
             paternity: Paternity::ChildFree,
+            execution,
+            uniform: None,
+            loose_bounds: aabb,
+            growth: Growth::Fixed,
             // phantom: PhantomData
         }
     }
@@ -127,7 +297,128 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
         self.max_elements
     }
 
-    fn subdivide(&mut self) -> Result<(), SubdivisionError<N>> {
+    /// Creates an octree that grows to fit elements inserted outside of aabb instead of rejecting them, up to the optional hard limit
+    pub fn growable(aabb: AABB<N>, max_elements: usize, limit: Option<AABB<N>>) -> Octree<N, T> {
+        let mut octree = Octree::new(aabb, max_elements);
+        octree.growth = Growth::Growable(limit);
+
+        octree
+    }
+
+    pub fn get_execution(&self) -> Execution {
+        self.execution
+    }
+
+    pub fn get_growth(&self) -> Growth<N> {
+        self.growth
+    }
+
+    /// Grows a growable octree until it contains point, does nothing for a fixed one
+    fn grow_to_contain(&mut self, point: Vector3<N>) -> Result<(), InsertionError<N>> {
+
+        let limit = match self.growth {
+            Growth::Fixed => return Ok(()),
+            Growth::Growable(limit) => limit
+        };
+
+        if let Some(limit) = limit {
+            if !limit.contains_point(point) {
+                return Err(
+                    InsertionError {
+                        error_type: InsertionErrorType::OutOfBounds(limit)
+                    }
+                )
+            }
+        }
+
+        while !self.aabb.contains_point(point) {
+            self.grow_towards(point, limit);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the root with one that is twice as large in the direction of point, and makes the old root one of its children. If the 
+    /// limit cuts the new root short so that the old root no longer fits as a child, the elements get inserted into the new root instead.
+    fn grow_towards(&mut self, point: Vector3<N>, limit: Option<AABB<N>>) {
+
+        let zero: N = NumCast::from(0).unwrap();
+        let one: N = NumCast::from(1).unwrap();
+        let two: N = NumCast::from(2).unwrap();
+
+        let min = self.aabb.get_min();
+        let mut dimensions = self.aabb.dimensions.abs();
+        let mut grown_min = min;
+
+        for i in 0..3 {
+            if dimensions[i] == zero {
+                dimensions[i] = one;
+            }
+
+            if point[i] < min[i] {
+                grown_min[i] = min[i] - dimensions[i];
+            }
+        }
+
+        let grown_dimensions = Vector3::new(dimensions.x * two, dimensions.y * two, dimensions.z * two);
+
+        let mut aabb = AABB::new(
+            Vector3::new(
+                grown_min.x + grown_dimensions.x/two, 
+                grown_min.y + grown_dimensions.y/two, 
+                grown_min.z + grown_dimensions.z/two
+            ),
+            grown_dimensions
+        );
+
+        if let Some(limit) = limit {
+            aabb = aabb.get_intersection(limit);
+        }
+
+        let mut grown = Octree::with_execution(aabb, self.max_elements, self.execution);
+        grown.growth = self.growth;
+
+        let mut old_root = std::mem::replace(self, grown);
+        old_root.growth = Growth::Fixed;
+
+        match self.get_subdivisions().iter().position(|subdivision| *subdivision == old_root.aabb) {
+            Some(index) => {
+                match self.subdivide() {
+                    Ok(_) => {},
+                    Err(err) => {
+                        panic!("{:?}", err);
+                    }
+                }
+
+                self.loose_bounds = self.loose_bounds.union(old_root.loose_bounds);
+                self.children[index] = old_root;
+            },
+            None => {
+                for element in old_root.iter() {
+                    self.push(*element).ok();
+                }
+
+                self.compress();
+            }
+        }
+    }
+
+    /// Whether or not work on this node and its children should be spread across rayon's thread pool
+    fn is_parallel(&self) -> bool {
+        match self.execution {
+            Execution::Sequential => false,
+            Execution::Parallel => true,
+            Execution::Automatic => {
+                let dimensions = self.aabb.dimensions.abs();
+                let volume: usize = NumCast::from(dimensions.x * dimensions.y * dimensions.z).unwrap_or(std::usize::MAX);
+
+                volume >= PARALLEL_VOLUME_THRESHOLD
+            }
+        }
+    }
+
+    /// Returns the AABBs of the children that this octree would be split into, smaller dimensions get split into fewer children
+    fn get_subdivisions(&self) -> Vec<AABB<N>> {
 
         let zero: N = NumCast::from(0).unwrap();
         let one: N = NumCast::from(1).unwrap();
@@ -148,152 +439,109 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
         let smaller_half = dimensions/two;
         let larger_half = dimensions - smaller_half - Vector3::new(adj, adj, adj);
 
-        let (tx, rx) = mpsc::channel::<Octree<N,T>>();
+        let mut subdivisions = Vec::with_capacity(8);
 
-        let max_elements = Arc::new(self.max_elements);
+        //down back left
+        subdivisions.push(AABB::<N>::from_extents(
+            min,
+            min + larger_half
+        ));
 
-        rayon::scope(move |s| {
-            
-            //down back left
-            let sub_max = min + larger_half;
+        if dimensions.x > one {
 
-            let downbackleft = AABB::<N>::from_extents(
-                min,
-                sub_max
-            );
-            tx.send(Octree::new(downbackleft, *max_elements)).unwrap();
+            //down back right
+            let sub_min = min + Vector3::new(larger_half.x + adj, zero, zero);
+            subdivisions.push(AABB::<N>::from_extents(
+                sub_min,
+                Vector3::new(max.x, sub_min.y + larger_half.y, sub_min.z + larger_half.z)
+            ));
 
-            if dimensions.x > one {
+            if dimensions.z > one {
 
-                let tx1 = tx.clone();
-                let max_elements = Arc::clone(&max_elements);
+                //down forward right
+                let sub_min = min + Vector3::new(larger_half.x + adj, zero, larger_half.z + adj);
+                subdivisions.push(AABB::<N>::from_extents(
+                    sub_min,
+                    Vector3::new(max.x, sub_min.y + larger_half.y, max.z)
+                ));
 
-                s.spawn(move |s| {
+                if dimensions.y > one {
 
-                    let sub_min = min + Vector3::new(larger_half.x + adj, zero, zero);
-                    let sub_max = Vector3::new(
-                        max.x, sub_min.y + larger_half.y, sub_min.z + larger_half.z
-                    );
-        
-                    let downbackright = AABB::<N>::from_extents(
-                        sub_min,
-                        sub_max
-                    );
-                    tx1.send(Octree::new(downbackright, *max_elements)).unwrap();
-        
-                    if dimensions.z > one {
-                        
-                        s.spawn(move |s| {
-
-                            //down forward right
-                            let sub_min = min + Vector3::new(larger_half.x + adj, zero, larger_half.z + adj);
-                            let sub_max = Vector3::new(
-                                max.x, sub_min.y + larger_half.y, max.z
-                            );
-                            let downforwardright = AABB::<N>::from_extents(
-                                sub_min,
-                                sub_max
-                            );
-                            tx1.send(Octree::new(downforwardright, *max_elements)).unwrap();
-            
-                            if dimensions.y > one {
-                                s.spawn(move |_| {
-                                    
-                                    //up forward right
-                                    let sub_min = min + Vector3::new(larger_half.x + adj, larger_half.y + adj, larger_half.z + adj);
-                                    let upforwardright = AABB::<N>::from_extents(
-                                        sub_min,
-                                        max
-                                    );
-                                    tx1.send(Octree::new(upforwardright, *max_elements)).unwrap();
-                                });
-                            }
-                        });
-                    }
-                });
+                    //up forward right
+                    subdivisions.push(AABB::<N>::from_extents(
+                        min + Vector3::new(larger_half.x + adj, larger_half.y + adj, larger_half.z + adj),
+                        max
+                    ));
+                }
             }
+        }
 
-            if dimensions.z > one {
+        if dimensions.z > one {
 
-                let tx2 = tx.clone();
-                let max_elements = Arc::clone(&max_elements);
+            //down forward left
+            let sub_min = min + Vector3::new(zero, zero, larger_half.z + adj);
+            subdivisions.push(AABB::<N>::from_extents(
+                sub_min, 
+                Vector3::new(sub_min.x + larger_half.x, sub_min.y + larger_half.y, max.z)
+            ));
 
-                s.spawn(move |s| {
+            if dimensions.y > one {
 
-                    //down forward left
-                    let sub_min = min + Vector3::new(zero, zero, larger_half.z + adj);
-                    let sub_max = Vector3::new(
-                        sub_min.x + larger_half.x, sub_min.y + larger_half.y, max.z
-                    );
-        
-                    let downforwardleft = AABB::<N>::from_extents(
-                        sub_min, 
-                        sub_max
-                    );
-                    tx2.send(Octree::new(downforwardleft, *max_elements)).unwrap();
-
-                    if dimensions.y > one {
-                        s.spawn(move |_| {
-
-                            //up forward left
-                            let sub_min = min + Vector3::new(zero, larger_half.y + adj, larger_half.z + adj);
-                            let sub_max = Vector3::new(
-                                sub_min.x + larger_half.x, max.y, max.z
-                            );
-                            let upforwardleft = AABB::<N>::from_extents(
-                                sub_min,
-                                sub_max
-                            );
-                            tx2.send(Octree::new(upforwardleft, *max_elements)).unwrap();
-                        });
-                    }
-                });
+                //up forward left
+                let sub_min = min + Vector3::new(zero, larger_half.y + adj, larger_half.z + adj);
+                subdivisions.push(AABB::<N>::from_extents(
+                    sub_min,
+                    Vector3::new(sub_min.x + larger_half.x, max.y, max.z)
+                ));
             }
+        }
 
-            if dimensions.y > one {
+        if dimensions.y > one {
 
-                let tx3 = tx.clone();
-                let max_elements = Arc::clone(&max_elements);
-
-                s.spawn(move |s| {
-
-                    //up back left
-                    let sub_min = min + Vector3::new(zero, larger_half.y + adj, zero);
-                    let sub_max = Vector3::new(
-                        sub_min.x + larger_half.x, max.y, sub_min.z + larger_half.z
-                    );
-                    let upbackleft = AABB::<N>::from_extents(
-                        sub_min,
-                        sub_max
-                    );
-                    tx3.send(Octree::new(upbackleft, *max_elements)).unwrap();
-        
-                    if dimensions.x > one {
-
-                        s.spawn(move |_| {
-
-                            //up back right
-                            let sub_min = min + Vector3::new(larger_half.x + adj, larger_half.y + adj, zero);
-                            let sub_max = Vector3::new(
-                                max.x, max.y, sub_min.z + larger_half.z
-                            );
-                            let upbackright = AABB::<N>::from_extents(
-                                sub_min,
-                                sub_max
-                            );
-                            tx3.send(Octree::new(upbackright, *max_elements)).unwrap();
-                        });
-                    }
-                });
+            //up back left
+            let sub_min = min + Vector3::new(zero, larger_half.y + adj, zero);
+            subdivisions.push(AABB::<N>::from_extents(
+                sub_min,
+                Vector3::new(sub_min.x + larger_half.x, max.y, sub_min.z + larger_half.z)
+            ));
+
+            if dimensions.x > one {
+
+                //up back right
+                let sub_min = min + Vector3::new(larger_half.x + adj, larger_half.y + adj, zero);
+                subdivisions.push(AABB::<N>::from_extents(
+                    sub_min,
+                    Vector3::new(max.x, max.y, sub_min.z + larger_half.z)
+                ));
             }
-        });
+        }
+
+        subdivisions
+    }
+
+    fn subdivide(&mut self) -> Result<(), SubdivisionError<N>> {
+
+        let zero: N = NumCast::from(0).unwrap();
 
-        for received in rx {
-            self.children.push(received);
+        let max_elements = self.max_elements;
+        let execution = self.execution;
+
+        let subdivisions = self.get_subdivisions();
+
+        if self.is_parallel() {
+            self.children = subdivisions.into_par_iter()
+                .map(|aabb| Octree::with_execution(aabb, max_elements, execution))
+                .collect();
+        } else {
+            self.children = subdivisions.into_iter()
+                .map(|aabb| Octree::with_execution(aabb, max_elements, execution))
+                .collect();
         }
 
         self.paternity = Paternity::ProudParent;
 
+        let dimensions = self.aabb.dimensions.abs();
+
         let mut total_volume = zero;
         for child in &self.children {
             total_volume += child.aabb.dimensions.x * child.aabb.dimensions.y * child.aabb.dimensions.z;
@@ -301,8 +549,11 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
 
         let volume = dimensions.x * dimensions.y * dimensions.z;
 
+        // floats get some leeway for rounding, this is zero for integers
+        let tolerance: N = NumCast::from(0.0001).unwrap_or(zero);
+
         if cfg!(debug_assertions) {
-            if total_volume == volume {
+            if (total_volume - volume).abs() <= volume * tolerance {
                 Ok(())
             } else {
                 Err(
@@ -318,32 +569,40 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
 
     /// Removes the element which matches item exactly
     pub fn remove_item(&mut self, item: &T) {
-        if let Paternity::ChildFree = self.paternity {
-            if self.elements.is_empty() {
+
+        if let Some(template) = self.uniform {
+            let pt = item.get_point();
+
+            if !self.aabb.contains_point(pt) || template.with_point(pt) != *item {
                 return;
             }
-        }
-
-        let (tx, rx) = mpsc::channel::<T>();
 
-        self.elements.par_iter().for_each_with(tx, |tx, element| {
+            self.expand();
+        }
 
-            if element == item {
-                tx.send(*element).unwrap();
+        if let Paternity::ChildFree = self.paternity {
+            if self.elements.is_empty() {
+                return;
             }
-            
-        });
+        }
 
-        let to_remove: Vec<T> = rx.into_iter().collect();
-        self.elements = self.elements.clone().into_iter()
-            .filter(|element| !to_remove.contains(element))
-            .collect();
+        self.elements.retain(|element| element != item);
 
         if let Paternity::ProudParent = self.paternity {
 
-            self.children.par_iter_mut().for_each(|child|{
-                child.remove_item(item);
-            });
+            if self.is_parallel() {
+                self.children.par_iter_mut()
+                    .filter(|child| child.aabb.contains_point(item.get_point()))
+                    .for_each(|child| {
+                        child.remove_item(item);
+                    });
+            } else {
+                self.children.iter_mut()
+                    .filter(|child| child.aabb.contains_point(item.get_point()))
+                    .for_each(|child| {
+                        child.remove_item(item);
+                    });
+            }
 
         }
 
@@ -352,40 +611,97 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
     /// Removes all elements which fit inside range, silently avoiding positions that do not fit inside the octree
     pub fn remove_range(&mut self, range: AABB<N>) {
 
+        if !self.aabb.intersects_bounds(range) {
+            return;
+        }
+
+        if range.contains_aabb(self.aabb) {
+            self.clear();
+            return;
+        }
+
+        self.expand();
+
         if let Paternity::ChildFree = self.paternity {
             if self.elements.is_empty() {
                 return;
             }
         }
 
-        let (tx, rx) = mpsc::channel::<T>();
-
-        self.elements.par_iter().for_each_with(tx, |tx, element| {
+        self.elements.retain(|element| !range.contains_point(element.get_point()));
 
-            let pt = element.get_point();
+        if let Paternity::ProudParent = self.paternity {
 
-            if range.contains_point(pt) {
-                tx.send(*element).unwrap();
+            if self.is_parallel() {
+                self.children.par_iter_mut()
+                    .filter(|child| child.aabb.intersects_bounds(range))
+                    .for_each(|child| {
+                        child.remove_range(range);
+                    });
+            } else {
+                self.children.iter_mut()
+                    .filter(|child| child.aabb.intersects_bounds(range))
+                    .for_each(|child| {
+                        child.remove_range(range);
+                    });
             }
-            
-        });
 
-        let to_remove: Vec<T> = rx.into_iter().collect();
-        self.elements = self.elements.clone().into_iter()
-            .filter(|element| !to_remove.contains(element))
-            .collect();
+        }
 
-        if let Paternity::ProudParent = self.paternity {
+    }
+
+    /// Inserts element, replacing whatever element was already at its point
+    pub fn insert(&mut self, element: T) -> Result<(), InsertionError<N>>{  
+
+        self.grow_to_contain(element.get_point())?;
 
-            self.children.par_iter_mut().for_each(|child|{
-                child.remove_range(range);
-            });
+        //avoid expanding a uniform fill for an element it already holds
+        if self.query_point(element.get_point()) == Some(element) {
+            return Ok(());
+        }
 
+        //if element already exists at point, replace it
+        if let Some(existing) = self.get_mut(element.get_point()) {
+            *existing = element;
+        } else {
+            self.insert_element(element)?;
         }
 
+        self.grow_loose_bounds(element.get_point(), element.get_bounds());
+
+        Ok(())
     }
 
-    pub fn insert(&mut self, element: T) -> Result<(), InsertionError<N>>{  
+    /// Inserts element without replacing any element already at its point, for things that are allowed to share a position, like 
+    /// actors. Elements added this way should be removed with remove_item.
+    pub fn push(&mut self, element: T) -> Result<(), InsertionError<N>> {
+        self.grow_to_contain(element.get_point())?;
+        self.insert_element(element)?;
+        self.grow_loose_bounds(element.get_point(), element.get_bounds());
+
+        Ok(())
+    }
+
+    /// Grows the loose bounds of every node along the path that an element at point would be inserted through
+    fn grow_loose_bounds(&mut self, point: Vector3<N>, bounds: AABB<N>) {
+
+        if !self.aabb.contains_point(point) {
+            return;
+        }
+
+        self.loose_bounds = self.loose_bounds.union(bounds);
+
+        if let Some(child) = self.children.iter_mut().find(|child| child.aabb.contains_point(point)) {
+            child.grow_loose_bounds(point, bounds);
+        }
+    }
+
+    /// Returns the outer bounds of every element and child of this node, which is the same as its AABB unless elements have a size
+    pub fn get_loose_bounds(&self) -> AABB<N> {
+        self.loose_bounds
+    }
+
+    fn insert_element(&mut self, element: T) -> Result<(), InsertionError<N>>{  
 
         let pt = element.get_point();
 
@@ -396,13 +712,6 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
                 }
             )
         }
-
-        //if element already exists at point, replace it
-        self.elements.clone().into_iter().collect::<Vec<T>>().par_iter_mut().for_each_with(element, |el, element| {
-            if element.get_point() == pt {
-                *element = *el;
-            }
-        });
        
         match &self.paternity { //do first match because you still need to insert into children after subdividing, not either/or
 
@@ -413,6 +722,13 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
                 return Ok(());
             }
 
+            // nodes that can't be split any further hold on to whatever ends up in them
+            Paternity::ChildFree if self.get_subdivisions().len() < 2 => {
+                self.elements.push(element);
+
+                return Ok(());
+            }
+
             Paternity::ChildFree => { 
                 match self.subdivide() {
                     Ok(_) => {},
@@ -436,24 +752,12 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
                     }
                 );
 
-                let (tx, rx) = mpsc::channel::<Result<(), InsertionError<N>>>();
-
-                self.children.par_iter_mut().for_each_with(tx, |tx, child| {
-                    // println!("Inserting into child");                    
-                    match child.insert(element) {
-                        Ok(_) => tx.send(Ok(())),
-                        Err(err) => tx.send(Err(err))
-                    }.unwrap();               
-                });
-
-                let mut received = rx.into_iter();
-                if let Some(r) = received.find(|x| x.is_ok()) {
-                    return r;
-                } else if let Some(r) = received.find(|x| x.is_err()) {
-                    return r;
+                // children share their faces when using floats, so only the first child that contains the point gets the element, 
+                // the same one that get_mut and query_point would look in
+                match self.children.iter_mut().find(|child| child.aabb.contains_point(pt)) {
+                    Some(child) => child.insert_element(element),
+                    None => result
                 }
-
-                result
             }
 
             _ => Err(
@@ -464,45 +768,288 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
         }
     }
 
-    pub fn count(&self) -> usize{
-        let mut count: usize = self.elements.len();
-
-        match &self.paternity {
-            Paternity::ChildFree => count,
-            Paternity::ProudParent => {
-                for child in &self.children {
-                    count += child.count();
-                }
-                count
-            }
-        }
-    }
-
-    pub fn query_point(&self, point: Vector3<N>) -> Option<T> {
+    /// Returns a mutable reference to the element at point. Changing the point of the element through this reference would leave it in 
+    /// the wrong place in the octree, use update_point for that instead.
+    pub fn get_mut(&mut self, point: Vector3<N>) -> Option<&mut T> {
 
-        if !self.aabb.contains_point(point){
+        if !self.aabb.contains_point(point) {
             return None;
         }
 
-        let (tx, rx) = mpsc::channel::<T>();
-
-        self.elements.par_iter().for_each_with(tx, |tx, element| {
-            if element.get_point() == point  {
-                tx.send(*element).unwrap();
-            }
-        });
+        self.expand();
 
-        if let Some(result) = rx.into_iter().next() {
-            return Some(result);
+        if let Some(index) = self.elements.iter().position(|element| element.get_point() == point) {
+            return self.elements.get_mut(index);
         }
 
-        if let Paternity::ChildFree = self.paternity {
-            return None
+        match self.paternity {
+            Paternity::ChildFree => None,
+            Paternity::ProudParent => self.children.iter_mut()
+                .find(|child| child.aabb.contains_point(point))
+                .and_then(|child| child.get_mut(point))
         }
+    }
 
-        let (tx, rx) = mpsc::channel::<T>();
+    /// Replaces the element at point with the result of update, moving it to wherever it belongs if update changed its point. Any 
+    /// element already at the new point gets replaced. If the new point is out of bounds, the octree is left untouched.
+    pub fn update_point<F: FnOnce(T) -> T>(&mut self, point: Vector3<N>, update: F) -> Result<(), InsertionError<N>> {
 
-        self.children.par_iter().for_each_with(tx, |tx, child|  {
+        let original = match self.query_point(point) {
+            Some(original) => original,
+            None => return Err(
+                InsertionError {
+                    error_type: InsertionErrorType::NotFound(point)
+                }
+            )
+        };
+
+        let updated = update(original);
+        let updated_point = updated.get_point();
+
+        //checked before anything gets changed, so that a uniform fill doesn't get expanded for nothing
+        if !self.aabb.contains_point(updated_point) {
+            return Err(
+                InsertionError {
+                    error_type: InsertionErrorType::OutOfBounds(self.aabb)
+                }
+            )
+        }
+
+        if updated_point == point {
+            if let Some(existing) = self.get_mut(point) {
+                *existing = updated;
+            }
+
+            return Ok(());
+        }
+
+        self.remove_item(&original);
+        self.insert(updated)
+    }
+
+    /// Sets every point inside of range to a copy of element. Nodes that range covers entirely become a single uniform fill instead of
+    /// storing an element per point, so filling large areas stays cheap. Points outside of the octree, or outside of the limit of a 
+    /// growable one, are silently skipped. Only integer octrees can be filled.
+    pub fn fill(&mut self, range: AABB<N>, element: T) {
+
+        if !is_integer::<N>() {
+            return;
+        }
+
+        //growable octrees grow to cover as much of the range as their limit allows
+        self.grow_to_contain(range.get_min()).ok();
+        self.grow_to_contain(range.get_max()).ok();
+
+        if !self.aabb.intersects_bounds(range) {
+            return;
+        }
+
+        if range.contains_aabb(self.aabb) {
+            self.clear();
+            self.uniform = Some(element.with_point(self.aabb.get_min()));
+            return;
+        }
+
+        if let Some(template) = self.uniform {
+            if template == element.with_point(template.get_point()) {
+                return;
+            }
+        }
+
+        self.expand();
+
+        self.elements.retain(|existing| !range.contains_point(existing.get_point()));
+
+        if let Paternity::ChildFree = self.paternity {
+
+            let intersection = self.aabb.get_intersection(range);
+
+            if self.elements.len() + intersection.get_volume() <= self.max_elements {
+                self.elements.extend(Self::get_uniform_elements(element, intersection));
+                return;
+            }
+
+            match self.subdivide() {
+                Ok(_) => {},
+                Err(err) => {
+                    panic!("{:?}", err);
+                }
+            }
+        }
+
+        if self.is_parallel() {
+            self.children.par_iter_mut()
+                .filter(|child| child.aabb.intersects_bounds(range))
+                .for_each(|child| {
+                    child.fill(range, element);
+                });
+        } else {
+            self.children.iter_mut()
+                .filter(|child| child.aabb.intersects_bounds(range))
+                .for_each(|child| {
+                    child.fill(range, element);
+                });
+        }
+    }
+
+    /// Collapses every node whose whole AABB holds copies of the same element into a uniform fill, eg: after tiles were inserted one
+    /// by one. Only integer octrees can be compressed.
+    pub fn compress(&mut self) {
+
+        if !is_integer::<N>() || self.uniform.is_some() {
+            return;
+        }
+
+        if let Paternity::ProudParent = self.paternity {
+            for child in &mut self.children {
+                child.compress();
+            }
+        }
+
+        if self.count() != self.aabb.get_volume() {
+            return;
+        }
+
+        let mut elements = self.iter();
+
+        if let Some(first) = elements.next().map(Cow::into_owned) {
+            if elements.all(|element| *element == first.with_point(element.get_point())) {
+                self.clear();
+                self.uniform = Some(first);
+            }
+        }
+    }
+
+    /// Returns the element that every point in this octree holds a copy of, if it is a uniform fill
+    pub fn get_uniform(&self) -> Option<T> {
+        self.uniform
+    }
+
+    /// Turns a uniform fill back into regular nodes. Only the top level is expanded, any children it gets split into stay uniform 
+    /// until something needs to change inside of them.
+    fn expand(&mut self) {
+
+        if let Some(template) = self.uniform.take() {
+
+            if self.aabb.get_volume() <= self.max_elements {
+                self.elements = Self::get_uniform_elements(template, self.aabb);
+                return;
+            }
+
+            match self.subdivide() {
+                Ok(_) => {},
+                Err(err) => {
+                    panic!("{:?}", err);
+                }
+            }
+
+            for child in &mut self.children {
+                child.uniform = Some(template);
+            }
+        }
+    }
+
+    /// Removes everything from this node and its children
+    fn clear(&mut self) {
+        self.elements.clear();
+        self.children.clear();
+        self.paternity = Paternity::ChildFree;
+        self.uniform = None;
+    }
+
+    /// Returns a copy of template at every point inside of range
+    fn get_uniform_elements(template: T, range: AABB<N>) -> Vec<T> {
+        range.iter_points()
+            .map(|point| template.with_point(point))
+            .collect()
+    }
+
+    /// Iterates over every element in the octree without consuming it, in no particular order. Stored elements are handed out as 
+    /// references, the points of a uniform fill as owned copies, since they aren't stored anywhere that could be borrowed from.
+    pub fn iter(&self) -> OctreeRefIter<'_, N, T> {
+        self.iter_range(self.aabb)
+    }
+
+    /// Iterates over every element that fits inside range without consuming the octree, in no particular order
+    pub fn iter_range(&self, range: AABB<N>) -> OctreeRefIter<'_, N, T> {
+        let mut nodes = Vec::new();
+
+        if self.aabb.intersects_bounds(range) {
+            nodes.push(self);
+        }
+
+        OctreeRefIter {
+            range,
+            elements: [].iter(),
+            nodes,
+            uniform: None,
+            uniform_index: 0,
+            uniform_volume: 0,
+        }
+    }
+
+    pub fn count(&self) -> usize{
+
+        if self.uniform.is_some() {
+            return self.aabb.get_volume();
+        }
+
+        let mut count: usize = self.elements.len();
+
+        match &self.paternity {
+            Paternity::ChildFree => count,
+            Paternity::ProudParent => {
+                for child in &self.children {
+                    count += child.count();
+                }
+                count
+            }
+        }
+    }
+
+    pub fn query_point(&self, point: Vector3<N>) -> Option<T> {
+
+        if !self.aabb.contains_point(point){
+            return None;
+        }
+
+        if let Some(template) = self.uniform {
+            return Some(template.with_point(point));
+        }
+
+        if !self.is_parallel() {
+            if let Some(result) = self.elements.iter().find(|element| element.get_point() == point) {
+                return Some(*result);
+            }
+
+            if let Paternity::ChildFree = self.paternity {
+                return None
+            }
+
+            return self.children.iter()
+                .find(|child| child.aabb.contains_point(point))
+                .and_then(|child| child.query_point(point))
+        }
+
+        let (tx, rx) = mpsc::channel::<T>();
+
+        self.elements.par_iter().for_each_with(tx, |tx, element| {
+            if element.get_point() == point  {
+                tx.send(*element).unwrap();
+            }
+        });
+
+        if let Some(result) = rx.into_iter().next() {
+            return Some(result);
+        }
+
+        if let Paternity::ChildFree = self.paternity {
+            return None
+        }
+
+        let (tx, rx) = mpsc::channel::<T>();
+
+        self.children.par_iter().for_each_with(tx, |tx, child|  {
             if let Some(result) = child.query_point(point) {
                 tx.send(result).unwrap();
             }
@@ -519,12 +1066,28 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
             return elements_in_range
         }
 
+        if let Some(template) = self.uniform {
+            return Self::get_uniform_elements(template, self.aabb.get_intersection(range))
+        }
+
         if let Paternity::ChildFree = self.paternity {
             if self.elements.is_empty() {
                 return elements_in_range
             }
         }
 
+        if !self.is_parallel() {
+            elements_in_range.extend(self.elements.iter().filter(|element| range.contains_point(element.get_point())));
+
+            if let Paternity::ProudParent = self.paternity {
+                for child in &self.children {
+                    elements_in_range.append(&mut child.query_range(range));
+                }
+            }
+
+            return elements_in_range
+        }
+
         let (tx, rx) = mpsc::channel::<T>();
 
         self.elements.par_iter().for_each_with(tx, |tx, element| {
@@ -552,6 +1115,564 @@ impl<N: Sync + Send + Signed + Scalar + Num + NumCast + Ord + AddAssign + SubAss
 
         elements_in_range
     }
+
+    /// Returns all elements whose bounds intersect range, unlike query_range which only looks at their points
+    pub fn query_intersecting(&self, range: AABB<N>) -> Vec<T> {
+
+        let mut elements_in_range: Vec<T> = Vec::new();
+
+        if !self.loose_bounds.intersects_bounds(range) {
+            return elements_in_range
+        }
+
+        if let Some(template) = self.uniform {
+            return Self::get_uniform_elements(template, self.aabb.get_intersection(range))
+        }
+
+        elements_in_range.extend(self.elements.iter().filter(|element| range.intersects_bounds(element.get_bounds())));
+
+        if let Paternity::ProudParent = self.paternity {
+            for child in &self.children {
+                elements_in_range.append(&mut child.query_intersecting(range));
+            }
+        }
+
+        elements_in_range
+    }
+
+    /// Returns all elements that are within radius of center
+    pub fn query_sphere(&self, center: Vector3<N>, radius: N) -> Vec<T> {
+
+        let mut elements_in_range: Vec<T> = Vec::new();
+
+        let radius_squared = radius * radius;
+
+        if self.aabb.distance_squared_to_point(center) > radius_squared {
+            return elements_in_range
+        }
+
+        if let Some(template) = self.uniform {
+            let bounds = AABB::from_extents(
+                Vector3::new(center.x - radius, center.y - radius, center.z - radius),
+                Vector3::new(center.x + radius, center.y + radius, center.z + radius)
+            );
+
+            return Self::get_uniform_elements(template, self.aabb.get_intersection(bounds)).into_iter()
+                .filter(|element| distance_squared(center, element.get_point()) <= radius_squared)
+                .collect()
+        }
+
+        if let Paternity::ChildFree = self.paternity {
+            if self.elements.is_empty() {
+                return elements_in_range
+            }
+        }
+
+        if !self.is_parallel() {
+            elements_in_range.extend(self.elements.iter().filter(|element| distance_squared(center, element.get_point()) <= radius_squared));
+
+            if let Paternity::ProudParent = self.paternity {
+                for child in &self.children {
+                    elements_in_range.append(&mut child.query_sphere(center, radius));
+                }
+            }
+
+            return elements_in_range
+        }
+
+        let (tx, rx) = mpsc::channel::<T>();
+
+        self.elements.par_iter().for_each_with(tx, |tx, element| {
+            if distance_squared(center, element.get_point()) <= radius_squared {
+                tx.send(*element).unwrap();
+            }
+        });
+
+        elements_in_range.extend(rx);
+
+        if let Paternity::ChildFree = self.paternity {
+            return elements_in_range
+        }
+
+        let (tx, rx) = mpsc::channel::<Vec<T>>();
+
+        self.children.par_iter().for_each_with(tx, |tx, child| {
+            tx.send(child.query_sphere(center, radius)).unwrap();
+        });
+
+        for mut received in rx {
+            elements_in_range.append(&mut received)
+        }
+
+        elements_in_range
+    }
+
+    /// Returns the element closest to point
+    pub fn nearest(&self, point: Vector3<N>) -> Option<T> {
+        self.k_nearest_by(point, 1, |_| true).into_iter().next()
+    }
+
+    /// Returns the element closest to point out of the elements that satisfy predicate, eg: the closest tile of a certain type
+    pub fn nearest_by<F: Fn(&T) -> bool>(&self, point: Vector3<N>, predicate: F) -> Option<T> {
+        self.k_nearest_by(point, 1, predicate).into_iter().next()
+    }
+
+    /// Returns up to k elements ordered from closest to furthest from point
+    pub fn k_nearest(&self, point: Vector3<N>, k: usize) -> Vec<T> {
+        self.k_nearest_by(point, k, |_| true)
+    }
+
+    /// Returns up to k elements that satisfy predicate, ordered from closest to furthest from point
+    pub fn k_nearest_by<F: Fn(&T) -> bool>(&self, point: Vector3<N>, k: usize, predicate: F) -> Vec<T> {
+        
+        let mut found: Vec<(N, T)> = Vec::with_capacity(k);
+
+        if k > 0 {
+            self.k_nearest_helper(point, k, &predicate, &mut found);
+        }
+
+        found.into_iter().map(|(_, element)| element).collect()
+    }
+
+    /// Keeps found sorted by distance and no longer than k. Children are visited closest first, and any child whose AABB is further away
+    /// than the furthest of k found elements gets skipped, along with every child after it.
+    fn k_nearest_helper<F: Fn(&T) -> bool>(&self, point: Vector3<N>, k: usize, predicate: &F, found: &mut Vec<(N, T)>) {
+
+        if let Some(template) = self.uniform {
+            self.k_nearest_uniform(template, point, k, predicate, found);
+            return
+        }
+
+        for element in &self.elements {
+            if predicate(element) {
+                Self::insert_nearest(found, k, distance_squared(point, element.get_point()), *element);
+            }
+        }
+
+        if let Paternity::ChildFree = self.paternity {
+            return
+        }
+
+        let mut children = self.children.iter()
+            .map(|child| (child.aabb.distance_squared_to_point(point), child))
+            .collect::<Vec<(N, &Octree<N, T>)>>();
+
+        children.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        for (distance, child) in children {
+
+            if found.len() == k && found.last().map_or(false, |(furthest, _)| distance > *furthest) {
+                break;
+            }
+
+            child.k_nearest_helper(point, k, predicate, found);
+        }
+    }
+
+    /// Walks the points of a uniform fill in shells around the point of the fill closest to point, so that only the shells that could
+    /// still hold one of the k nearest get visited. Every point of a shell is at least its radius away from point, which is what lets 
+    /// the walk stop early.
+    fn k_nearest_uniform<F: Fn(&T) -> bool>(&self, template: T, point: Vector3<N>, k: usize, predicate: &F, found: &mut Vec<(N, T)>) {
+
+        let min = self.aabb.get_min();
+        let max = self.aabb.get_max();
+
+        let lower: Vector3<i64> = Vector3::new(NumCast::from(min.x).unwrap(), NumCast::from(min.y).unwrap(), NumCast::from(min.z).unwrap());
+        let upper: Vector3<i64> = Vector3::new(NumCast::from(max.x).unwrap(), NumCast::from(max.y).unwrap(), NumCast::from(max.z).unwrap());
+
+        let closest: Vector3<i64> = Vector3::new(
+            NumCast::from(partial_min(partial_max(point.x, min.x), max.x)).unwrap(),
+            NumCast::from(partial_min(partial_max(point.y, min.y), max.y)).unwrap(),
+            NumCast::from(partial_min(partial_max(point.z, min.z), max.z)).unwrap(),
+        );
+
+        let furthest_radius = (closest - lower).max().max((upper - closest).max());
+
+        for radius in 0..=furthest_radius {
+
+            let radius_squared: N = NumCast::from(radius * radius).unwrap();
+
+            if found.len() == k && found.last().map_or(false, |(furthest, _)| radius_squared > *furthest) {
+                break;
+            }
+
+            let from = (closest - Vector3::repeat(radius)).sup(&lower);
+            let to = (closest + Vector3::repeat(radius)).inf(&upper);
+
+            for z in from.z..=to.z {
+                for y in from.y..=to.y {
+
+                    //rows on the faces of the shell are walked in full, the rest of the rows only cross the shell at both of their ends
+                    let xs = if (z - closest.z).abs() == radius || (y - closest.y).abs() == radius {
+                        (from.x..=to.x).collect::<Vec<i64>>()
+                    } else {
+                        vec![closest.x - radius, closest.x + radius].into_iter().filter(|x| *x >= from.x && *x <= to.x).collect()
+                    };
+
+                    for x in xs {
+                        let element = template.with_point(Vector3::new(NumCast::from(x).unwrap(), NumCast::from(y).unwrap(), NumCast::from(z).unwrap()));
+
+                        if predicate(&element) {
+                            Self::insert_nearest(found, k, distance_squared(point, element.get_point()), element);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Inserts element into found if it is closer than the furthest of the k elements found so far, keeping found sorted by distance
+    fn insert_nearest(found: &mut Vec<(N, T)>, k: usize, distance: N, element: T) {
+        if found.len() < k || found.last().map_or(false, |(furthest, _)| distance < *furthest) {
+            let index = found.iter().position(|(found_distance, _)| distance < *found_distance).unwrap_or_else(|| found.len());
+
+            found.insert(index, (distance, element));
+            found.truncate(k);
+        }
+    }
+
+    /// Returns every element whose cell is crossed by the ray, paired with the distance at which the ray enters it, ordered from 
+    /// nearest to furthest. Elements further away than max_distance are left out.
+    pub fn query_ray(&self, ray: Ray, max_distance: Option<f32>) -> Vec<(f32, T)> {
+        let mut hits = self.query_ray_unordered(ray, max_distance);
+
+        hits.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        hits
+    }
+
+    fn query_ray_unordered(&self, ray: Ray, max_distance: Option<f32>) -> Vec<(f32, T)> {
+
+        let mut hits: Vec<(f32, T)> = Vec::new();
+
+        match self.loose_bounds.get_ray_intersection(ray) {
+            Some((near, _)) if max_distance.map_or(true, |max| near <= max) => {},
+            _ => return hits
+        }
+
+        if let Some(template) = self.uniform {
+            return Self::get_uniform_ray_hits(template, self.aabb, ray, max_distance)
+        }
+
+        if let Paternity::ChildFree = self.paternity {
+            if self.elements.is_empty() {
+                return hits
+            }
+        }
+
+        if !self.is_parallel() {
+            hits.extend(self.elements.iter()
+                .filter_map(|element| Self::ray_distance_to_element(ray, element, max_distance).map(|distance| (distance, *element)))
+            );
+
+            if let Paternity::ProudParent = self.paternity {
+                for child in &self.children {
+                    hits.append(&mut child.query_ray_unordered(ray, max_distance));
+                }
+            }
+
+            return hits
+        }
+
+        let (tx, rx) = mpsc::channel::<(f32, T)>();
+
+        self.elements.par_iter().for_each_with(tx, |tx, element| {
+            if let Some(distance) = Self::ray_distance_to_element(ray, element, max_distance) {
+                tx.send((distance, *element)).unwrap();
+            }
+        });
+
+        hits.extend(rx);
+
+        if let Paternity::ChildFree = self.paternity {
+            return hits
+        }
+
+        let (tx, rx) = mpsc::channel::<Vec<(f32, T)>>();
+
+        self.children.par_iter().for_each_with(tx, |tx, child| {
+            tx.send(child.query_ray_unordered(ray, max_distance)).unwrap();
+        });
+
+        for mut received in rx {
+            hits.append(&mut received)
+        }
+
+        hits
+    }
+
+    /// Returns the nearest element whose cell is crossed by the ray, paired with the distance at which the ray enters it. Children are 
+    /// visited in the order the ray passes through them, and the search stops as soon as the next child starts further away than the 
+    /// closest hit found so far.
+    pub fn query_ray_first(&self, ray: Ray, max_distance: Option<f32>) -> Option<(f32, T)> {
+
+        match self.loose_bounds.get_ray_intersection(ray) {
+            Some((near, _)) if max_distance.map_or(true, |max| near <= max) => {},
+            _ => return None
+        }
+
+        if let Some(template) = self.uniform {
+            return Self::get_uniform_ray_hits(template, self.aabb, ray, max_distance).into_iter()
+                .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        }
+
+        let mut closest: Option<(f32, T)> = None;
+
+        for element in &self.elements {
+            if let Some(distance) = Self::ray_distance_to_element(ray, element, max_distance) {
+                if closest.map_or(true, |(closest_distance, _)| distance < closest_distance) {
+                    closest = Some((distance, *element));
+                }
+            }
+        }
+
+        if let Paternity::ChildFree = self.paternity {
+            return closest
+        }
+
+        let mut crossed_children = self.children.iter()
+            .filter_map(|child| child.loose_bounds.get_ray_intersection(ray).map(|(near, _)| (near, child)))
+            .collect::<Vec<(f32, &Octree<N, T>)>>();
+
+        crossed_children.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        for (near, child) in crossed_children {
+
+            if let Some((closest_distance, _)) = closest {
+                if near > closest_distance {
+                    break;
+                }
+            }
+
+            if let Some((distance, element)) = child.query_ray_first(ray, max_distance) {
+                if closest.map_or(true, |(closest_distance, _)| distance < closest_distance) {
+                    closest = Some((distance, element));
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Returns the points of a uniform fill over range that the ray crosses within max_distance, paired with the distance at which the 
+    /// ray enters them. Only the cells along the ray get looked at: the ray is clipped to range, then walked one layer at a time along 
+    /// the axis it moves furthest on, which keeps it within a cell or two of each other axis per layer.
+    fn get_uniform_ray_hits(template: T, range: AABB<N>, ray: Ray, max_distance: Option<f32>) -> Vec<(f32, T)> {
+
+        let mut hits: Vec<(f32, T)> = Vec::new();
+
+        let (near, far) = match range.get_ray_intersection(ray) {
+            Some((near, far)) => (near, max_distance.map_or(far, |max| far.min(max))),
+            None => return hits
+        };
+
+        if near > far {
+            return hits
+        }
+
+        //points that the ray only grazes still count as crossed, so the cells on either side of a boundary are both looked at
+        let slack = 1e-4;
+
+        let min = range.get_min();
+        let max = range.get_max();
+
+        let lower: Vector3<i64> = Vector3::new(NumCast::from(min.x).unwrap(), NumCast::from(min.y).unwrap(), NumCast::from(min.z).unwrap());
+        let upper: Vector3<i64> = Vector3::new(NumCast::from(max.x).unwrap(), NumCast::from(max.y).unwrap(), NumCast::from(max.z).unwrap());
+
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+
+        let axis = direction.iamax();
+        let others = [(axis + 1) % 3, (axis + 2) % 3];
+
+        let get_cells = |from: f32, to: f32, index: usize| {
+            (partial_max((partial_min(from, to) - slack).floor() as i64, lower[index]), partial_min((partial_max(from, to) + slack).floor() as i64, upper[index]))
+        };
+
+        let start = ray.get_point(near);
+        let end = ray.get_point(far);
+
+        let (first_layer, last_layer) = get_cells(start[axis], end[axis], axis);
+
+        for layer in first_layer..=last_layer {
+
+            let (layer_near, layer_far) = if direction[axis].abs() < std::f32::EPSILON {
+                (near, far)
+            } else {
+                let entry = (layer as f32 - origin[axis]) / direction[axis];
+                let exit = (layer as f32 + 1. - origin[axis]) / direction[axis];
+
+                (partial_max(near, entry.min(exit)), partial_min(far, entry.max(exit)))
+            };
+
+            if layer_near > layer_far + slack {
+                continue;
+            }
+
+            let layer_start = ray.get_point(layer_near);
+            let layer_end = ray.get_point(layer_far);
+
+            let (first_a, last_a) = get_cells(layer_start[others[0]], layer_end[others[0]], others[0]);
+            let (first_b, last_b) = get_cells(layer_start[others[1]], layer_end[others[1]], others[1]);
+
+            for a in first_a..=last_a {
+                for b in first_b..=last_b {
+                    let mut point = Vector3::zeros();
+
+                    point[axis] = NumCast::from(layer).unwrap();
+                    point[others[0]] = NumCast::from(a).unwrap();
+                    point[others[1]] = NumCast::from(b).unwrap();
+
+                    let element = template.with_point(point);
+
+                    if let Some(distance) = Self::ray_distance_to_element(ray, &element, max_distance) {
+                        hits.push((distance, element));
+                    }
+                }
+            }
+        }
+
+        hits
+    }
+
+    /// Returns the distance at which the ray enters the bounds of the element, if it does so within max_distance
+    fn ray_distance_to_element(ray: Ray, element: &T, max_distance: Option<f32>) -> Option<f32> {
+        element.get_bounds().get_ray_intersection(ray)
+            .map(|(near, _)| near)
+            .filter(|near| max_distance.map_or(true, |max| *near <= max))
+    }
+
+    /// Returns what would have to be added, removed and changed in this octree for it to contain the same elements as other. Subtrees 
+    /// that cover the same space and hold identical contents in both octrees are skipped over.
+    pub fn diff(&self, other: &Octree<N, T>) -> OctreeDiff<T> where N: Hash + Eq {
+
+        let mut from: HashMap<Vector3<N>, T> = HashMap::new();
+        let mut to: HashMap<Vector3<N>, T> = HashMap::new();
+
+        Octree::collect_differences(self, other, &mut from, &mut to);
+
+        let mut diff = OctreeDiff::default();
+
+        for (point, element) in &from {
+            match to.get(point) {
+                None => diff.removed.push(*element),
+                Some(other_element) if other_element != element => diff.changed.push((*element, *other_element)),
+                _ => {}
+            }
+        }
+
+        for (point, element) in &to {
+            if !from.contains_key(point) {
+                diff.added.push(*element);
+            }
+        }
+
+        diff
+    }
+
+    /// Every point is only ever stored once per octree, so any element that isn't inside of an identical subtree gets collected in 
+    /// either from or to, regardless of the depth it ended up being stored at
+    fn collect_differences(a: &Octree<N, T>, b: &Octree<N, T>, from: &mut HashMap<Vector3<N>, T>, to: &mut HashMap<Vector3<N>, T>) where N: Hash + Eq {
+
+        if a == b {
+            return;
+        }
+
+        if a.aabb == b.aabb && a.children.len() == b.children.len() && a.uniform.is_none() && b.uniform.is_none() {
+
+            from.extend(a.elements.iter().map(|element| (element.get_point(), *element)));
+            to.extend(b.elements.iter().map(|element| (element.get_point(), *element)));
+
+            // subdivisions are deterministic, so children at the same index cover the same space
+            for (a_child, b_child) in a.children.iter().zip(b.children.iter()) {
+                Octree::collect_differences(a_child, b_child, from, to);
+            }
+
+            return;
+        }
+
+        from.extend(a.iter().map(|element| (element.get_point(), *element)));
+        to.extend(b.iter().map(|element| (element.get_point(), *element)));
+    }
+
+    /// Applies the changes in diff, as returned by diff(), to this octree
+    pub fn apply(&mut self, diff: &OctreeDiff<T>) -> Result<(), InsertionError<N>> {
+
+        for element in &diff.removed {
+            self.remove_item(element);
+        }
+
+        for (_, element) in &diff.changed {
+            self.insert(*element)?;
+        }
+
+        for element in &diff.added {
+            self.insert(*element)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The elements that differ between two octrees, see Octree::diff
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct OctreeDiff<T> {
+    added: Vec<T>,
+    removed: Vec<T>,
+    /// Elements whose point exists in both octrees, as (from, to)
+    changed: Vec<(T, T)>,
+}
+
+impl<T> Default for OctreeDiff<T> {
+    fn default() -> Self {
+        OctreeDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy> OctreeDiff<T> {
+
+    pub fn get_added(&self) -> &Vec<T> {
+        &self.added
+    }
+
+    pub fn get_removed(&self) -> &Vec<T> {
+        &self.removed
+    }
+
+    pub fn get_changed(&self) -> &Vec<(T, T)> {
+        &self.changed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the diff that undoes this one
+    pub fn invert(&self) -> Self {
+        OctreeDiff {
+            added: self.removed.clone(),
+            removed: self.added.clone(),
+            changed: self.changed.iter().map(|(from, to)| (*to, *from)).collect(),
+        }
+    }
+}
+
+/// Hacky way of checking if N is an integer, the same way AABB does it
+fn is_integer<N: Num + NumCast>() -> bool {
+    let one: N = NumCast::from(1).unwrap();
+    let two: N = NumCast::from(2).unwrap();
+
+    one/two == N::zero()
+}
+
+fn distance_squared<N: Scalar + Num + Copy>(a: Vector3<N>, b: Vector3<N>) -> N {
+    let diff = Vector3::new(a.x - b.x, a.y - b.y, a.z - b.z);
+
+    diff.x * diff.x + diff.y * diff.y + diff.z * diff.z
 }
 
 #[derive(Clone, Debug)]
@@ -581,6 +1702,7 @@ pub enum InsertionErrorType<N: Scalar> {
     Empty,
     BlockFull(AABB<N>),
     OutOfBounds(AABB<N>),
+    NotFound(Vector3<N>),
 }
 
 #[derive(Debug, Clone)]
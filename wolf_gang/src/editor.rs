@@ -1,4 +1,5 @@
 use gdnative::prelude::*;
+use gdnative::api::File;
 
 use legion::*;
 use crate::{
@@ -24,6 +25,23 @@ use crate::{
 
 type AABB = crate::geometry::aabb::AABB<i32>;
 
+/// Reads how .vox palette indices map to tiles from config, going by the default mapping if there isn't one
+fn get_vox_tile_mapping() -> level_map::vox::VoxTileMapping {
+    let file = File::new();
+
+    if file.open(level_map::vox::VOX_TILE_MAPPING_PATH, File::READ).is_ok() {
+        let file_string = file.get_as_text().to_string();
+        file.close();
+
+        match level_map::vox::VoxTileMapping::from_ron(file_string.as_str()) {
+            Ok(mapping) => return mapping,
+            Err(err) => godot_print!("Failed to deserialize {}: {:?}", level_map::vox::VOX_TILE_MAPPING_PATH, err)
+        }
+    }
+
+    level_map::vox::VoxTileMapping::default()
+}
+
 pub struct Editor {
     game_state: GameState,
     camera: Option<Ref<Node>>,
@@ -36,9 +54,17 @@ impl GameStateTraits for Editor {
         
         self.camera = Some(camera::initialize_camera(world));
         resources.insert(self.map);
+        resources.insert(level_map::MapChunkIndex::default());
+        resources.insert(level_map::flood::FloodSettings::default());
+        resources.insert(level_map::symmetry::Symmetry::default());
+        resources.insert(level_map::generator::GeneratorSettings::default());
+        resources.insert(level_map::heightmap::HeightmapSettings::default());
+        resources.insert(get_vox_tile_mapping());
         resources.insert(level_map::document::Document::default());
         resources.insert(PaletteSelection(0));
+        resources.insert(ShapeSelection(level_map::shape::TileShape::Full));
         resources.insert(SelectedTool(selection_box::ToolBoxType::TerrainToolBox));
+        resources.insert(actor::ActorIndex::default());
 
         // if let Some(actor_definitions) = ActorDefinitions::from_config("res://config/actors.ron") {
             // resources.insert(actor_definitions);
@@ -47,11 +73,17 @@ impl GameStateTraits for Editor {
 
     fn free(&mut self, world: &mut World, resources: &mut Resources) {
         resources.remove::<level_map::document::Document>();
+        resources.remove::<actor::ActorIndex>();
 
         if let Some(camera) = self.camera {
             node::free(world, camera);
         }
-        self.map.free(world);
+        self.map.free(world, resources);
+        resources.remove::<level_map::MapChunkIndex>();
+        resources.remove::<level_map::symmetry::Symmetry>();
+        resources.remove::<level_map::generator::GeneratorSettings>();
+        resources.remove::<level_map::heightmap::HeightmapSettings>();
+        resources.remove::<level_map::vox::VoxTileMapping>();
 
         actor::free_all(world);
     }
@@ -122,7 +154,20 @@ impl GameStateTraits for Editor {
 
     }
 
-    fn on_client_connected(&self, connection_id: u32, world: &mut World, _: &mut Resources) {
+    fn on_client_connected(&self, connection_id: u32, world: &mut World, resources: &mut Resources) {
+
+        //the map configuration has to arrive before any of the map data so that it gets chunked correctly
+        if let Some(map) = resources.get::<level_map::Map>().map(|map| *map) {
+            world.push(
+                (
+                    ServerMessageSender {
+                        client_id: connection_id,
+                        data_type: DataType::MapConfig(map),
+                        message_type: MessageType::Ordered,
+                    },
+                )
+            );
+        }
 
         //Get all of the selection boxes to send them to the new client
         let mut query = <(Entity, Read<selection_box::SelectionBox>, Read<ClientID>, Read<level_map::CoordPos>)>::query();
@@ -267,6 +312,21 @@ impl PaletteSelection {
     }
 }
 
+/// The shape that new tiles get placed with
+#[derive(Copy, Clone)]
+pub struct ShapeSelection(level_map::shape::TileShape);
+
+impl ShapeSelection {
+
+    pub fn new(shape: level_map::shape::TileShape) -> ShapeSelection {
+        ShapeSelection(shape)
+    }
+
+    pub fn val(&self) -> level_map::shape::TileShape {
+        self.0
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct ActorPaletteSelection(i64);
 
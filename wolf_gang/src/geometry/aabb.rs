@@ -4,13 +4,15 @@ use num::{
     Num, NumCast,Signed
 };
 
+use super::ray::Ray;
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
 pub struct AABB<F: Scalar> {
     pub center: Vector3<F>,
     pub dimensions: Vector3<F>,
 }
 
-impl<F: Signed + Scalar + Num + NumCast + Ord + Copy + Clone> AABB<F> {
+impl<F: Signed + Scalar + Num + NumCast + PartialOrd + Copy + Clone> AABB<F> {
     pub fn new(center: Vector3<F>, dimensions: Vector3<F>) -> Self {
         Self {
             center,
@@ -124,13 +126,13 @@ impl<F: Signed + Scalar + Num + NumCast + Ord + Copy + Clone> AABB<F> {
             let mut max_z: F = NumCast::from(corner.z).unwrap();
 
             for corner in rotated_corners_iter {
-                min_x = std::cmp::min(min_x, NumCast::from(corner.x).unwrap());
-                min_y = std::cmp::min(min_y, NumCast::from(corner.y).unwrap());
-                min_z = std::cmp::min(min_z, NumCast::from(corner.z).unwrap());
+                min_x = partial_min(min_x, NumCast::from(corner.x).unwrap());
+                min_y = partial_min(min_y, NumCast::from(corner.y).unwrap());
+                min_z = partial_min(min_z, NumCast::from(corner.z).unwrap());
     
-                max_x = std::cmp::max(max_x, NumCast::from(corner.x).unwrap());
-                max_y = std::cmp::max(max_y, NumCast::from(corner.y).unwrap());
-                max_z = std::cmp::max(max_z, NumCast::from(corner.z).unwrap());
+                max_x = partial_max(max_x, NumCast::from(corner.x).unwrap());
+                max_y = partial_max(max_y, NumCast::from(corner.y).unwrap());
+                max_z = partial_max(max_z, NumCast::from(corner.z).unwrap());
             }
 
             let min = Vector3::<F>::new(
@@ -158,15 +160,15 @@ impl<F: Signed + Scalar + Num + NumCast + Ord + Copy + Clone> AABB<F> {
         let other_max = other.get_max();
 
         let intersect_min = Vector3::<F>::new(
-            std::cmp::max(min.x, other_min.x),
-            std::cmp::max(min.y, other_min.y),
-            std::cmp::max(min.z, other_min.z)
+            partial_max(min.x, other_min.x),
+            partial_max(min.y, other_min.y),
+            partial_max(min.z, other_min.z)
         );
 
         let intersect_max = Vector3::<F>::new(
-            std::cmp::min(max.x, other_max.x),
-            std::cmp::min(max.y, other_max.y),
-            std::cmp::min(max.z, other_max.z)
+            partial_min(max.x, other_max.x),
+            partial_min(max.y, other_max.y),
+            partial_min(max.z, other_max.z)
         );
 
         AABB::from_extents(intersect_min, intersect_max)
@@ -192,4 +194,230 @@ impl<F: Signed + Scalar + Num + NumCast + Ord + Copy + Clone> AABB<F> {
         && point.y >= min.y && point.y <= max.y
         && point.z >= min.z && point.z <= max.z
     }
-}
\ No newline at end of file
+
+    /// Returns true if every point of other is also within self
+    pub fn contains_aabb(&self, other: AABB<F>) -> bool {
+        self.contains_point(other.get_min()) && self.contains_point(other.get_max())
+    }
+
+    /// Returns the smallest box that contains both self and other
+    pub fn union(&self, other: AABB<F>) -> AABB<F> {
+        let min = self.get_min();
+        let max = self.get_max();
+
+        let other_min = other.get_min();
+        let other_max = other.get_max();
+
+        AABB::from_extents(
+            Vector3::new(
+                partial_min(min.x, other_min.x),
+                partial_min(min.y, other_min.y),
+                partial_min(min.z, other_min.z)
+            ),
+            Vector3::new(
+                partial_max(max.x, other_max.x),
+                partial_max(max.y, other_max.y),
+                partial_max(max.z, other_max.z)
+            )
+        )
+    }
+
+    /// Returns the parts of self that aren't covered by other, as up to six disjoint boxes. The boxes are sliced off along x first,
+    /// then y, then z, so the ones on the x axis span the full height and depth of self.
+    pub fn subtract(&self, other: AABB<F>) -> Vec<AABB<F>> {
+        if !self.intersects_bounds(other) {
+            return vec![*self]
+        }
+
+        let two: F = NumCast::from(2).unwrap();
+        let one: F = NumCast::from(1).unwrap();
+        let zero: F = NumCast::from(0).unwrap();
+
+        //hacky way to check if F is int, since max is inclusive
+        let is_int = one/two == zero;
+        let step = if is_int { one } else { zero };
+
+        let is_valid = |lower: F, upper: F| if is_int { lower <= upper } else { lower < upper };
+
+        let mut min = self.get_min();
+        let mut max = self.get_max();
+
+        let intersection = self.get_intersection(other);
+        let intersect_min = intersection.get_min();
+        let intersect_max = intersection.get_max();
+
+        let mut result = Vec::new();
+
+        for i in 0..3 {
+            if is_valid(min[i], intersect_min[i] - step) {
+                let mut slice_max = max;
+                slice_max[i] = intersect_min[i] - step;
+                result.push(AABB::from_extents(min, slice_max));
+            }
+
+            if is_valid(intersect_max[i] + step, max[i]) {
+                let mut slice_min = min;
+                slice_min[i] = intersect_max[i] + step;
+                result.push(AABB::from_extents(slice_min, max));
+            }
+
+            //what remains to be sliced is only the part that lines up with the intersection on this axis
+            min[i] = intersect_min[i];
+            max[i] = intersect_max[i];
+        }
+
+        result
+    }
+
+    /// Returns the number of integer points within the box
+    pub fn get_volume(&self) -> usize {
+        let dimensions = self.dimensions.abs();
+
+        NumCast::from(dimensions.x * dimensions.y * dimensions.z).unwrap_or(0)
+    }
+
+    /// Returns the point at index when stepping through the integer points of the box along x, then y, then z
+    pub fn get_point_at_index(&self, index: usize) -> Vector3<F> {
+        let dimensions = self.dimensions.abs();
+        let min = self.get_min();
+
+        let x: usize = NumCast::from(dimensions.x).unwrap();
+        let y: usize = NumCast::from(dimensions.y).unwrap();
+
+        Vector3::new(
+            min.x + NumCast::from(index % x).unwrap(),
+            min.y + NumCast::from((index / x) % y).unwrap(),
+            min.z + NumCast::from(index / (x * y)).unwrap(),
+        )
+    }
+
+    /// Iterates over every integer point within the box, x first, then y, then z. Only meaningful for integer boxes.
+    pub fn iter_points(&self) -> AABBPointIter<F> {
+        AABBPointIter {
+            aabb: *self,
+            index: 0,
+            volume: self.get_volume(),
+        }
+    }
+
+    /// Returns the squared distance from point to the closest point within the box, which is zero if the box contains point
+    pub fn distance_squared_to_point(&self, point: Vector3<F>) -> F {
+        let min = self.get_min();
+        let max = self.get_max();
+
+        let closest = Vector3::new(
+            partial_min(partial_max(point.x, min.x), max.x),
+            partial_min(partial_max(point.y, min.y), max.y),
+            partial_min(partial_max(point.z, min.z), max.z),
+        );
+
+        let diff = Vector3::new(point.x - closest.x, point.y - closest.y, point.z - closest.z);
+
+        diff.x * diff.x + diff.y * diff.y + diff.z * diff.z
+    }
+
+    /// Returns the distances along the ray at which it enters and exits the box, or None if the ray misses it. If the origin is inside
+    /// the box, the entry distance is 0. Integer boxes are treated as covering the whole cell of each of their points, so a box with
+    /// both extents at (0,0,0) spans from (0,0,0) to (1,1,1), the same way tiles are drawn.
+    pub fn get_ray_intersection(&self, ray: Ray) -> Option<(f32, f32)> {
+        let two: F = NumCast::from(2).unwrap();
+        let one: F = NumCast::from(1).unwrap();
+        let zero: F = NumCast::from(0).unwrap();
+
+        let min = self.get_min();
+        let max = self.get_max();
+
+        //hacky way to check if F is int, since max is inclusive
+        let cell_size: f32 = if one/two == zero {
+            1.
+        } else {
+            0.
+        };
+
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+
+        let mut near = std::f32::NEG_INFINITY;
+        let mut far = std::f32::INFINITY;
+
+        for i in 0..3 {
+            let lower: f32 = NumCast::from(min[i]).unwrap();
+            let upper: f32 = <f32 as NumCast>::from(max[i]).unwrap() + cell_size;
+
+            if direction[i].abs() < std::f32::EPSILON {
+                //the ray runs parallel to these two faces, so it can only hit if it starts between them
+                if origin[i] < lower || origin[i] > upper {
+                    return None
+                }
+            } else {
+                let mut entry = (lower - origin[i]) / direction[i];
+                let mut exit = (upper - origin[i]) / direction[i];
+
+                if entry > exit {
+                    std::mem::swap(&mut entry, &mut exit);
+                }
+
+                near = near.max(entry);
+                far = far.min(exit);
+
+                if near > far {
+                    return None
+                }
+            }
+        }
+
+        //the box is entirely behind the ray
+        if far < 0. {
+            return None
+        }
+
+        Some((near.max(0.), far))
+    }
+}
+
+/// Iterator over the integer points of an AABB, created by AABB::iter_points
+pub struct AABBPointIter<F: Scalar> {
+    aabb: AABB<F>,
+    index: usize,
+    volume: usize,
+}
+
+impl<F: Signed + Scalar + Num + NumCast + PartialOrd + Copy + Clone> Iterator for AABBPointIter<F> {
+    type Item = Vector3<F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.volume {
+            return None
+        }
+
+        let point = self.aabb.get_point_at_index(self.index);
+        self.index += 1;
+
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.volume - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<F: Signed + Scalar + Num + NumCast + PartialOrd + Copy + Clone> ExactSizeIterator for AABBPointIter<F> {}
+
+/// Same as std::cmp::min, but also works for floats. If the values can't be compared, a is returned.
+pub fn partial_min<F: PartialOrd>(a: F, b: F) -> F {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Same as std::cmp::max, but also works for floats. If the values can't be compared, a is returned.
+pub fn partial_max<F: PartialOrd>(a: F, b: F) -> F {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Serialize, Deserialize};
+use nalgebra::Vector3;
+
+/// A half-line used for picking and line of sight checks. It lives in the same coordinate space as whatever it is tested against,
+/// so rays coming from the camera need to be converted into map coords before being cast through the map's octrees.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct Ray {
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Creates a new ray, normalizing direction so that distances along the ray are in the same units as the space it is cast through
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Ray {
+            origin,
+            direction: direction.try_normalize(std::f32::EPSILON).unwrap_or_else(Vector3::zeros)
+        }
+    }
+
+    /// Creates a ray that starts at from and points towards to
+    pub fn from_points(from: Vector3<f32>, to: Vector3<f32>) -> Self {
+        Ray::new(from, to - from)
+    }
+
+    pub fn get_origin(&self) -> Vector3<f32> {
+        self.origin
+    }
+
+    pub fn get_direction(&self) -> Vector3<f32> {
+        self.direction
+    }
+
+    /// Returns the point that is distance along the ray from its origin
+    pub fn get_point(&self, distance: f32) -> Vector3<f32> {
+        self.origin + self.direction * distance
+    }
+}
@@ -1,6 +1,7 @@
 #![feature(cmp_min_max_by)]
 #![feature(inner_deref)]
 #![feature(ip)]
+#![feature(test)]
 
 #![allow(dead_code)]
 
@@ -31,9 +32,15 @@ use game_state::{GameState, NewState};
 
 mod nodes;
 
+#[cfg(test)]
+extern crate test;
+
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod benches;
+
 static mut OWNER_NODE: Option<Ref<Node>> = None;
 
 thread_local! {
@@ -186,6 +193,8 @@ impl WolfGang {
                     .add_system(systems::level_map::mesh::create_add_components_system())
                     .flush()
                     .add_thread_local_fn(systems::level_map::mesh::create_drawing_system())
+                    .add_thread_local(systems::level_map::mesh::create_collision_system())
+                    .add_thread_local(systems::level_map::mesh::create_material_surface_system())
                     
                     .add_thread_local(systems::custom_mesh::create_draw_system())
 
@@ -193,6 +202,11 @@ impl WolfGang {
                     .add_thread_local(systems::transform::position::create_system())
                     
                     .add_system(systems::history::create_history_input_system())
+                    .add_system(systems::level_map::symmetry::create_symmetry_input_system())
+
+                    //symmetry has to see every change sent this frame, so it goes after everything else has been flushed
+                    .flush()
+                    .add_thread_local_fn(systems::level_map::symmetry::create_symmetry_fn())
 
                     .build(),
                 world, resources
@@ -240,6 +254,9 @@ fn init(handle: InitHandle) {
     handle.add_class::<nodes::tool_list::ToolList>();
     handle.add_class::<nodes::palette::Palette>();
     handle.add_class::<nodes::actor_palette::ActorPalette>();
+    handle.add_class::<nodes::prefab_name_dialog::PrefabNameDialog>();
+    handle.add_class::<nodes::prefab_palette::PrefabPalette>();
+    handle.add_class::<nodes::shape_palette::ShapePalette>();
 }
 
 godot_init!(init);
\ No newline at end of file
@@ -2,25 +2,61 @@ use gdnative::prelude::*;
 use gdnative::api::{
     ConfirmationDialog,
     Directory,
+    File,
     FileDialog,
+    ResourceLoader,
+    StreamTexture,
     file_dialog::Mode
 };
 
+use legion::*;
+
 use crate::{
+    editor::PaletteSelection,
     node,
     game_state::{StateMachine},
     systems::{
         level_map,
-        level_map::document::Document,
+        level_map::{
+            CoordPos,
+            Map,
+            MapChange,
+            MapChunkData,
+            clipboard::Clipboard,
+            document::{Document, vec_to_byte_array},
+            flood::SelectionMask,
+            heightmap::{Heightmap, HeightmapSettings},
+            mesher::ChunkOctrees,
+            obj,
+            TILE_DEFINITIONS,
+            vox::{Vox, VoxModel, VoxTileMapping},
+        },
+        networking::{ClientID, DataType, MessageSender, MessageType},
+        selection_box::{SelectionBox, SelectionBoxRotation, TerrainToolBox},
     }
 };
 
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Point = nalgebra::Vector3<i32>;
+
+/// What the file that gets picked in the dialog is for
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum DialogPurpose {
+    Document,
+    HeightmapImport,
+    HeightmapExport,
+    VoxImport,
+    VoxExport,
+    ObjExport,
+}
+
 #[derive(NativeClass)]
 #[inherit(FileDialog)]
 #[register_with(Self::register_signals)]
 #[user_data(user_data::LocalCellData<SaveLoadDialog>)]
 pub struct SaveLoadDialog {
     confirm_dialog: Option<Ref<ConfirmationDialog>>,
+    purpose: DialogPurpose,
 }
 
 // __One__ `impl` block can have the `#[methods]` attribute, which will generate
@@ -61,7 +97,8 @@ impl SaveLoadDialog {
                     file_dialog.set_current_dir(maps_dir);
 
                     SaveLoadDialog{
-                        confirm_dialog: None
+                        confirm_dialog: None,
+                        purpose: DialogPurpose::Document,
                     }
                 },
 
@@ -102,7 +139,7 @@ impl SaveLoadDialog {
     }
 
     #[export]
-    /// Tells the FileDialog whether to open as Open or Save dialogs
+    /// Tells the FileDialog whether to open as Open or Save dialogs, and whether it is for documents, heightmaps, .vox models or .obj exports
     fn save_load_handler(&mut self, file_dialog: &FileDialog, type_flag: i64) {
 
         unsafe { 
@@ -110,13 +147,44 @@ impl SaveLoadDialog {
             match type_flag {
                 0 => { //open
                     file_dialog.set_mode(FileDialog::MODE_OPEN_FILE);
+                    self.purpose = DialogPurpose::Document;
                 },
                 1 => { //save
                     file_dialog.set_mode(FileDialog::MODE_SAVE_FILE);   
+                    self.purpose = DialogPurpose::Document;
+                },
+                2 => { //import heightmap
+                    file_dialog.set_mode(FileDialog::MODE_OPEN_FILE);
+                    self.purpose = DialogPurpose::HeightmapImport;
+                },
+                3 => { //export heightmap
+                    file_dialog.set_mode(FileDialog::MODE_SAVE_FILE);
+                    self.purpose = DialogPurpose::HeightmapExport;
+                },
+                4 => { //import vox
+                    file_dialog.set_mode(FileDialog::MODE_OPEN_FILE);
+                    self.purpose = DialogPurpose::VoxImport;
+                },
+                5 => { //export vox
+                    file_dialog.set_mode(FileDialog::MODE_SAVE_FILE);
+                    self.purpose = DialogPurpose::VoxExport;
+                },
+                6 => { //export obj
+                    file_dialog.set_mode(FileDialog::MODE_SAVE_FILE);
+                    self.purpose = DialogPurpose::ObjExport;
                 },
                 _ => {}
             }
 
+            let filter = match self.purpose {
+                DialogPurpose::Document => "*.wgm",
+                DialogPurpose::VoxImport | DialogPurpose::VoxExport => "*.vox",
+                DialogPurpose::ObjExport => "*.obj",
+                _ => "*.png"
+            };
+
+            file_dialog.set_filters(StringArray::from_vec(vec![GodotString::from(filter)]));
+
             file_dialog.popup_centered_clamped(Vector2::new(800.0, 600.0), 0.75); 
             file_dialog.invalidate();
             
@@ -169,6 +237,45 @@ impl SaveLoadDialog {
         let resources = crate::WolfGang::get_resources().unwrap();
         let resources = &mut resources.borrow_mut();
 
+        match self.purpose {
+            DialogPurpose::HeightmapImport => {
+                import_heightmap(world, resources, path);
+                return
+            },
+            DialogPurpose::HeightmapExport => {
+                let suffix = ".png";
+                if !path.ends_with(&GodotString::from(suffix)) {
+                    path = GodotString::from(path.to_string() + suffix);
+                }
+
+                export_heightmap(world, resources, path);
+                return
+            },
+            DialogPurpose::VoxImport => {
+                import_vox(world, resources, path);
+                return
+            },
+            DialogPurpose::VoxExport => {
+                let suffix = ".vox";
+                if !path.ends_with(&GodotString::from(suffix)) {
+                    path = GodotString::from(path.to_string() + suffix);
+                }
+
+                export_vox(world, resources, path);
+                return
+            },
+            DialogPurpose::ObjExport => {
+                let suffix = ".obj";
+                if !path.ends_with(&GodotString::from(suffix)) {
+                    path = GodotString::from(path.to_string() + suffix);
+                }
+
+                export_obj(world, path);
+                return
+            },
+            DialogPurpose::Document => {}
+        }
+
         unsafe {
             match file_dialog.mode() {
                 Mode::OPEN_FILE => {
@@ -207,4 +314,212 @@ impl SaveLoadDialog {
         }
     }
 
-}
\ No newline at end of file
+}
+
+/// Sends a map change that puts the heightmap at path into the map, starting from the minimum corner of this client's terrain selection box
+fn import_heightmap(world: &mut World, resources: &mut Resources, path: GodotString) {
+
+    let client_id = resources.get::<ClientID>().map(|client_id| *client_id);
+    let tile = resources.get::<PaletteSelection>().map_or(0, |selection| selection.val());
+    let settings = resources.get::<HeightmapSettings>().map(|settings| settings.clone()).unwrap_or_default();
+
+    let mut query = <(Read<SelectionBox>, Read<CoordPos>, Read<ClientID>)>::query().filter(component::<TerrainToolBox>());
+
+    let min = client_id.and_then(|client_id| query.iter(world)
+        .find(|(_, _, id)| id.val() == client_id.val())
+        .map(|(selection_box, coord_pos, _)| AABB::new(coord_pos.value, selection_box.aabb.dimensions).get_min())
+    ).unwrap_or_else(Point::zeros);
+
+    match Heightmap::from_image(path.to_string(), &settings, tile) {
+        Ok(heightmap) => {
+            world.push(
+                (
+                    MessageSender{
+                        data_type: DataType::MapChange{
+                            store_history: client_id.map(|client_id| client_id.val()),
+                            change: MapChange::MapHeightmap{ min, heightmap },
+                        },
+                        message_type: MessageType::Ordered
+                    },
+                )
+            );
+        },
+        Err(err) => godot_print!("Couldn't import heightmap {}: {:?}", path, err)
+    }
+}
+
+/// Saves the top surface of the map as a heightmap at path
+fn export_heightmap(world: &mut World, resources: &mut Resources, path: GodotString) {
+
+    let settings = resources.get::<HeightmapSettings>().map(|settings| settings.clone()).unwrap_or_default();
+
+    let heightmap = resources.get::<Map>()
+        .map(|map| *map)
+        .and_then(|map| Heightmap::from_map(world, map));
+
+    if let Some((heightmap, _)) = heightmap {
+        godot_print!("Exporting heightmap {}", path);
+
+        if let Err(err) = heightmap.save_png(path.to_string(), &settings) {
+            godot_print!("Couldn't export heightmap {}: {:?}", path, err)
+        }
+    }
+}
+
+/// Puts the first model of the .vox file at path into the clipboard of this client's terrain selection box, so that it can be pasted
+fn import_vox(world: &mut World, resources: &mut Resources, path: GodotString) {
+
+    let client_id = match resources.get::<ClientID>() {
+        Some(client_id) => *client_id,
+        None => return
+    };
+
+    let mapping = resources.get::<VoxTileMapping>().map(|mapping| mapping.clone()).unwrap_or_default();
+
+    let file = File::new();
+
+    if let Err(err) = file.open(path.clone(), File::READ) {
+        godot_print!("Couldn't open {}: {:?}", path, err);
+        return
+    }
+
+    let byte_array = file.get_buffer(file.get_len());
+    file.close();
+
+    let raw = (0..byte_array.len()).map(|i| byte_array.get(i)).collect::<Vec<u8>>();
+
+    let model = match Vox::from_bytes(&raw) {
+        Ok(vox) => match vox.models.into_iter().next() {
+            Some(model) => model,
+            None => {
+                godot_print!("{} doesn't have any models", path);
+                return
+            }
+        },
+        Err(err) => {
+            godot_print!("Couldn't import {}: {:?}", path, err);
+            return
+        }
+    };
+
+    let mut query = <(Entity, Read<SelectionBoxRotation>, Read<ClientID>)>::query().filter(component::<TerrainToolBox>());
+
+    let selection_box = query.iter(world)
+        .find(|(_, _, id)| id.val() == client_id.val())
+        .map(|(entity, rotation, _)| (*entity, rotation.value));
+
+    if let Some((entity, rotation)) = selection_box {
+        let clipboard = Clipboard::new(model.to_octree(Point::zeros(), &mapping), rotation);
+
+        if let Some(mut entry) = world.entry(entity) {
+            entry.add_component(clipboard);
+        }
+    }
+}
+
+/// Saves the tiles within this client's terrain selection box as a .vox model at path
+fn export_vox(world: &mut World, resources: &mut Resources, path: GodotString) {
+
+    let client_id = match resources.get::<ClientID>() {
+        Some(client_id) => *client_id,
+        None => return
+    };
+
+    let mapping = resources.get::<VoxTileMapping>().map(|mapping| mapping.clone()).unwrap_or_default();
+
+    let mut query = <(Read<SelectionBox>, Read<CoordPos>, Read<SelectionBoxRotation>, Read<ClientID>, TryRead<SelectionMask>)>::query()
+        .filter(component::<TerrainToolBox>());
+
+    let selection_box = query.iter(world)
+        .find(|(_, _, _, id, _)| id.val() == client_id.val())
+        .map(|(selection_box, coord_pos, rotation, _, mask)| {
+            let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+
+            (aabb, rotation.value, mask.filter(|mask| mask.applies_to(aabb)).cloned())
+        });
+
+    let clipboard = match selection_box {
+        Some((aabb, rotation, mask)) => Clipboard::from_range(world, resources, aabb, rotation, mask.as_ref()),
+        None => None
+    };
+
+    let bytes = clipboard
+        .map_or_else(
+            || Err(std::io::Error::new(std::io::ErrorKind::NotFound, "There is no terrain selection")),
+            |clipboard| VoxModel::from_octree(clipboard.get_octree(), &mapping)
+        )
+        .and_then(|model| Vox { models: vec![model], palette: None }.to_bytes());
+
+    match bytes {
+        Ok(bytes) => {
+            godot_print!("Exporting {}", path);
+
+            let file = File::new();
+
+            match file.open(path.clone(), File::WRITE) {
+                Ok(_) => {
+                    file.store_buffer(vec_to_byte_array(bytes));
+                    file.close();
+                },
+                Err(err) => godot_print!("Couldn't export {}: {:?}", path, err)
+            }
+        },
+        Err(err) => godot_print!("Couldn't export {}: {:?}", path, err)
+    }
+}
+
+/// Saves the mesh of the whole map as a .obj file at path, with its .mtl file and a copy of the tile sheet next to it
+fn export_obj(world: &mut World, path: GodotString) {
+
+    let mut query = <Read<MapChunkData>>::query();
+
+    let chunk_octrees = query.iter(world)
+        .map(|map_data| (map_data.get_chunk_point(), map_data.octree.clone()))
+        .collect::<ChunkOctrees>();
+
+    let definitions = TILE_DEFINITIONS.with(|definitions| definitions.clone());
+
+    let obj_path = path.to_string();
+    let mtl_path = format!("{}.mtl", obj_path.trim_end_matches(".obj"));
+
+    //the .mtl and the tile sheet are looked up relative to the directory the .obj is in
+    let (directory, mtl_file_name) = match mtl_path.rfind('/') {
+        Some(index) => (&mtl_path[..index], &mtl_path[index + 1..]),
+        None => (".", mtl_path.as_str())
+    };
+
+    let texture_path = format!("{}/{}", directory, obj::TEXTURE_FILE_NAME);
+
+    godot_print!("Exporting {}", obj_path);
+
+    let file = File::new();
+
+    let written = file.open(obj_path.clone(), File::WRITE)
+        .map(|_| {
+            file.store_string(obj::write_obj(&obj::mesh_map(&chunk_octrees, &definitions), mtl_file_name));
+            file.close();
+        })
+        .and_then(|_| file.open(mtl_path.clone(), File::WRITE))
+        .map(|_| {
+            file.store_string(obj::write_mtl());
+            file.close();
+        });
+
+    if let Err(err) = written {
+        godot_print!("Couldn't export {}: {:?}", obj_path, err);
+        return
+    }
+
+    let image = ResourceLoader::godot_singleton().load(definitions.sheet.as_str(), "StreamTexture", false)
+        .and_then(|texture_resource| texture_resource.cast::<StreamTexture>())
+        .and_then(|texture| unsafe { texture.assume_safe() }.get_data());
+
+    match image {
+        Some(image) => {
+            if let Err(err) = unsafe { image.assume_safe() }.save_png(texture_path.clone()) {
+                godot_print!("Couldn't save {}: {:?}", texture_path, err);
+            }
+        },
+        None => godot_print!("Couldn't get the tile sheet to save to {}", texture_path)
+    }
+}
@@ -190,6 +190,31 @@ impl FileMenu {
 
                 menu_button.emit_signal("save_load_popup", &[Variant::from_i64(1)]); 
                 
+            },
+            4 => { //import heightmap
+
+                menu_button.emit_signal("save_load_popup", &[Variant::from_i64(2)]);
+
+            },
+            5 => { //export heightmap
+
+                menu_button.emit_signal("save_load_popup", &[Variant::from_i64(3)]);
+
+            },
+            6 => { //import vox
+
+                menu_button.emit_signal("save_load_popup", &[Variant::from_i64(4)]);
+
+            },
+            7 => { //export vox
+
+                menu_button.emit_signal("save_load_popup", &[Variant::from_i64(5)]);
+
+            },
+            8 => { //export obj
+
+                menu_button.emit_signal("save_load_popup", &[Variant::from_i64(6)]);
+
             },
             _ => {}
         }
@@ -6,6 +6,9 @@ pub mod file_confirmation;
 pub mod connect_menu;
 pub mod connet_dialog;
 pub mod palette;
+pub mod prefab_name_dialog;
+pub mod prefab_palette;
+pub mod shape_palette;
 pub mod tool_list;
 
 pub mod utils;
\ No newline at end of file
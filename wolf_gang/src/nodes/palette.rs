@@ -1,9 +1,15 @@
 use gdnative::prelude::*;
 use gdnative::api::{
+    AtlasTexture,
     ItemList,
+    ResourceLoader,
+    StreamTexture,
 };
 
-use crate::editor::PaletteSelection;
+use crate::{
+    editor::PaletteSelection,
+    systems::level_map::TILE_DEFINITIONS,
+};
 
 #[derive(NativeClass)]
 #[inherit(ItemList)]
@@ -19,18 +25,21 @@ impl Palette{
         Palette {}
     }
 
+    /// Selects the tile id kept in the item's metadata
     #[export]
-    fn item_selected(&self, _: &ItemList, index: i64) {
+    fn item_selected(&self, item_list: &ItemList, index: i64) {
         
         let resources = crate::WolfGang::get_resources().unwrap();
         let resources = &mut resources.borrow_mut();
 
-        resources.insert(PaletteSelection::new(index as u32));
+        resources.insert(PaletteSelection::new(item_list.get_item_metadata(index).to_u64() as u32));
     }
 
     #[export]
     fn _ready(&self, item_list: &ItemList) {
 
+        populate_palette(item_list);
+
         item_list.emit_signal("item_selected", &[Variant::from_i64(0)]);
 
     }
@@ -41,4 +50,35 @@ impl Palette{
             item_list.select(0, true);
         }
     }
-}
\ No newline at end of file
+}
+
+/// Adds an item for every tile in config/tiles.ron, with the tile's icon cut out of the tile sheet
+fn populate_palette(item_list: &ItemList) {
+
+    TILE_DEFINITIONS.with(|definitions| {
+
+        let texture = ResourceLoader::godot_singleton().load(definitions.sheet.as_str(), "StreamTexture", false)
+            .and_then(|texture_resource| texture_resource.cast::<StreamTexture>())
+            .unwrap_or_else(|| panic!("Failed to load the tile sheet {}", definitions.sheet));
+
+        for id in definitions.get_ids() {
+
+            let (x, y) = definitions.get_icon(id);
+
+            let icon = AtlasTexture::new();
+
+            icon.set_atlas(texture.clone());
+            icon.set_region(Rect2::new(
+                Point2::new(x as f32 * definitions.tile_pixels, y as f32 * definitions.tile_pixels),
+                Size2::new(definitions.tile_pixels, definitions.tile_pixels)
+            ));
+
+            item_list.add_icon_item(icon, true);
+
+            let index = item_list.get_item_count() - 1;
+
+            item_list.set_item_metadata(index, Variant::from_u64(id as u64));
+            item_list.set_item_tooltip(index, definitions.get_name(id));
+        }
+    });
+}
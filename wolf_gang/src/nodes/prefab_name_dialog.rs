@@ -0,0 +1,126 @@
+use gdnative::prelude::*;
+use gdnative::api::{
+    ConfirmationDialog,
+    LineEdit,
+};
+use nalgebra::Rotation3;
+
+use crate::{
+    nodes::prefab_palette::PrefabPalette,
+    systems::level_map::prefab,
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+
+static mut OWNER_NODE: Option<Ref<ConfirmationDialog>> = None;
+
+/// The range and rotation of the selection box when saving was asked for, kept until the dialog is confirmed
+static mut PENDING: Option<(AABB, Rotation3<f32>)> = None;
+
+#[derive(NativeClass)]
+#[inherit(ConfirmationDialog)]
+#[user_data(user_data::LocalCellData<PrefabNameDialog>)]
+pub struct PrefabNameDialog {
+    line_edit: Option<Ref<LineEdit>>,
+}
+
+#[methods]
+impl PrefabNameDialog {
+
+    fn new(confirmation_dialog: &ConfirmationDialog) -> Self {
+
+        unsafe { OWNER_NODE = Some(confirmation_dialog.assume_shared()); }
+
+        PrefabNameDialog {
+            line_edit: None,
+        }
+    }
+
+    /// Asks for a name to save everything within range as a prefab under
+    pub fn prompt(range: AABB, rotation: Rotation3<f32>) {
+        if let Some(owner) = unsafe { OWNER_NODE } {
+            unsafe {
+                PENDING = Some((range, rotation));
+
+                //deferred since this gets called while the map editor's systems are running, and showing the dialog pauses them
+                owner.assume_safe().call_deferred("show_prompt", &[]);
+            }
+        }
+    }
+
+    #[export]
+    fn _ready(&mut self, confirmation_dialog: &ConfirmationDialog) {
+        unsafe {
+            self.line_edit = Some(
+                confirmation_dialog.assume_unique()
+                    .find_node("LineEdit", true, true).unwrap()
+                    .assume_unique().cast::<LineEdit>().unwrap()
+                    .into_shared()
+            );
+
+            confirmation_dialog.connect("confirmed", confirmation_dialog.assume_shared(), "on_confirmation", VariantArray::new_shared(), 0).ok();
+            confirmation_dialog.connect("popup_hide", confirmation_dialog.assume_shared(), "on_hide", VariantArray::new_shared(), 0).ok();
+        }
+
+        confirmation_dialog.set_title("Save prefab");
+    }
+
+    #[export]
+    fn show_prompt(&mut self, confirmation_dialog: &ConfirmationDialog) {
+        //stop typing the name from moving the selection box
+        crate::STATE_MACHINE.with(|s| {
+            let state_machine = &mut *s.borrow_mut();
+            state_machine.set_state_active("MapEditor", false);
+        });
+
+        if let Some(line_edit) = self.line_edit {
+            unsafe {
+                let line_edit = line_edit.assume_safe();
+                line_edit.clear();
+                line_edit.grab_focus();
+            }
+        }
+
+        confirmation_dialog.popup_centered(Vector2::new(200.,100.));
+    }
+
+    #[export]
+    fn on_confirmation(&mut self, _: &ConfirmationDialog) {
+        let name = match self.line_edit {
+            Some(line_edit) => unsafe { line_edit.assume_safe().text().to_string() },
+            None => return
+        };
+
+        let name = name.trim();
+
+        if !prefab::is_valid_name(name) {
+            godot_print!("Can't save a prefab named \"{}\"", name);
+            return
+        }
+
+        let (range, rotation) = match unsafe { PENDING.take() } {
+            Some(pending) => pending,
+            None => return
+        };
+
+        let world_lock = crate::WolfGang::get_world().unwrap();
+        let world = &mut world_lock.write().unwrap();
+        let resources = crate::WolfGang::get_resources().unwrap();
+        let resources = &mut resources.borrow_mut();
+
+        if let Some(prefab) = prefab::Prefab::from_range(world, resources, name, range, rotation) {
+            match prefab.save() {
+                Ok(_) => PrefabPalette::refresh(),
+                Err(err) => godot_print!("Failed to save prefab {}: {:?}", prefab.get_name(), err)
+            }
+        }
+    }
+
+    #[export]
+    fn on_hide(&mut self, _: &ConfirmationDialog) {
+        crate::STATE_MACHINE.with(|s| {
+            let state_machine = &mut *s.borrow_mut();
+            state_machine.set_state_active("MapEditor", true);
+        });
+    }
+}
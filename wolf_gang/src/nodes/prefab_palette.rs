@@ -0,0 +1,64 @@
+use gdnative::prelude::*;
+use gdnative::api::{
+    ImageTexture,
+    ItemList,
+};
+
+use crate::systems::level_map::prefab;
+
+static mut OWNER_NODE: Option<Ref<ItemList>> = None;
+
+#[derive(NativeClass)]
+#[inherit(ItemList)]
+#[user_data(user_data::LocalCellData<PrefabPalette>)]
+pub struct PrefabPalette {}
+
+#[methods]
+impl PrefabPalette {
+    fn new(item_list: &ItemList) -> Self {
+
+        unsafe { OWNER_NODE = Some(item_list.assume_shared()); }
+
+        unsafe {
+            item_list.connect("item_selected", item_list.assume_shared(), "item_selected", VariantArray::default(), 0).ok();
+        }
+
+        PrefabPalette {}
+    }
+
+    /// Repopulates the palette from the prefab directory, for after a prefab has been saved
+    pub fn refresh() {
+        if let Some(owner) = unsafe { OWNER_NODE } {
+            let item_list = unsafe { owner.assume_safe() };
+
+            populate_prefab_palette(&item_list);
+        }
+    }
+
+    #[export]
+    fn item_selected(&self, item_list: &ItemList, index: i64) {
+
+        let resources = crate::WolfGang::get_resources().unwrap();
+        let resources = &mut resources.borrow_mut();
+
+        let file_path = prefab::Prefab::get_file_path(&item_list.get_item_text(index).to_string());
+
+        match prefab::Prefab::from_file(file_path) {
+            Some(prefab) => resources.insert(prefab),
+            None => { resources.remove::<prefab::Prefab>(); }
+        }
+    }
+
+    #[export]
+    fn _ready(&self, item_list: &ItemList) {
+        populate_prefab_palette(item_list);
+    }
+}
+
+fn populate_prefab_palette(item_list: &ItemList) {
+    item_list.clear();
+
+    prefab::get_prefab_names().into_iter().for_each(|name| {
+        item_list.add_item(name, ImageTexture::new(), true);
+    });
+}
@@ -0,0 +1,55 @@
+use gdnative::prelude::*;
+use gdnative::api::{
+    ImageTexture,
+    ItemList,
+};
+
+use crate::{
+    editor::ShapeSelection,
+    systems::level_map::shape::TILE_SHAPES,
+};
+
+/// Lists the shapes that tiles can be placed with, next to the tile palette
+#[derive(NativeClass)]
+#[inherit(ItemList)]
+#[user_data(user_data::LocalCellData<ShapePalette>)]
+pub struct ShapePalette {}
+
+#[methods]
+impl ShapePalette {
+    fn new(item_list: &ItemList) -> Self {
+
+        unsafe { item_list.connect("item_selected", item_list.assume_shared(), "item_selected", VariantArray::default(), 0).ok(); }
+
+        ShapePalette {}
+    }
+
+    #[export]
+    fn item_selected(&self, _: &ItemList, index: i64) {
+
+        let resources = crate::WolfGang::get_resources().unwrap();
+        let resources = &mut resources.borrow_mut();
+
+        if let Some(shape) = TILE_SHAPES.get(index as usize) {
+            resources.insert(ShapeSelection::new(*shape));
+        }
+    }
+
+    #[export]
+    fn _ready(&self, item_list: &ItemList) {
+
+        TILE_SHAPES.iter().for_each(|shape| {
+            item_list.add_item(shape.get_name(), ImageTexture::new(), true);
+        });
+
+        item_list.emit_signal("item_selected", &[Variant::from_i64(0)]);
+
+    }
+
+    #[export]
+    fn _process(&self, item_list: &ItemList, _: f64) {
+        if !item_list.is_anything_selected() {
+            item_list.select(0, true);
+        }
+    }
+}
@@ -1,10 +1,7 @@
 use gdnative::prelude::*;
 use gdnative::api::{
-    AtlasTexture,
     ItemList,
-    ResourceLoader,
     ScrollContainer,
-    StreamTexture,
 };
 use crate::{
     editor,
@@ -46,9 +43,17 @@ impl ToolList {
             let actor_palette = get_actor_palette(item_list);
             let actor_window = actor_palette.get_parent().unwrap().assume_unique().cast::<ScrollContainer>().unwrap().into_shared();
 
+            let shape_palette = get_shape_palette(item_list);
+            let shape_window = shape_palette.get_parent().unwrap().assume_unique().cast::<ScrollContainer>().unwrap().into_shared();
+
+            let prefab_palette = get_prefab_palette(item_list);
+            let prefab_window = prefab_palette.get_parent().unwrap().assume_unique().cast::<ScrollContainer>().unwrap().into_shared();
+
             match index {
                 0 => { 
                     palette_window.assume_safe().set_visible(true);
+                    shape_window.assume_safe().set_visible(true);
+                    prefab_window.assume_safe().set_visible(true);
                     actor_window.assume_safe().set_visible(false);
 
                     resources.insert(editor::SelectedTool(selection_box::ToolBoxType::TerrainToolBox));
@@ -58,6 +63,8 @@ impl ToolList {
                 },
                 1 => {
                     palette_window.assume_safe().set_visible(false);
+                    shape_window.assume_safe().set_visible(false);
+                    prefab_window.assume_safe().set_visible(false);
                     actor_window.assume_safe().set_visible(true);
 
                     let actor_selection = resources.get::<editor::ActorPaletteSelection>().map(|selection| selection.val());
@@ -68,7 +75,11 @@ impl ToolList {
                     world.push((selection_box::ActivateActorToolBox{},));
 
                 }
-                _ => palette_window.assume_safe().set_visible(false)
+                _ => {
+                    palette_window.assume_safe().set_visible(false);
+                    shape_window.assume_safe().set_visible(false);
+                    prefab_window.assume_safe().set_visible(false);
+                }
 
             }
         }
@@ -78,10 +89,6 @@ impl ToolList {
     #[export]
     fn _ready(&self, item_list: &ItemList) {
 
-        unsafe {
-            let palette = get_palette(item_list);
-            populate_palette(&palette);
-        }
         item_list.emit_signal("item_selected", &[Variant::from_i64(0)]);
 
     }
@@ -102,32 +109,28 @@ unsafe fn get_actor_palette(item_list: &ItemList) -> TRef<ItemList> {
     actor_palette.assume_safe()
 }
 
-unsafe fn get_palette(item_list: &ItemList) -> TRef<ItemList> {
+unsafe fn get_prefab_palette(item_list: &ItemList) -> TRef<ItemList> {
     let main_tools_panel = item_list.get_parent().unwrap().assume_safe().get_parent().unwrap().assume_unique();
-    let palette = node::get_node(&main_tools_panel, &"Palette".to_string(), true)
+    let prefab_palette = node::get_node(&main_tools_panel, &"PrefabPalette".to_string(), true)
         .unwrap().assume_unique().cast::<ItemList>().unwrap().into_shared();
 
-    palette.assume_safe()
+    prefab_palette.assume_safe()
 }
 
-unsafe fn populate_palette(palette: &ItemList) {
-
-    let texture_resource = ResourceLoader::godot_singleton().load("res://images/ground.png", "StreamTexture", false).unwrap();
-    let texture = texture_resource.cast::<StreamTexture>().unwrap();
-
-    for i in 0..64 {
-
-        let x = i % 16;
-        let y = i / 16;
-
-        let icon = AtlasTexture::new();
+unsafe fn get_shape_palette(item_list: &ItemList) -> TRef<ItemList> {
+    let main_tools_panel = item_list.get_parent().unwrap().assume_safe().get_parent().unwrap().assume_unique();
+    let shape_palette = node::get_node(&main_tools_panel, &"ShapePalette".to_string(), true)
+        .unwrap().assume_unique().cast::<ItemList>().unwrap().into_shared();
 
-        icon.set_atlas(texture.clone());
-        icon.set_region(Rect2::new(Point2::new(x as f32 * 128.,y as f32 * 128.), Size2::new(128.,128.)));
+    shape_palette.assume_safe()
+}
 
-        palette.add_icon_item(icon, true);
+unsafe fn get_palette(item_list: &ItemList) -> TRef<ItemList> {
+    let main_tools_panel = item_list.get_parent().unwrap().assume_safe().get_parent().unwrap().assume_unique();
+    let palette = node::get_node(&main_tools_panel, &"Palette".to_string(), true)
+        .unwrap().assume_unique().cast::<ItemList>().unwrap().into_shared();
 
-    }
+    palette.assume_safe()
 }
 
 // unsafe fn populate_actor_palette(item_list: &ItemList, actor_definitions: &ActorDefinitions) {
@@ -15,6 +15,8 @@ use bincode::Options;
 type Point = nalgebra::Vector3<i32>;
 
 use crate::{
+    collections::octree::{Octree, PointData},
+    geometry::ray::Ray,
     node, 
     node::{
         NodeParent,
@@ -32,6 +34,11 @@ use crate::{
 };
 
 type AABB = crate::geometry::aabb::AABB<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+/// Size of the space covered by the ActorIndex octree in map coordinates, centered on the origin. Actors outside of it still get found,
+/// just not as quickly.
+pub const ACTOR_INDEX_DIMENSIONS: f32 = 2048.;
 
 use std::{
     collections::HashMap,
@@ -107,6 +114,134 @@ impl Bounds {
     }
 }
 
+/// An actor's entity along with the space that it takes up in map coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IndexedActor {
+    entity: Entity,
+    aabb: AABB,
+}
+
+impl IndexedActor {
+    pub fn get_entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn get_aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl PointData<f32> for IndexedActor {
+    fn get_point(&self) -> Vector3D {
+        self.get_bounds().center
+    }
+
+    fn with_point(&self, point: Vector3D) -> Self {
+        let offset = point - self.get_point();
+
+        let mut aabb = self.aabb;
+        aabb.center += Point::new(offset.x.round() as i32, offset.y.round() as i32, offset.z.round() as i32);
+
+        IndexedActor {
+            aabb,
+            ..*self
+        }
+    }
+
+    fn get_bounds(&self) -> crate::geometry::aabb::AABB<f32> {
+        aabb_to_float(self.aabb)
+    }
+}
+
+/// Converts a map AABB to one that covers the whole cell of every point inside of it, the same way tiles are drawn
+fn aabb_to_float(aabb: AABB) -> crate::geometry::aabb::AABB<f32> {
+    let min = aabb.get_min();
+    let max = aabb.get_max();
+
+    crate::geometry::aabb::AABB::from_extents(
+        Vector3D::new(min.x as f32, min.y as f32, min.z as f32),
+        Vector3D::new(max.x as f32 + 1., max.y as f32 + 1., max.z as f32 + 1.)
+    )
+}
+
+/// Resource that keeps track of where every actor is in an octree, so that finding the actors in a range or under the cursor doesn't
+/// have to check every one of them. It gets updated whenever an actor's CoordPos or Rotation changes, and actors are taken back out of it
+/// when an ActorRemoval goes through change. Anything that still manages to outlive its entity is dropped the next time a query runs 
+/// into it.
+pub struct ActorIndex {
+    octree: Octree<f32, IndexedActor>,
+    indexed: HashMap<Entity, IndexedActor>,
+    /// Actors that didn't fit inside of the octree
+    outside: Vec<IndexedActor>,
+}
+
+impl Default for ActorIndex {
+    fn default() -> Self {
+        ActorIndex {
+            octree: Octree::new(
+                crate::geometry::aabb::AABB::new(
+                    Vector3D::zeros(), 
+                    Vector3D::new(ACTOR_INDEX_DIMENSIONS, ACTOR_INDEX_DIMENSIONS, ACTOR_INDEX_DIMENSIONS)
+                ), 
+                crate::collections::octree::DEFAULT_MAX
+            ),
+            indexed: HashMap::new(),
+            outside: Vec::new(),
+        }
+    }
+}
+
+impl ActorIndex {
+
+    /// Adds the actor to the index, or moves it if it is already indexed
+    pub fn update(&mut self, entity: Entity, aabb: AABB) {
+        self.remove(entity);
+
+        let indexed_actor = IndexedActor {
+            entity,
+            aabb
+        };
+
+        if self.octree.push(indexed_actor).is_err() {
+            self.outside.push(indexed_actor);
+        }
+
+        self.indexed.insert(entity, indexed_actor);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(indexed_actor) = self.indexed.remove(&entity) {
+            self.octree.remove_item(&indexed_actor);
+            self.outside.retain(|outside| *outside != indexed_actor);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = ActorIndex::default();
+    }
+
+    /// Returns every indexed actor whose AABB intersects range
+    pub fn query_range(&self, range: AABB) -> Vec<IndexedActor> {
+        self.octree.query_intersecting(aabb_to_float(range)).into_iter()
+            .chain(self.outside.iter().copied())
+            .filter(|indexed_actor| range.intersects_bounds(indexed_actor.aabb))
+            .collect()
+    }
+
+    /// Returns the first actor that the ray hits, where the ray is in map coordinates
+    pub fn pick(&self, ray: Ray) -> Option<IndexedActor> {
+        let outside_hit = self.outside.iter()
+            .filter_map(|indexed_actor| indexed_actor.get_bounds().get_ray_intersection(ray).map(|(near, _)| (near, *indexed_actor)))
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        match (self.octree.query_ray_first(ray, None), outside_hit) {
+            (Some((near, indexed_actor)), Some((outside_near, _))) if near <= outside_near => Some(indexed_actor),
+            (Some(_), Some((_, outside_actor))) => Some(outside_actor),
+            (hit, outside_hit) => hit.or(outside_hit).map(|(_, indexed_actor)| indexed_actor)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayableCharacter(pub Option<Character>);
 
@@ -233,8 +368,16 @@ pub fn create_move_to_coord_system() -> impl systems::Runnable {
 
                     aabb.center = coord_pos.value;
 
-                    commands.exec_mut(move |world, _| {
+                    commands.exec_mut(move |world, resources| {
                         position_actor_helper(world, entity, aabb);
+
+                        let is_actor = world.entry(entity).map_or(false, |entry| entry.get_component::<ActorID>().is_ok());
+
+                        if is_actor {
+                            if let Some(mut actor_index) = resources.get_mut::<ActorIndex>() {
+                                actor_index.update(entity, aabb);
+                            }
+                        }
                     });
                 })
         })
@@ -292,59 +435,65 @@ pub fn serialize_single_actor_in_world(world: &mut World, entity: Entity) -> Res
     })
 }
 
-pub fn change(world: &mut World, change: &ActorChange, store_history: Option<u32>) {
+/// Deserializes the result of serialize_actors_in_world into a world of its own
+pub fn deserialize_actors(serialized: &[u8]) -> Result<World, bincode::Error> {
+    REGISTRY.with(|r| {
+        let registry = r.borrow();
+
+        CANON.with(|c| {
+            let canon = c.borrow();
+
+            let mut deserialized = bincode::de::Deserializer::from_slice(
+                serialized,
+                bincode::config::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes()
+            );
+
+            registry.as_deserialize(& *canon).deserialize(&mut deserialized)
+        })
+    })
+}
+
+pub fn change(world: &mut World, resources: &mut Resources, change: &ActorChange, store_history: Option<u32>) {
     match change {
 
         ActorChange::ActorInsertion{serialized} => {
-        REGISTRY.with(|r| {
-            let registry = r.borrow();
 
-            CANON.with(move |c| {
-                let canon = c.borrow();
-                
-                let mut deserialized = bincode::de::Deserializer::from_slice(
-                    &serialized[..], 
-                    bincode::config::DefaultOptions::new()
-                        .with_fixint_encoding()
-                        .allow_trailing_bytes()
-                );
-
-                let actor_world: World = registry.as_deserialize(& *canon).deserialize(&mut deserialized).unwrap();
-
-                let mut query = <(Entity, Read<ActorID>)>::query();
-                query.iter(&actor_world)
-                    .map(|(actor_entity, actor_id)| (*actor_entity, *actor_id))
-                    .collect::<Vec<(Entity, ActorID)>>()
-                    .into_iter()
-                    .for_each(|(actor_entity, actor_id)| {
-
-                        let world_actors = query.iter(world)
-                            .map(|(actor_entity, actor_id)| (*actor_entity, *actor_id))
-                            .collect::<Vec<(Entity, ActorID)>>();
-
-                        if world_actors.is_empty() || !world_actors.into_iter().any(|(_,id)| id.val() == actor_id.val()) {
-                            
-                            if let Some(store_history) = store_history {
-                                let mut history_query = <(Write<History>, Read<ClientID>)>::query();
-
-                                history_query.iter_mut(world).filter(|(_, id)| id.val() == store_history).for_each(|(history, _)| {
-                                    history.add_step(
-                                        StepType::ActorChange(
-                                            (ActorChange::ActorRemoval(actor_id.val()), change.clone())
-                                        )
-                                    );
-                                });
-                            }
+            let actor_world: World = deserialize_actors(serialized).unwrap();
 
-                            MERGER.with(|m| {
-                                let mut merger = m.borrow_mut();
-                                world.clone_from_single(&actor_world, actor_entity, &mut *merger);
+            let mut query = <(Entity, Read<ActorID>)>::query();
+            query.iter(&actor_world)
+                .map(|(actor_entity, actor_id)| (*actor_entity, *actor_id))
+                .collect::<Vec<(Entity, ActorID)>>()
+                .into_iter()
+                .for_each(|(actor_entity, actor_id)| {
 
+                    let world_actors = query.iter(world)
+                        .map(|(actor_entity, actor_id)| (*actor_entity, *actor_id))
+                        .collect::<Vec<(Entity, ActorID)>>();
+
+                    if world_actors.is_empty() || !world_actors.into_iter().any(|(_,id)| id.val() == actor_id.val()) {
+                        
+                        if let Some(store_history) = store_history {
+                            let mut history_query = <(Write<History>, Read<ClientID>)>::query();
+
+                            history_query.iter_mut(world).filter(|(_, id)| id.val() == store_history).for_each(|(history, _)| {
+                                history.add_step(
+                                    StepType::ActorChange(
+                                        (ActorChange::ActorRemoval(actor_id.val()), change.clone())
+                                    )
+                                );
                             });
                         }
-                });
+
+                        MERGER.with(|m| {
+                            let mut merger = m.borrow_mut();
+                            world.clone_from_single(&actor_world, actor_entity, &mut *merger);
+
+                        });
+                    }
             });
-        });
         },
         ActorChange::ActorRemoval(actor_id) => {
 
@@ -367,6 +516,11 @@ pub fn change(world: &mut World, change: &ActorChange, store_history: Option<u32
                             }
                         }
                     }
+
+                    if let Some(mut actor_index) = resources.get_mut::<ActorIndex>() {
+                        actor_index.remove(entity);
+                    }
+
                     node::free(world, node);
                 }
         }
@@ -385,7 +539,22 @@ pub fn free_all(world: &mut World) {
         })
 }
 
-pub fn select_actors_from_range(world: &mut World, range: AABB) -> Vec<Entity> {
+/// Returns every actor whose bounds intersect range, using the ActorIndex when there is one
+pub fn select_actors_from_range(world: &mut World, resources: &mut Resources, range: AABB) -> Vec<Entity> {
+
+    if let Some(mut actor_index) = resources.get_mut::<ActorIndex>() {
+
+        let (entities, removed): (Vec<Entity>, Vec<Entity>) = actor_index.query_range(range).into_iter()
+            .map(|indexed_actor| indexed_actor.get_entity())
+            .partition(|entity| world.entry(*entity).map_or(false, |entry| entry.get_component::<ActorID>().is_ok()));
+
+        for entity in removed {
+            actor_index.remove(entity);
+        }
+
+        return entities
+    }
+
     let mut actor_query = <(Entity, Read<Bounds>, Read<Rotation>, Read<CoordPos>)>::query().filter(component::<ActorID>());
 
     actor_query.iter(world)
@@ -398,3 +567,34 @@ pub fn select_actors_from_range(world: &mut World, range: AABB) -> Vec<Entity> {
         .map(|(entity, _, _, _)| *entity)
         .collect::<Vec<Entity>>()
 }
+
+/// Returns the first actor that the ray hits, where the ray is in map coordinates, using the ActorIndex when there is one
+pub fn pick_actor(world: &mut World, resources: &mut Resources, ray: Ray) -> Option<Entity> {
+
+    if let Some(mut actor_index) = resources.get_mut::<ActorIndex>() {
+
+        while let Some(indexed_actor) = actor_index.pick(ray) {
+            let entity = indexed_actor.get_entity();
+
+            if world.entry(entity).map_or(false, |entry| entry.get_component::<ActorID>().is_ok()) {
+                return Some(entity)
+            }
+
+            actor_index.remove(entity);
+        }
+
+        return None
+    }
+
+    let mut actor_query = <(Entity, Read<Bounds>, Read<Rotation>, Read<CoordPos>)>::query().filter(component::<ActorID>());
+
+    actor_query.iter(world)
+        .filter_map(|(entity, bounds, rotation, coord_pos)| {
+            let mut aabb = bounds.get_scaled_and_rotated_aabb(rotation.value);
+            aabb.center = coord_pos.value;
+
+            aabb_to_float(aabb).get_ray_intersection(ray).map(|(near, _)| (near, *entity))
+        })
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, entity)| entity)
+}
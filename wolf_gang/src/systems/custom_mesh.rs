@@ -30,7 +30,7 @@ impl MeshData {
 }
 
 pub struct Material {
-    name: Option<&'static str>,
+    name: Option<String>,
 }
 
 impl Material {
@@ -40,9 +40,9 @@ impl Material {
         }
     }
 
-    pub fn from_str(s: &'static str) -> Self {
+    pub fn from_str(s: &str) -> Self {
         Material {
-            name: Some(s)
+            name: Some(s.to_string())
         }
     }
 }
@@ -99,40 +99,13 @@ pub fn create_draw_system() -> impl systems::Runnable {
 
                 godot_print!("Drawing {:?}", unsafe { node_ref.val().assume_safe().name() });
 
-                let verts = &mesh_data.verts;
-                let uvs = &mesh_data.uvs;
-                let uv2s = &mesh_data.uv2s;
-                let normals = &mesh_data.normals;
-                let indices = &mesh_data.indices;
-                
                 let immediate_geometry: Ref<ImmediateGeometry> = unsafe { 
                     node_ref.val().assume_safe().cast::<ImmediateGeometry>().unwrap().assume_shared()
                 };
 
                 entities.insert(*entity, immediate_geometry);
 
-                unsafe {
-
-                    let immediate_geometry = immediate_geometry.assume_safe();            
-
-                    immediate_geometry.clear();
-                    immediate_geometry.begin(Mesh::PRIMITIVE_TRIANGLES, Null::null());
-                    
-                    let uv2s_len = uv2s.len();
-
-                    for index in indices {
-                        let index = *index as usize;
-
-                        immediate_geometry.set_normal(normals[index]);
-                        immediate_geometry.set_uv(uvs[index]);
-                        if index < uv2s_len {
-                            immediate_geometry.set_uv2(uv2s[index]);
-                        }
-                        immediate_geometry.add_vertex(verts[index]);
-                    }
-
-                    immediate_geometry.end();
-                }
+                unsafe { draw(&immediate_geometry.assume_safe(), mesh_data); }
                 
             });
 
@@ -141,24 +114,14 @@ pub fn create_draw_system() -> impl systems::Runnable {
                 commands.exec_mut(move |world, _| {
                     if let Some(mut entry) = world.entry(entity) {
                         if let Ok(material) = entry.get_component::<Material>() {
-                            let resource = ResourceLoader::godot_singleton().load(match material.name {
-                                Some(r) => r,
-                                None => { 
-                                    //TODO: make it so it grabs a default material if no name value is set.
-                                    panic!("Material name returned None");
-                                }
-                            }, "Material", false);
-                
                             unsafe {
-                                immediate_geometry.assume_safe().upcast::<GeometryInstance>().set_material_override(match resource {
-                                        Some(r) => r,
-                                        None => {
-                                            //TODO: Same thing, gotta get a default material if none is found
-                                            panic!("Resource {:?} does not exist", material.name);
-                                        }
+                                set_material(&immediate_geometry.assume_safe(), match &material.name {
+                                    Some(r) => r,
+                                    None => { 
+                                        //TODO: make it so it grabs a default material if no name value is set.
+                                        panic!("Material name returned None");
                                     }
-                                    .cast::<gdnative::api::Material>().unwrap()
-                                );
+                                });
                             }
                         }
 
@@ -171,3 +134,44 @@ pub fn create_draw_system() -> impl systems::Runnable {
     
 }
 
+/// Replaces whatever immediate_geometry was drawing with the triangles in mesh_data
+pub fn draw(immediate_geometry: &ImmediateGeometry, mesh_data: &MeshData) {
+    let verts = &mesh_data.verts;
+    let uvs = &mesh_data.uvs;
+    let uv2s = &mesh_data.uv2s;
+    let normals = &mesh_data.normals;
+    let indices = &mesh_data.indices;
+
+    immediate_geometry.clear();
+    immediate_geometry.begin(Mesh::PRIMITIVE_TRIANGLES, Null::null());
+    
+    let uv2s_len = uv2s.len();
+
+    for index in indices {
+        let index = *index as usize;
+
+        immediate_geometry.set_normal(normals[index]);
+        immediate_geometry.set_uv(uvs[index]);
+        if index < uv2s_len {
+            immediate_geometry.set_uv2(uv2s[index]);
+        }
+        immediate_geometry.add_vertex(verts[index]);
+    }
+
+    immediate_geometry.end();
+}
+
+/// Loads the material at path and draws immediate_geometry with it
+pub fn set_material(immediate_geometry: &ImmediateGeometry, path: &str) {
+    let resource = ResourceLoader::godot_singleton().load(path, "Material", false);
+
+    immediate_geometry.upcast::<GeometryInstance>().set_material_override(match resource {
+            Some(r) => r,
+            None => {
+                //TODO: gotta get a default material if none is found
+                panic!("Resource {:?} does not exist", path);
+            }
+        }
+        .cast::<gdnative::api::Material>().unwrap()
+    );
+}
@@ -26,6 +26,27 @@ use std::io::{ Error, ErrorKind };
 pub enum StepType {
     MapChange((Octree<i32, TileData>, Octree<i32, TileData>)),
     ActorChange((ActorChange, ActorChange)),
+    /// Several steps that get undone and redone together, such as an edit and its mirrored copies
+    Group(Vec<StepType>),
+}
+
+impl StepType {
+    /// Returns the map and actor changes that make up this step in the order they get applied. Groups get undone in the reverse of the
+    /// order their steps were made in, so that overlapping steps end up back where they started.
+    pub fn get_ordered_steps(&self, forward: bool) -> Vec<&StepType> {
+        match self {
+            StepType::Group(steps) => {
+                let mut ordered = steps.iter().flat_map(|step| step.get_ordered_steps(true)).collect::<Vec<&StepType>>();
+
+                if !forward {
+                    ordered.reverse();
+                }
+
+                ordered
+            },
+            step => vec![step]
+        }
+    }
 }
 
 /// Resource which holds chnages as a VecDeque
@@ -34,6 +55,9 @@ pub struct History {
     history: VecDeque<StepType>,
     current_step: i32,
     previous_amount: i32,
+    /// Steps added while a group is open, which get added to the history as one step once the group ends
+    #[serde(skip)]
+    group: Option<Vec<StepType>>,
 }
 
 impl History {
@@ -42,11 +66,17 @@ impl History {
             history: VecDeque::new(),
             current_step: -1,
             previous_amount: -1,
+            group: None,
         }
     }
 
     pub fn add_step(&mut self, step: StepType) {
 
+        if let Some(group) = &mut self.group {
+            group.push(step);
+            return
+        }
+
         //if there is a history beyond this step, wipe it out
         if self.current_step > -1 && self.history.len() as i32 > self.current_step + 1 {
             //this will always be shrinking so the generator is unreachable - there's nothing to generate
@@ -60,28 +90,29 @@ impl History {
         println!("Current step is {}", self.current_step);
     }
 
+    /// Starts collecting added steps into a single step, until end_group is called
+    pub fn begin_group(&mut self) {
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+
+    /// Adds the steps collected since begin_group as one step
+    pub fn end_group(&mut self) {
+        if let Some(mut group) = self.group.take() {
+            match group.len() {
+                0 => {},
+                1 => self.add_step(group.remove(0)),
+                _ => self.add_step(StepType::Group(group))
+            }
+        }
+    }
+
     /// Moves forward or backward in history by the given amount
     pub fn move_by_step(&mut self, commands: &mut legion::systems::CommandBuffer, resources: &mut Resources, amount: i32) {
 
         if let Ok((step, next_step)) = self.determine_move(amount) {
-            match step {
-                StepType::MapChange((undo_map, redo_map)) => {
-                    if let Some(map) = resources.get::<Map>().map(|map| *map) {
-                        let octree = if amount > 0 { redo_map.clone() } else { undo_map.clone() };
-
-                        commands.exec_mut(move |world, _| {
-                            map.change(world, octree.clone(), None);
-                        })
-                    }
-                },
-                StepType::ActorChange((undo_actor, redo_actor)) => {
-                    let change = if amount > 0 { redo_actor.clone() } else { undo_actor.clone() };
-                                
-                    commands.exec_mut(move |world, _| {
-                        actor::change(world, &change, None);
-                    })
-                },
-            }
+            apply_step(commands, resources, step, amount > 0);
 
             self.current_step = std::cmp::max(0, std::cmp::min(self.history.len() as i32 - 1, next_step));
             self.previous_amount = amount;
@@ -117,6 +148,50 @@ impl History {
     }
 }
 
+/// Redoes step if forward is true, otherwise undoes it
+fn apply_step(commands: &mut legion::systems::CommandBuffer, resources: &mut Resources, step: &StepType, forward: bool) {
+    match step {
+        StepType::MapChange((undo_map, redo_map)) => {
+            if let Some(map) = resources.get::<Map>().map(|map| *map) {
+                let octree = if forward { redo_map.clone() } else { undo_map.clone() };
+
+                commands.exec_mut(move |world, resources| {
+                    map.change(world, resources, octree.clone(), None);
+                })
+            }
+        },
+        StepType::ActorChange((undo_actor, redo_actor)) => {
+            let change = if forward { redo_actor.clone() } else { undo_actor.clone() };
+                        
+            commands.exec_mut(move |world, resources| {
+                actor::change(world, resources, &change, None);
+            })
+        },
+        StepType::Group(_) => {
+            step.get_ordered_steps(forward).into_iter().for_each(|step| apply_step(commands, resources, step, forward));
+        },
+    }
+}
+
+/// Collects the history steps that f adds for client_id into a single step
+pub fn group_steps<F: FnOnce(&mut World, &mut Resources)>(world: &mut World, resources: &mut Resources, client_id: Option<u32>, f: F) {
+    let mut query = <(Write<History>, Read<ClientID>)>::query();
+
+    if let Some(client_id) = client_id {
+        if let Some((history, _)) = query.iter_mut(world).find(|(_, id)| id.val() == client_id) {
+            history.begin_group();
+        }
+    }
+
+    f(world, resources);
+
+    if let Some(client_id) = client_id {
+        if let Some((history, _)) = query.iter_mut(world).find(|(_, id)| id.val() == client_id) {
+            history.end_group();
+        }
+    }
+}
+
 pub fn send_move_by_step(commands: &mut legion::systems::CommandBuffer, client_id: u32, amount: i32) {
     commands.push(
         (
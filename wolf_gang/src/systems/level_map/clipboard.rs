@@ -0,0 +1,148 @@
+use legion::*;
+use nalgebra::Rotation3;
+use serde::{Serialize, Deserialize};
+
+use crate::collections::octree::{self, PointData};
+
+use super::{Map, MapChunkIndex, TileData, flood::SelectionMask};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Component that holds the terrain a client copied with their selection box. The tiles are stored relative to the minimum corner of the
+/// copied range, along with the rotation the selection box had at the time so that pasting can rotate by the difference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clipboard {
+    octree: Octree,
+    rotation: Rotation3<f32>,
+    /// The magic wand selection the tiles were copied with, relative to the same corner, so that pasting leaves the cells outside of it alone
+    mask: Option<SelectionMask>,
+}
+
+impl Clipboard {
+
+    /// Copies every tile of the map within range into a new clipboard, or only the ones within mask if there is one, in which case pasting
+    /// only changes the cells of the mask
+    pub fn from_range(world: &mut World, resources: &Resources, range: AABB, rotation: Rotation3<f32>, mask: Option<&SelectionMask>) -> Option<Self> {
+        let map = resources.get::<Map>().map(|map| *map)?;
+        let chunk_index = resources.get::<MapChunkIndex>()?;
+
+        let min = range.get_min();
+        let mut octree = Octree::new(AABB::from_extents(Point::zeros(), range.get_max() - min), octree::DEFAULT_MAX);
+
+        map.query_chunk_range(world, &chunk_index, range).into_iter()
+            .filter(|tile_data| mask.map_or(true, |mask| mask.contains(tile_data.get_point())))
+            .for_each(|tile_data| {
+                octree.insert(tile_data.with_point(tile_data.get_point() - min)).ok();
+            });
+
+        octree.compress();
+
+        let mask = mask.map(|mask| SelectionMask::new(
+            octree.get_aabb(),
+            mask.get_points().iter()
+                .filter(|point| range.contains_point(**point))
+                .map(|point| point - min)
+                .collect()
+        ));
+
+        Some(Clipboard {
+            octree,
+            rotation,
+            mask
+        })
+    }
+
+    /// Makes a clipboard out of an octree whose range already starts from zero, as if it had been copied with the given rotation
+    pub fn new(octree: Octree, rotation: Rotation3<f32>) -> Self {
+        Clipboard {
+            octree,
+            rotation,
+            mask: None
+        }
+    }
+
+    /// Returns the copied tiles, relative to the minimum corner of the copied range
+    pub fn get_octree(&self) -> &Octree {
+        &self.octree
+    }
+
+    /// Returns the dimensions of the copied range
+    pub fn get_dimensions(&self) -> Point {
+        self.octree.get_aabb().dimensions
+    }
+
+    /// Returns the octree to change the map with when pasting into a selection box centered at center with the given rotation. The contents
+    /// are turned about the y axis by however many quarter turns the selection box has rotated since it was copied, shapes included.
+    pub fn get_pasted_octree(&self, center: Point, rotation: Rotation3<f32>) -> Octree {
+        let mut octree = Octree::new(self.get_pasted_range(center, rotation), octree::DEFAULT_MAX);
+        let quarter_turns = self.get_quarter_turns_since_copy(rotation);
+
+        self.octree.iter().for_each(|tile_data| {
+            let tile_data = tile_data.with_point(self.get_pasted_point(tile_data.get_point(), center, rotation));
+
+            octree.insert(tile_data.with_shape(tile_data.get_shape().rotated(quarter_turns))).ok();
+        });
+
+        octree.compress();
+
+        octree
+    }
+
+    /// Returns the mask the clipboard was copied with, moved and turned the same way as the tiles when pasting into a selection box centered 
+    /// at center with the given rotation
+    pub fn get_pasted_mask(&self, center: Point, rotation: Rotation3<f32>) -> Option<SelectionMask> {
+        self.mask.as_ref().map(|mask| SelectionMask::new(
+            self.get_pasted_range(center, rotation),
+            mask.get_points().iter().map(|point| self.get_pasted_point(*point, center, rotation)).collect()
+        ))
+    }
+
+    /// Returns the range that pasting into a selection box centered at center with the given rotation covers
+    pub fn get_pasted_range(&self, center: Point, rotation: Rotation3<f32>) -> AABB {
+        let quarter_turns = self.get_quarter_turns_since_copy(rotation);
+
+        AABB::new(center, rotate_point(self.get_dimensions(), quarter_turns).abs())
+    }
+
+    /// Returns where a point relative to the minimum corner of the copied range ends up when pasted
+    pub fn get_pasted_point(&self, point: Point, center: Point, rotation: Rotation3<f32>) -> Point {
+        let quarter_turns = self.get_quarter_turns_since_copy(rotation);
+
+        //the copied points start at zero, so the rotated box needs shifting back so that its minimum corner lands on the range's
+        let max = self.octree.get_aabb().get_max();
+        let corner = rotate_point(max, quarter_turns);
+        let offset = self.get_pasted_range(center, rotation).get_min() - Point::new(corner.x.min(0), corner.y.min(0), corner.z.min(0));
+
+        rotate_point(point, quarter_turns) + offset
+    }
+
+    /// Returns how many quarter turns the selection box has made about the y axis since the copy was made
+    pub fn get_quarter_turns_since_copy(&self, rotation: Rotation3<f32>) -> i32 {
+        get_quarter_turns(rotation * self.rotation.inverse())
+    }
+}
+
+/// Returns the number of quarter turns rotation makes about the y axis, from 0 to 3
+pub fn get_quarter_turns(rotation: Rotation3<f32>) -> i32 {
+    (get_y_angle(rotation) / std::f32::consts::FRAC_PI_2).round() as i32 & 3
+}
+
+/// Returns the angle rotation turns about the y axis, from -π to π
+pub fn get_y_angle(rotation: Rotation3<f32>) -> f32 {
+    //going by where the x axis ends up rather than the rotation's axis, which can point anywhere once it's close to a half turn
+    let turned = rotation * nalgebra::Vector3::x();
+
+    (-turned.z).atan2(turned.x)
+}
+
+/// Rotates point about the y axis by the given amount of quarter turns, the same direction as Rotation3::from_axis_angle with a positive angle
+pub fn rotate_point(point: Point, quarter_turns: i32) -> Point {
+    match quarter_turns & 3 {
+        1 => Point::new(point.z, point.y, -point.x),
+        2 => Point::new(-point.x, point.y, -point.z),
+        3 => Point::new(-point.z, point.y, point.x),
+        _ => point
+    }
+}
@@ -0,0 +1,233 @@
+//! Builds a simplified collision shape for map chunks. The flat tops of full tiles are merged into as few rectangles as possible for each
+//! height of the chunk, and each side of a column gets one wall for every run of tiles that side is open along, without any of the bevels the
+//! drawn mesh has. Tiles that aren't full keep their shape, so that ramps can be walked up. Nothing gets a bottom, since nothing should be
+//! able to get underneath the map to collide with one.
+
+use rayon::prelude::*;
+
+use crate::collections::octree::{self, PointData};
+
+use super::{TileData, TILE_DIMENSIONS, map_coords_to_world, mesher::{self, ChunkOctrees}};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+/// The sides of a column that can have walls
+const WALL_DIRS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Returns the triangles of every column of the chunk within range, three points to a triangle, along with each column's index in the chunk
+pub fn collide_range(chunks: &ChunkOctrees, octree: &Octree, range: AABB) -> Vec<(usize, Vec<Vector3D>)> {
+    let range_min = range.get_min();
+    let area = range.dimensions.x * range.dimensions.z;
+
+    (0..area).into_par_iter().map(|i| {
+
+        let x = (i % range.dimensions.x) + range_min.x;
+        let z = (i / range.dimensions.x) + range_min.z;
+
+        (mesher::get_column_index(octree, x, z), get_column_faces(chunks, octree, x, z))
+
+    }).collect()
+}
+
+/// Returns the merged tops of every height of the chunk within range, along with the height. Each height is merged across the whole chunk,
+/// since a rectangle can reach outside of the range.
+pub fn collide_tops(chunks: &ChunkOctrees, octree: &Octree, range: AABB) -> Vec<(i32, Vec<Vector3D>)> {
+    (range.get_min().y..=range.get_max().y).into_par_iter().map(|y| {
+        (y, get_top_faces(chunks, octree, y))
+    }).collect()
+}
+
+/// Returns the triangles of the whole chunk, with the columns in order followed by the tops from the bottom up
+pub fn collide_chunk(chunks: &ChunkOctrees, octree: &Octree) -> Vec<Vector3D> {
+    let aabb = octree.get_aabb();
+
+    let mut columns = collide_range(chunks, octree, aabb);
+    columns.sort_by_key(|(index, _)| *index);
+
+    let mut tops = collide_tops(chunks, octree, aabb);
+    tops.sort_by_key(|(y, _)| *y);
+
+    let mut faces = columns.into_iter().flat_map(|(_, faces)| faces).collect::<Vec<Vector3D>>();
+    faces.extend(tops.into_iter().flat_map(|(_, faces)| faces));
+
+    faces
+}
+
+/// Returns the tops of the full tiles at height y that nothing sits on, greedily merged into rectangles. Each rectangle is grown along x as
+/// far as it goes, then along z for as long as every tile of the next row along is open too.
+pub fn get_top_faces(chunks: &ChunkOctrees, octree: &Octree, y: i32) -> Vec<Vector3D> {
+    let aabb = octree.get_aabb();
+    let min = aabb.get_min();
+    let max = aabb.get_max();
+    let dimensions = aabb.dimensions;
+
+    let mut open = vec![false; (dimensions.x * dimensions.z) as usize];
+
+    octree.query_range(AABB::from_extents(Point::new(min.x, y, min.z), Point::new(max.x, y, max.z))).into_iter()
+        .filter(|tile_data| tile_data.get_shape().is_full() && mesher::get_tile(chunks, octree, tile_data.get_point() + Point::y()).is_none())
+        .for_each(|tile_data| {
+            let point = tile_data.get_point();
+            open[mesher::get_column_index(octree, point.x, point.z)] = true;
+        });
+
+    let index = |x: i32, z: i32| (x + dimensions.x * z) as usize;
+
+    let mut faces = Vec::new();
+
+    for z in 0..dimensions.z {
+        for x in 0..dimensions.x {
+            if !open[index(x, z)] {
+                continue
+            }
+
+            let mut width = 1;
+            while x + width < dimensions.x && open[index(x + width, z)] {
+                width += 1;
+            }
+
+            let mut depth = 1;
+            while z + depth < dimensions.z && (x..x + width).all(|row_x| open[index(row_x, z + depth)]) {
+                depth += 1;
+            }
+
+            for covered_z in z..z + depth {
+                for covered_x in x..x + width {
+                    open[index(covered_x, covered_z)] = false;
+                }
+            }
+
+            let near = map_coords_to_world(Point::new(min.x + x, y + 1, min.z + z));
+            let far = map_coords_to_world(Point::new(min.x + x + width, y + 1, min.z + z + depth));
+
+            push_quad(&mut faces, [
+                near,
+                Vector3D::new(far.x, near.y, near.z),
+                far,
+                Vector3D::new(near.x, near.y, far.z),
+            ], Vector3D::y());
+        }
+    }
+
+    faces
+}
+
+/// Returns the triangles for a single column of the chunk, other than the tops of its full tiles which collide_tops merges
+pub fn get_column_faces(chunks: &ChunkOctrees, octree: &Octree, x: i32, z: i32) -> Vec<Vector3D> {
+    let aabb = octree.get_aabb();
+
+    let (full, mut shaped): (Vec<TileData>, Vec<TileData>) = octree.query_range(AABB::from_extents(Point::new(x, aabb.get_min().y, z), Point::new(x, aabb.get_max().y, z)))
+        .into_iter()
+        .partition(|tile_data| tile_data.get_shape().is_full());
+
+    let mut heights = full.iter().map(|tile_data| tile_data.get_point().y).collect::<Vec<i32>>();
+
+    heights.sort();
+    shaped.sort_by_key(|tile_data| tile_data.get_point().y);
+
+    let mut faces = Vec::new();
+
+    WALL_DIRS.iter().for_each(|(dir_x, dir_z)| {
+        let dir = Point::new(*dir_x, 0, *dir_z);
+
+        //merge the tiles that are open on this side and on top of each other into one wall
+        let mut run: Option<(i32, i32)> = None;
+
+        heights.iter().for_each(|y| {
+            let point = Point::new(x, *y, z);
+
+            if is_filled(chunks, octree, point + dir) {
+                if let Some((bottom, top)) = run.take() {
+                    push_wall(&mut faces, x, z, dir, bottom, top);
+                }
+            } else {
+                run = match run {
+                    Some((bottom, top)) if top + 1 == *y => Some((bottom, *y)),
+                    Some((bottom, top)) => {
+                        push_wall(&mut faces, x, z, dir, bottom, top);
+                        Some((*y, *y))
+                    },
+                    None => Some((*y, *y))
+                };
+            }
+        });
+
+        if let Some((bottom, top)) = run {
+            push_wall(&mut faces, x, z, dir, bottom, top);
+        }
+    });
+
+    shaped.iter().for_each(|tile_data| {
+        let point = tile_data.get_point();
+        let shape = tile_data.get_shape();
+        let min = map_coords_to_world(point);
+
+        if mesher::get_tile(chunks, octree, point + Point::y()).is_none() {
+            shape.get_top_triangles(min).iter().for_each(|triangle| faces.extend(triangle));
+        }
+
+        WALL_DIRS.iter().for_each(|(dir_x, dir_z)| {
+            let dir = Point::new(*dir_x, 0, *dir_z);
+
+            let covered = mesher::get_tile(chunks, octree, point + dir).map_or(false, |neighbor| shape.is_side_covered_by(dir, neighbor.get_shape()));
+
+            if !covered {
+                shape.get_side_triangles(min, dir).iter().for_each(|triangle| faces.extend(triangle));
+            }
+        });
+    });
+
+    faces
+}
+
+/// Returns whether there is a full tile at point, looking in whichever chunk it falls into. Tiles that aren't full don't reach all the way up
+/// the side of their neighbours, so those neighbours still need a wall.
+fn is_filled(chunks: &ChunkOctrees, octree: &Octree, point: Point) -> bool {
+    mesher::get_tile(chunks, octree, point).map_or(false, |tile_data| tile_data.get_shape().is_full())
+}
+
+/// Adds the wall on the dir side of the column at x and z, from the bottom of the bottom tile to the top of the top tile
+fn push_wall(faces: &mut Vec<Vector3D>, x: i32, z: i32, dir: Point, bottom: i32, top: i32) {
+    let min = map_coords_to_world(Point::new(x, bottom, z));
+    let max = map_coords_to_world(Point::new(x, top + 1, z)) + Vector3D::new(TILE_DIMENSIONS.x, 0., TILE_DIMENSIONS.z);
+
+    let normal = Vector3D::new(dir.x as f32, 0., dir.z as f32);
+
+    let corners = if dir.x != 0 {
+        let wall_x = if dir.x > 0 { max.x } else { min.x };
+
+        [
+            Vector3D::new(wall_x, min.y, min.z),
+            Vector3D::new(wall_x, min.y, max.z),
+            Vector3D::new(wall_x, max.y, max.z),
+            Vector3D::new(wall_x, max.y, min.z),
+        ]
+    } else {
+        let wall_z = if dir.z > 0 { max.z } else { min.z };
+
+        [
+            Vector3D::new(min.x, min.y, wall_z),
+            Vector3D::new(max.x, min.y, wall_z),
+            Vector3D::new(max.x, max.y, wall_z),
+            Vector3D::new(min.x, max.y, wall_z),
+        ]
+    };
+
+    push_quad(faces, corners, normal);
+}
+
+/// Adds the quad as two triangles, wound clockwise when looked at from the side normal points to since that is the side Godot treats as the
+/// front
+fn push_quad(faces: &mut Vec<Vector3D>, corners: [Vector3D; 4], normal: Vector3D) {
+    let [a, b, c, d] = corners;
+
+    let (b, d) = if (b - a).cross(&(c - a)).dot(&normal) > 0. {
+        (d, b)
+    } else {
+        (b, d)
+    };
+
+    faces.extend(&[a, b, c, a, c, d]);
+}
@@ -20,6 +20,14 @@ use serde::{Serialize, Deserialize};
 use std::collections::HashSet;
 
 type Octree = octree::Octree<i32, level_map::TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Written at the start of saved documents, followed by the format version. Documents saved before there was a header start right 
+/// away with their file path, which bincode writes as a leading 0 or 1, so they can't be mistaken for having one.
+const HEADER: &[u8; 4] = b"WGM\0";
+
+/// Bumped whenever the layout of anything saved in a document changes
+pub const FORMAT_VERSION: u32 = 1;
 
 pub struct ResetMap{}
 
@@ -27,6 +35,7 @@ pub struct ResetMap{}
 pub struct Document {
     pub file_path: Option<String>,
     pub title: String,
+    map: level_map::Map,
     map_chunks: Vec<Octree>,
     actor_data: Option<Vec<u8>>,
 }
@@ -44,11 +53,25 @@ impl Document {
                 None => None
             },
             title,
+            map: level_map::Map::default(),
             map_chunks: Vec::new(),
             actor_data: None,
         }
     }
 
+    pub fn get_map(&self) -> level_map::Map {
+        self.map
+    }
+
+    pub fn get_map_chunks(&self) -> &Vec<Octree> {
+        &self.map_chunks
+    }
+
+    /// Sets the map configuration that gets saved with the document
+    pub fn set_map(&mut self, map: level_map::Map) {
+        self.map = map;
+    }
+
     ///Updates the data for the document by iterating through queries on the world    
     pub fn update_data(&mut self, world: &mut legion::world::World) {
         //go through and updata the data with the octree from each map chunk
@@ -70,6 +93,16 @@ impl Document {
     /// Populate the world with the required entities from self's document data
     pub fn populate_world(&self, world: &mut legion::world::World, _resources: &mut Resources) {
 
+        //the map configuration has to arrive before any of the map data so that it gets chunked correctly
+        world.push(
+            (
+                MessageSender{
+                    data_type: DataType::MapConfig(self.map),
+                    message_type: MessageType::Ordered
+                },
+            )
+        );
+
         for octree in &self.map_chunks {
             world.push(
                 (
@@ -98,9 +131,12 @@ impl Document {
         }
     }
 
-    /// Returns a Vec<u8> of the result of serializing the document using bincode
+    /// Returns a Vec<u8> of the header and format version followed by the result of serializing the document using bincode
     pub fn to_raw(&self) -> Vec<u8> {
-        let encoded: Vec<u8> = bincode::serialize(self).unwrap();
+        let mut encoded: Vec<u8> = HEADER.to_vec();
+
+        encoded.extend(bincode::serialize(&FORMAT_VERSION).unwrap());
+        encoded.extend(bincode::serialize(self).unwrap());
 
         encoded
     }
@@ -139,6 +175,7 @@ impl Document {
                 let working_file = Document {
                     file_path: self.file_path.clone(),
                     title: self.title.clone(),
+                    map: self.map,
                     ..Default::default()
                 };
 
@@ -147,19 +184,20 @@ impl Document {
                         let opened_file = Document {
                             file_path: opened_file.file_path,
                             title: opened_file.title,
+                            map: opened_file.map,
                             ..Default::default()
                         };
 
                         let mut working_data = HashSet::new();
 
                         working_file.map_chunks.iter().for_each(|octree| {
-                            working_data.extend(octree.clone().into_iter())
+                            working_data.extend(octree.iter().map(|tile_data| *tile_data))
                         });
 
                         let mut opened_data = HashSet::new();
 
                         opened_file.map_chunks.iter().for_each(|octree| {
-                            opened_data.extend(octree.clone().into_iter())
+                            opened_data.extend(octree.iter().map(|tile_data| *tile_data))
                         });
 
                         opened_file == working_file && opened_data.symmetric_difference(&working_data).count() == 0
@@ -203,12 +241,56 @@ impl Document {
 
         let raw = Self::raw_from_file(file_path);
 
-        bincode::deserialize::<Self>(&raw)
+        Self::from_raw(&raw)
         
     }
 
+    /// Reads a document written by to_raw, or one saved before documents had a header
     pub fn from_raw(raw: &[u8]) -> Result<Self, Box<bincode::ErrorKind>> {
-        bincode::deserialize::<Self>(raw)
+
+        if !raw.starts_with(HEADER) {
+            return bincode::deserialize::<LegacyDocument>(raw).map(Document::from);
+        }
+
+        let raw = &raw[HEADER.len()..];
+
+        let version = bincode::deserialize::<u32>(raw)?;
+        let raw = &raw[std::mem::size_of::<u32>()..];
+
+        match version {
+            FORMAT_VERSION => bincode::deserialize::<Self>(raw),
+            _ => Err(Box::new(bincode::ErrorKind::Custom(format!("Format version {} isn't supported", version))))
+        }
+    }
+}
+
+/// The layout documents were saved with before they had a header, back when the map had no configuration and tiles had no shape
+#[derive(Deserialize)]
+struct LegacyDocument {
+    file_path: Option<String>,
+    title: String,
+    map_chunks: Vec<octree::LegacyOctree<i32, LegacyTileData>>,
+    actor_data: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct LegacyTileData {
+    tile: u32,
+    point: Point,
+}
+
+impl From<LegacyDocument> for Document {
+    fn from(legacy: LegacyDocument) -> Self {
+        Document {
+            file_path: legacy.file_path,
+            title: legacy.title,
+            //chunks were always 10 tiles across, which is the default
+            map: level_map::Map::default(),
+            map_chunks: legacy.map_chunks.into_iter()
+                .map(|octree| octree.into_octree(|tile_data| level_map::TileData::new(tile_data.tile, tile_data.point)))
+                .collect(),
+            actor_data: legacy.actor_data,
+        }
     }
 }
 
@@ -0,0 +1,225 @@
+use legion::*;
+use serde::{Serialize, Deserialize};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::collections::octree::{self, PointData};
+
+use super::{Map, MapChunkData, MapChunkIndex, TileData};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Which neighbours of a tile count as touching it when flood filling
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Connectivity {
+    /// Only the four sides on the same layer, so a fill stays on one floor
+    Four,
+    /// The four sides as well as above and below
+    Six,
+    /// Every tile sharing a face, edge or corner
+    TwentySix,
+}
+
+impl Connectivity {
+    /// Returns the next connectivity, going from Four to Six to TwentySix and back around
+    pub fn next(&self) -> Self {
+        match self {
+            Connectivity::Four => Connectivity::Six,
+            Connectivity::Six => Connectivity::TwentySix,
+            Connectivity::TwentySix => Connectivity::Four,
+        }
+    }
+
+    pub fn get_directions(&self) -> Vec<Point> {
+        match self {
+            Connectivity::Four => vec![Point::x(), -Point::x(), Point::z(), -Point::z()],
+            Connectivity::Six => vec![Point::x(), -Point::x(), Point::y(), -Point::y(), Point::z(), -Point::z()],
+            Connectivity::TwentySix => AABB::from_extents(Point::new(-1,-1,-1), Point::new(1,1,1)).iter_points()
+                .filter(|dir| *dir != Point::zeros())
+                .collect()
+        }
+    }
+}
+
+/// Component of a selection box holding the tiles picked with the magic wand. While the box still covers the range they were picked in,
+/// the tools that change the tiles in the box only change these ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectionMask {
+    aabb: AABB,
+    points: HashSet<Point>,
+}
+
+impl SelectionMask {
+    pub fn new(aabb: AABB, points: HashSet<Point>) -> Self {
+        SelectionMask {
+            aabb,
+            points
+        }
+    }
+
+    /// Returns a mask of every tile in tiles, covering the smallest range that contains them
+    pub fn from_tiles(tiles: &[TileData]) -> Option<Self> {
+        Some(SelectionMask::new(
+            get_bounds(tiles)?, 
+            tiles.iter().map(|tile_data| tile_data.get_point()).collect()
+        ))
+    }
+
+    pub fn get_aabb(&self) -> AABB {
+        self.aabb
+    }
+
+    /// The mask only applies to a selection box covering the same range it was picked in, once the box moves or changes size it's ignored
+    pub fn applies_to(&self, aabb: AABB) -> bool {
+        self.aabb == aabb
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.points.contains(&point)
+    }
+
+    pub fn get_points(&self) -> &HashSet<Point> {
+        &self.points
+    }
+
+    /// Returns octree with everything outside of the mask put back the way it is in existing, so that a change only affects the masked tiles
+    pub fn get_masked_octree(&self, octree: &Octree, existing: Vec<TileData>) -> Octree {
+        let mut masked = Octree::new(octree.get_aabb(), octree::DEFAULT_MAX);
+
+        existing.into_iter().filter(|tile_data| !self.contains(tile_data.get_point())).for_each(|tile_data| {
+            masked.insert(tile_data).ok();
+        });
+
+        octree.iter().filter(|tile_data| self.contains(tile_data.get_point())).for_each(|tile_data| {
+            masked.insert(*tile_data).ok();
+        });
+
+        masked.compress();
+
+        masked
+    }
+}
+
+/// Resource holding the settings the flood fill and magic wand tools use
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FloodSettings {
+    pub connectivity: Connectivity,
+    pub max_volume: usize,
+}
+
+impl Default for FloodSettings {
+    fn default() -> Self {
+        FloodSettings {
+            connectivity: Connectivity::Four,
+            max_volume: 4096,
+        }
+    }
+}
+
+/// Returns every tile connected to start that has the same tile as it, stopping once max_volume tiles have been found. Chunks are only
+/// looked up as the fill reaches them. This doesn't go through mesher::get_open_sides, which only looks for neighbours that leave a wall
+/// showing on the same layer, whereas a fill needs neighbours holding the same tile, including above and below for Six and TwentySix.
+pub fn flood_select(world: &mut World, resources: &Resources, start: Point, connectivity: Connectivity, max_volume: usize) -> Vec<TileData> {
+
+    let map = match resources.get::<Map>() {
+        Some(map) => *map,
+        None => return Vec::new()
+    };
+
+    let chunk_index = match resources.get::<MapChunkIndex>() {
+        Some(chunk_index) => chunk_index,
+        None => return Vec::new()
+    };
+
+    let mut chunks: HashMap<Point, Option<MapChunkData>> = HashMap::new();
+
+    let mut get_tile = |point: Point| -> Option<TileData> {
+        let chunk_pt = map.get_chunk_range(AABB::from_extents(point, point)).get_min();
+
+        let map_data = chunks.entry(chunk_pt).or_insert_with(|| {
+            chunk_index.get(chunk_pt).and_then(|entity| super::get_map_chunk_data(world, entity))
+        });
+
+        map_data.as_ref().and_then(|map_data| map_data.octree.query_point(point))
+    };
+
+    let tile = match get_tile(start) {
+        Some(tile_data) => tile_data.get_tile(),
+        None => return Vec::new()
+    };
+
+    let directions = connectivity.get_directions();
+
+    let mut results = Vec::new();
+    let mut visited: HashSet<Point> = HashSet::new();
+    let mut queue: VecDeque<Point> = VecDeque::new();
+
+    visited.insert(start);
+    queue.push_back(start);
+
+    while let Some(point) = queue.pop_front() {
+        if results.len() >= max_volume {
+            break
+        }
+
+        if let Some(tile_data) = get_tile(point).filter(|tile_data| tile_data.get_tile() == tile) {
+            results.push(tile_data);
+
+            for dir in &directions {
+                let neighbor = point + dir;
+
+                if map.get_bounds().map_or(true, |bounds| bounds.contains_point(neighbor)) && visited.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    results
+}
+
+/// Returns the point to start a fill from within range, which is the highest tile in it, so that a selection box resting on the ground
+/// picks the floor it's touching
+pub fn get_start_in_range(world: &mut World, resources: &Resources, range: AABB) -> Option<Point> {
+    let map = resources.get::<Map>().map(|map| *map)?;
+    let chunk_index = resources.get::<MapChunkIndex>()?;
+
+    map.query_chunk_range(world, &chunk_index, range).into_iter()
+        .map(|tile_data| tile_data.get_point())
+        .max_by_key(|point| (point.y, -point.x, -point.z))
+}
+
+/// Returns the smallest AABB containing every tile in tiles
+pub fn get_bounds(tiles: &[TileData]) -> Option<AABB> {
+    let mut iter = tiles.iter().map(|tile_data| tile_data.get_point());
+
+    let first = iter.next()?;
+
+    Some(iter.fold(AABB::from_extents(first, first), |aabb, point| aabb.union(AABB::from_extents(point, point))))
+}
+
+/// Returns the octree to change the map with for repainting the tiles connected to start with tile, each keeping its shape. Any other
+/// tiles within the bounds of the fill are kept as they are.
+pub fn get_flood_fill_octree(world: &mut World, resources: &Resources, start: Point, tile: u32, connectivity: Connectivity, max_volume: usize) -> Option<Octree> {
+    let selected = flood_select(world, resources, start, connectivity, max_volume);
+    let range = get_bounds(&selected)?;
+
+    let map = resources.get::<Map>().map(|map| *map)?;
+    let chunk_index = resources.get::<MapChunkIndex>()?;
+
+    let mut octree = Octree::new(range, octree::DEFAULT_MAX);
+
+    map.query_chunk_range(world, &chunk_index, range).into_iter().for_each(|existing| {
+        octree.insert(existing).ok();
+    });
+
+    selected.into_iter().for_each(|selected| {
+        octree.insert(selected.with_tile(tile)).ok();
+    });
+
+    octree.compress();
+
+    Some(octree)
+}
@@ -0,0 +1,248 @@
+use legion::*;
+use nalgebra::Rotation3;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    collections::octree,
+    nodes::actor_palette::ActorPalette,
+    systems::{
+        actor,
+        actor::{Actor, ActorChange, ActorID, Bounds},
+        history,
+        transform::rotation::Rotation,
+    },
+};
+
+use super::{CoordPos, Map, TileData};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// The name of the actor definition in actors.ron that gets scattered over generated terrain
+pub const TREE_ACTOR: &str = "Tree";
+
+/// A band of the generated terrain that gets filled with one tile
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeightLayer {
+    /// How far up the region this layer goes, from 0 at the bottom to 1 at the top
+    pub up_to: f32,
+    pub tile: u32,
+}
+
+/// Resource holding the settings the terrain generator uses, which also get sent along with the change so that every client generates
+/// the same terrain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorSettings {
+    pub seed: u64,
+    /// Width in tiles of the largest hills
+    pub scale: f32,
+    /// How many layers of finer noise get added on top of the largest hills
+    pub octaves: u32,
+    /// Layers from the bottom up, with anything above the last layer using the last layer's tile
+    pub layers: Vec<HeightLayer>,
+    /// Chance from 0 to 1 of a tree being placed on each column of the bottom layer
+    pub tree_density: f32,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        GeneratorSettings {
+            seed: 0,
+            scale: 32.,
+            octaves: 4,
+            layers: vec![
+                //ground
+                HeightLayer { up_to: 0.5, tile: 0 },
+                //cliff
+                HeightLayer { up_to: 0.8, tile: 2 },
+                //snow
+                HeightLayer { up_to: 1., tile: 7 },
+            ],
+            tree_density: 0.02,
+        }
+    }
+}
+
+impl GeneratorSettings {
+
+    /// Returns the tile for a cell at fraction of the way up the region
+    pub fn get_tile_at(&self, fraction: f32) -> Option<u32> {
+        self.layers.iter()
+            .find(|layer| fraction <= layer.up_to)
+            .or_else(|| self.layers.last())
+            .map(|layer| layer.tile)
+    }
+
+    /// Returns the height of the terrain from 1 up to the height of aabb for the column at x and z
+    pub fn get_height(&self, aabb: AABB, x: i32, z: i32) -> i32 {
+        let noise = fractal_noise(self.seed, x as f32 / self.scale, z as f32 / self.scale, self.octaves);
+
+        1 + (noise * (aabb.dimensions.y - 1) as f32).round() as i32
+    }
+
+    /// Returns the octree to change the map with, which covers all of aabb so that anything already there is replaced
+    pub fn get_generated_octree(&self, aabb: AABB) -> Octree {
+        let min = aabb.get_min();
+        let max = aabb.get_max();
+
+        let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
+
+        for x in min.x..=max.x {
+            for z in min.z..=max.z {
+                let height = self.get_height(aabb, x, z);
+
+                for y in 0..height {
+                    let fraction = (y + 1) as f32 / aabb.dimensions.y as f32;
+
+                    if let Some(tile) = self.get_tile_at(fraction) {
+                        octree.insert(TileData::new(tile, Point::new(x, min.y + y, z))).ok();
+                    }
+                }
+            }
+        }
+
+        octree.compress();
+
+        octree
+    }
+
+    /// Returns the points on top of the generated terrain where trees go, only picking columns whose top is in the bottom layer
+    pub fn get_tree_points(&self, aabb: AABB) -> Vec<Point> {
+        let min = aabb.get_min();
+        let max = aabb.get_max();
+
+        let bottom_layer = self.layers.first().map_or(0., |layer| layer.up_to);
+        let tree_seed = hash(self.seed, 0x7472_6565, 0);
+
+        let mut points = Vec::new();
+
+        for x in min.x..=max.x {
+            for z in min.z..=max.z {
+                let height = self.get_height(aabb, x, z);
+
+                if height as f32 / aabb.dimensions.y as f32 <= bottom_layer && random(tree_seed, x, z) < self.tree_density {
+                    points.push(Point::new(x, min.y + height, z));
+                }
+            }
+        }
+
+        points
+    }
+
+    /// Returns the actor insertion for trees scattered over the generated terrain, using the tree definition from the actor palette.
+    /// Trees are made by whoever generates the terrain so that they have the same ActorIDs on every client.
+    pub fn get_tree_change(&self, aabb: AABB) -> Option<ActorChange> {
+        if self.tree_density <= 0. {
+            return None
+        }
+
+        let points = self.get_tree_points(aabb);
+
+        if points.is_empty() {
+            return None
+        }
+
+        let palette_world = ActorPalette::get_world()?;
+        let palette_world = palette_world.borrow();
+        let palette_world = palette_world.as_ref()?;
+
+        let mut query = <(Entity, Read<Actor>, Read<Bounds>)>::query();
+
+        let (tree_entity, bounds) = query.iter(palette_world)
+            .find(|(_, actor, _)| actor.0 == TREE_ACTOR)
+            .map(|(entity, _, bounds)| (*entity, *bounds))?;
+
+        //actors are positioned by the center of their bounds, so shift them up to stand on the point
+        let offset = -bounds.get_scaled_and_rotated_aabb(Rotation3::identity()).get_min();
+
+        let mut actor_world = World::default();
+
+        actor::MERGER.with(|m| {
+            let mut merger = m.borrow_mut();
+
+            points.into_iter().for_each(|point| {
+                let entity = actor_world.clone_from_single(palette_world, tree_entity, &mut *merger);
+
+                if let Some(mut entry) = actor_world.entry(entity) {
+                    entry.add_component(ActorID::new());
+                    entry.add_component(CoordPos::new(point + offset));
+                    entry.add_component(Rotation::default());
+                }
+            });
+        });
+
+        actor::serialize_actors_in_world(&mut actor_world).ok()
+            .map(|serialized| ActorChange::ActorInsertion { serialized })
+    }
+}
+
+/// Replaces everything within aabb with generated terrain, along with any trees, as a single step in the history of store_history
+pub fn generate(world: &mut World, resources: &mut Resources, aabb: AABB, settings: &GeneratorSettings, trees: Option<ActorChange>, store_history: Option<u32>) {
+    let map = match resources.get::<Map>() {
+        Some(map) => *map,
+        None => return
+    };
+
+    history::group_steps(world, resources, store_history, |world, resources| {
+        map.change(world, resources, settings.get_generated_octree(aabb), store_history);
+
+        if let Some(trees) = &trees {
+            actor::change(world, resources, trees, store_history);
+        }
+    });
+}
+
+/// Returns smoothed value noise from 0 to 1, summed over octaves that each have double the frequency and half the amplitude of the last
+pub fn fractal_noise(seed: u64, x: f32, z: f32, octaves: u32) -> f32 {
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+    let mut max = 0.;
+
+    for octave in 0..octaves.max(1) {
+        total += value_noise(hash(seed, octave as i32, 0), x * frequency, z * frequency) * amplitude;
+        max += amplitude;
+
+        amplitude /= 2.;
+        frequency *= 2.;
+    }
+
+    total / max
+}
+
+/// Returns noise from 0 to 1 that is interpolated between random values at each whole number
+fn value_noise(seed: u64, x: f32, z: f32) -> f32 {
+    let x0 = x.floor() as i32;
+    let z0 = z.floor() as i32;
+
+    let smooth = |t: f32| t * t * (3. - 2. * t);
+
+    let tx = smooth(x - x0 as f32);
+    let tz = smooth(z - z0 as f32);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    lerp(
+        lerp(random(seed, x0, z0), random(seed, x0 + 1, z0), tx),
+        lerp(random(seed, x0, z0 + 1), random(seed, x0 + 1, z0 + 1), tx),
+        tz
+    )
+}
+
+/// Returns a random number from 0 to 1 that is always the same for the same seed and coordinates
+fn random(seed: u64, x: i32, z: i32) -> f32 {
+    (hash(seed, x, z) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// SplitMix64 over the seed and coordinates, which only needs to be well mixed enough for terrain
+fn hash(seed: u64, x: i32, z: i32) -> u64 {
+    let mut value = seed
+        ^ (x as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u32 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    value ^ (value >> 31)
+}
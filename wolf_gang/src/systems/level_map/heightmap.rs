@@ -0,0 +1,285 @@
+use gdnative::prelude::*;
+use gdnative::api::{
+    File,
+    Image,
+};
+use legion::*;
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+
+use crate::collections::octree::{self, PointData};
+
+use super::{Map, MapChunkData, TileData, TILE_DIMENSIONS, fill_octree_from_aabb, mesher};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// The end of the file name that a colour map next to a heightmap has, so that "hills.png" is coloured by "hills_colors.png"
+pub const COLOR_MAP_SUFFIX: &str = "_colors.png";
+
+/// Returns the path of the colour map that goes with the heightmap at path
+pub fn get_color_map_path(path: &str) -> String {
+    format!("{}{}", path.trim_end_matches(".png"), COLOR_MAP_SUFFIX)
+}
+
+/// Resource holding how heightmap images get converted to and from tiles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeightmapSettings {
+    /// The height in world units that a white pixel stands for
+    pub max_height: f32,
+    /// Tiles to use for each colour of a colour map, picking whichever colour is closest to the pixel's
+    pub tile_colors: Vec<([u8; 3], u32)>,
+}
+
+impl Default for HeightmapSettings {
+    fn default() -> Self {
+        HeightmapSettings {
+            max_height: 16.,
+            tile_colors: vec![
+                ([0, 128, 0], 0),
+                ([128, 128, 128], 2),
+                ([255, 255, 255], 7),
+            ],
+        }
+    }
+}
+
+impl HeightmapSettings {
+
+    /// Returns how many tiles high a column is for a pixel's brightness from 0 to 1
+    pub fn get_tile_height(&self, brightness: f32) -> i32 {
+        (brightness * self.max_height / TILE_DIMENSIONS.y).round() as i32
+    }
+
+    /// Returns the brightness from 0 to 1 of a pixel for a column that is height tiles high
+    pub fn get_brightness(&self, height: i32) -> f32 {
+        (height as f32 * TILE_DIMENSIONS.y / self.max_height).max(0.).min(1.)
+    }
+
+    /// Returns the first colour in tile_colors that is used for tile
+    pub fn get_color_for_tile(&self, tile: u32) -> Option<[u8; 3]> {
+        self.tile_colors.iter()
+            .find(|(_, tile_color_tile)| *tile_color_tile == tile)
+            .map(|(color, _)| *color)
+    }
+
+    /// Returns the tile for the colour in tile_colors that is closest to color
+    pub fn get_tile_for_color(&self, color: Color) -> Option<u32> {
+        let rgb = [color.r * 255., color.g * 255., color.b * 255.];
+
+        self.tile_colors.iter()
+            .map(|(tile_color, tile)| {
+                let distance = tile_color.iter().zip(rgb.iter())
+                    .map(|(a, b)| (*a as f32 - b).powi(2))
+                    .sum::<f32>();
+
+                (distance, *tile)
+            })
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, tile)| tile)
+    }
+}
+
+/// The height and tile of each column in a rectangle of the map, with x along the width and z along the depth
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heightmap {
+    width: i32,
+    depth: i32,
+    heights: Vec<i32>,
+    tiles: Vec<u32>,
+}
+
+impl Heightmap {
+
+    /// Makes a heightmap out of the heights and tiles of each column, going along the width first
+    pub fn new(width: i32, depth: i32, heights: Vec<i32>, tiles: Vec<u32>) -> Self {
+        Heightmap {
+            width,
+            depth,
+            heights,
+            tiles
+        }
+    }
+
+    fn get_index(&self, x: i32, z: i32) -> usize {
+        (x + z * self.width) as usize
+    }
+
+    /// Reads a grayscale image, one column per pixel. Columns use tile, unless there is a colour map next to the image in which case
+    /// each column uses the tile closest to its colour.
+    pub fn from_image<S: ToString>(path: S, settings: &HeightmapSettings, tile: u32) -> Result<Self, GodotError> {
+        let path = path.to_string();
+
+        let image = Image::new();
+        image.load(path.clone())?;
+
+        let width = image.get_width() as i32;
+        let depth = image.get_height() as i32;
+
+        let color_map_path = get_color_map_path(&path);
+        let color_map = if File::new().file_exists(color_map_path.clone()) {
+            let color_map = Image::new();
+            color_map.load(color_map_path)?;
+
+            Some(color_map)
+        } else {
+            None
+        };
+
+        image.lock();
+        if let Some(color_map) = &color_map {
+            color_map.lock();
+        }
+
+        let mut heightmap = Heightmap {
+            width,
+            depth,
+            heights: Vec::with_capacity((width * depth) as usize),
+            tiles: Vec::with_capacity((width * depth) as usize),
+        };
+
+        for z in 0..depth {
+            for x in 0..width {
+                let pixel = image.get_pixel(x as i64, z as i64);
+
+                heightmap.heights.push(settings.get_tile_height(pixel.r));
+
+                let tile = color_map.as_ref()
+                    .filter(|color_map| x < color_map.get_width() as i32 && z < color_map.get_height() as i32)
+                    .and_then(|color_map| settings.get_tile_for_color(color_map.get_pixel(x as i64, z as i64)))
+                    .unwrap_or(tile);
+
+                heightmap.tiles.push(tile);
+            }
+        }
+
+        image.unlock();
+        if let Some(color_map) = &color_map {
+            color_map.unlock();
+        }
+
+        Ok(heightmap)
+    }
+
+    /// Reads the top surface of every map chunk, returning it along with the minimum corner of the range that it covers. The height of each
+    /// column is where the ground starting from the lowest tile of that column ends, so anything floating above it is left out.
+    pub fn from_map(world: &World, map: Map) -> Option<(Self, Point)> {
+        let mut query = <Read<MapChunkData>>::query();
+
+        let chunk_octrees = query.iter(world)
+            .map(|map_data| (map_data.get_chunk_point(), map_data.octree.clone()))
+            .collect::<mesher::ChunkOctrees>();
+
+        //the lowest tile of each column, which is where the climb to the top starts from
+        let mut bottoms: HashMap<(i32, i32), Point> = HashMap::new();
+
+        chunk_octrees.values().for_each(|octree| {
+            octree.iter().for_each(|tile_data| {
+                let point = tile_data.get_point();
+
+                let bottom = bottoms.entry((point.x, point.z)).or_insert(point);
+                if point.y < bottom.y {
+                    *bottom = point;
+                }
+            });
+        });
+
+        let mut points = bottoms.values();
+        let first = *points.next()?;
+
+        let range = points.fold(AABB::from_extents(first, first), |range, point| range.union(AABB::from_extents(*point, *point)));
+        let min = range.get_min();
+
+        let mut heightmap = Heightmap {
+            width: range.dimensions.x,
+            depth: range.dimensions.z,
+            heights: vec![0; (range.dimensions.x * range.dimensions.z) as usize],
+            tiles: vec![0; (range.dimensions.x * range.dimensions.z) as usize],
+        };
+
+        bottoms.values().for_each(|bottom| {
+            let chunk_point = map.get_chunk_range(AABB::from_extents(*bottom, *bottom)).get_min();
+
+            if let Some(octree) = chunk_octrees.get(&chunk_point) {
+                let top = mesher::get_true_top(*bottom, &chunk_octrees, octree);
+                let index = heightmap.get_index(bottom.x - min.x, bottom.z - min.z);
+
+                heightmap.heights[index] = top.y - min.y;
+                heightmap.tiles[index] = octree.query_point(*bottom).map_or(0, |tile_data| tile_data.get_tile());
+            }
+        });
+
+        Some((heightmap, min))
+    }
+
+    /// Saves the heights as a grayscale image, one pixel per column, along with a colour map of the tiles next to it so that importing the
+    /// image again gives back the same tiles. Tiles that don't have a colour in tile_colors come out black.
+    pub fn save_png<S: ToString>(&self, path: S, settings: &HeightmapSettings) -> Result<(), GodotError> {
+        let path = path.to_string();
+
+        let image = Image::new();
+        image.create(self.width as i64, self.depth as i64, false, Image::FORMAT_L8);
+
+        let color_map = Image::new();
+        color_map.create(self.width as i64, self.depth as i64, false, Image::FORMAT_RGB8);
+
+        image.lock();
+        color_map.lock();
+
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                let index = self.get_index(x, z);
+                let brightness = settings.get_brightness(self.heights[index]);
+
+                image.set_pixel(x as i64, z as i64, Color::rgb(brightness, brightness, brightness));
+
+                let [r, g, b] = settings.get_color_for_tile(self.tiles[index]).unwrap_or([0, 0, 0]);
+
+                color_map.set_pixel(x as i64, z as i64, Color::rgb(r as f32 / 255., g as f32 / 255., b as f32 / 255.));
+            }
+        }
+
+        image.unlock();
+        color_map.unlock();
+
+        image.save_png(path.clone())?;
+        color_map.save_png(get_color_map_path(&path))
+    }
+
+    /// Returns the octree to change the map with, which starts from min and covers up to the tallest column so that anything already there
+    /// is replaced
+    pub fn get_octree(&self, min: Point) -> Option<Octree> {
+        let max_height = *self.heights.iter().max()?;
+
+        if self.width < 1 || self.depth < 1 || max_height < 1 {
+            return None
+        }
+
+        let aabb = AABB::from_extents(min, min + Point::new(self.width - 1, max_height - 1, self.depth - 1));
+
+        let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
+
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                let index = self.get_index(x, z);
+                let height = self.heights[index];
+
+                if height < 1 {
+                    continue
+                }
+
+                let column = AABB::from_extents(min + Point::new(x, 0, z), min + Point::new(x, height - 1, z));
+
+                fill_octree_from_aabb(column, Some(TileData::new(self.tiles[index], Point::zeros()))).iter().for_each(|tile_data| {
+                    octree.insert(*tile_data).ok();
+                });
+            }
+        }
+
+        octree.compress();
+
+        Some(octree)
+    }
+}
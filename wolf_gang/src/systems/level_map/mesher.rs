@@ -0,0 +1,1150 @@
+//! Turns the octrees of map chunks into plain vertex and index buffers. Nothing in here touches Godot or legion, so the geometry can be
+//! tested on its own and reused by anything that needs the map's mesh.
+
+use std::sync::{Mutex, mpsc};
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+
+use crate::collections::octree::{self, PointData};
+
+use super::{TileData, TILE_DIMENSIONS, map_coords_to_world, shape, tile_definitions::{TileDefinitions, WallRepeat}};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+type Vector2D = nalgebra::Vector2<f32>;
+
+const BEVEL_SIZE: f32 = 0.2;
+const BEVEL_HEIGHT: f32 = 0.1;
+
+lazy_static!{
+    pub static ref NEIGHBOR_DIRS: [Point; 8] = [
+            Point::x(),
+            -Point::x(),
+            Point::z(),
+            -Point::z(),
+            Point::x()+Point::z(),
+            -Point::x()+Point::z(),
+            -Point::x()-Point::z(),
+            Point::x()-Point::z()
+        ];
+}
+
+/// The octrees of every chunk in the map, keyed by their chunk point, so that the mesher can look across chunk borders
+pub type ChunkOctrees = HashMap<Point, Octree>;
+
+/// The vertices of a column kept apart by the material their tiles get drawn with, in the order the materials first show up
+pub type MaterialVertexData = Vec<(String, VertexData)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexData {
+    pub verts: Vec<Vector3D>,
+    pub normals: Vec<Vector3D>,
+    pub uvs: Vec<Vector2D>,
+    pub uv2s: Vec<Vector2D>,
+    pub indices: Vec<i32>,
+}
+
+impl VertexData {
+    pub fn replace(&mut self, other: VertexData) {
+        self.verts.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        self.uv2s.clear();
+        self.indices.clear();
+
+        self.verts.extend(other.verts);
+        self.normals.extend(other.normals);
+        self.uvs.extend(other.uvs);
+        self.uv2s.extend(other.uv2s);
+        self.indices.extend(other.indices);
+    }
+
+    /// Adds the vertices of other after the ones already here, offsetting its indices to match
+    pub fn append(&mut self, other: &VertexData) {
+        let offset = self.verts.len() as i32;
+
+        self.verts.extend(other.verts.iter());
+        self.normals.extend(other.normals.iter());
+        self.uvs.extend(other.uvs.iter());
+        self.uv2s.extend(other.uv2s.iter());
+        self.indices.extend(other.indices.iter().map(|i| i + offset));
+    }
+}
+
+/// Returns the chunk point of a chunk from the range of its octree
+pub fn get_chunk_point(octree: &Octree) -> Point {
+    let aabb = octree.get_aabb();
+    let min = aabb.get_min();
+    let dimensions = aabb.dimensions;
+
+    Point::new(
+        (min.x as f32 / dimensions.x as f32).floor() as i32,
+        (min.y as f32 / dimensions.y as f32).floor() as i32,
+        (min.z as f32 / dimensions.z as f32).floor() as i32,
+    )
+}
+
+/// Returns the index that the column at x and z has in the columns of the chunk
+pub fn get_column_index(octree: &Octree, x: i32, z: i32) -> usize {
+    let aabb = octree.get_aabb();
+    let min = aabb.get_min();
+
+    ((x - min.x) + aabb.dimensions.x * (z - min.z)) as usize
+}
+
+/// Returns the tile at point, looking in whichever chunk it falls into
+pub fn get_tile(chunks: &ChunkOctrees, octree: &Octree, point: Point) -> Option<TileData> {
+    let aabb = octree.get_aabb();
+
+    if aabb.contains_point(point) {
+        return octree.query_point(point)
+    }
+
+    let dimensions = aabb.dimensions;
+    let chunk_point = Point::new(
+        (point.x as f32 / dimensions.x as f32).floor() as i32,
+        (point.y as f32 / dimensions.y as f32).floor() as i32,
+        (point.z as f32 / dimensions.z as f32).floor() as i32,
+    );
+
+    chunks.get(&chunk_point).and_then(|octree| octree.query_point(point))
+}
+
+/// Returns the vertices of every column of the chunk within range, along with each column's index in the chunk
+pub fn mesh_range(chunks: &ChunkOctrees, octree: &Octree, range: AABB, definitions: &TileDefinitions) -> Vec<(usize, MaterialVertexData)> {
+    let range_min = range.get_min();
+    let area = range.dimensions.x * range.dimensions.z;
+
+    let checked: Mutex<HashSet<Point>> = Mutex::new(HashSet::new());
+
+    (0..area).into_par_iter().map(|i| {
+
+        let x = (i % range.dimensions.x) + range_min.x;
+        let z = (i / range.dimensions.x) + range_min.z;
+
+        (get_column_index(octree, x, z), mesh_column(chunks, octree, x, z, &checked, definitions))
+
+    }).collect()
+}
+
+/// Returns the vertices of the whole chunk with every material together, with the columns in order
+pub fn mesh_chunk(chunks: &ChunkOctrees, octree: &Octree, definitions: &TileDefinitions) -> VertexData {
+    let mut columns = mesh_range(chunks, octree, octree.get_aabb(), definitions);
+    columns.sort_by_key(|(index, _)| *index);
+
+    let mut vertex_data = VertexData::default();
+
+    columns.iter().for_each(|(_, column)| vertex_data.append(&merge_materials(column)));
+
+    vertex_data
+}
+
+/// Returns the vertices of every material together
+pub fn merge_materials(material_vertex_data: &[(String, VertexData)]) -> VertexData {
+    let mut vertex_data = VertexData::default();
+
+    material_vertex_data.iter().for_each(|(_, material_data)| vertex_data.append(material_data));
+
+    vertex_data
+}
+
+/// Returns the vertices of a single column of the chunk. Full tiles that have been worked on get added to checked, so that other columns can
+/// skip looking them up.
+fn mesh_column(chunks: &ChunkOctrees, octree: &Octree, x: i32, z: i32, checked: &Mutex<HashSet<Point>>, definitions: &TileDefinitions) -> MaterialVertexData {
+    let aabb = octree.get_aabb();
+    let max = aabb.get_max();
+    let min = aabb.get_min();
+
+    let (checked_tx, checked_rx) = mpsc::channel::<Point>();
+
+    let tiles = octree.query_range(AABB::from_extents(Point::new(x, min.y, z), Point::new(x, max.y, z)));
+
+    let tile_vertex_datas = tiles.par_iter()
+        .map_with(checked_tx, |checked_tx, tile| {
+
+            let checked = {
+                let checked_lock = checked.lock().unwrap();
+
+                if tile.get_shape().is_full() {
+                    checked_tx.send(tile.get_point()).unwrap();
+                }
+
+                checked_lock.clone()
+            };
+
+            mesh_tile(chunks, octree, *tile, &checked, checked_tx, definitions)
+                .map(|tile_vertex_data| (definitions.get_material(tile.get_tile()), tile_vertex_data))
+        })
+        .collect::<Vec<Option<(&str, VertexData)>>>();
+
+    let mut material_vertex_data = MaterialVertexData::new();
+
+    tile_vertex_datas.iter().flatten().for_each(|(material, tile_vertex_data)| {
+        match material_vertex_data.iter_mut().find(|(name, _)| name == material) {
+            Some((_, vertex_data)) => vertex_data.append(tile_vertex_data),
+            None => material_vertex_data.push((material.to_string(), tile_vertex_data.clone()))
+        }
+    });
+
+    checked.lock().unwrap().extend(checked_rx);
+
+    material_vertex_data
+}
+
+/// Returns the vertices for the piece of the column that has tile at its top, or None if tile isn't the top of a piece. Tiles that aren't full
+/// are always a piece of their own.
+fn mesh_tile(chunks: &ChunkOctrees, octree: &Octree, tile: TileData, checked: &HashSet<Point>, checked_tx: &mpsc::Sender<Point>, definitions: &TileDefinitions) -> Option<VertexData> {
+
+    if !tile.get_shape().is_full() {
+        return Some(mesh_shaped_tile(chunks, octree, tile, definitions))
+    }
+
+    let aabb = octree.get_aabb();
+    let max = aabb.get_max();
+    let min = aabb.get_min();
+
+    let point = tile.get_point();
+    let tile_selection = tile.get_tile();
+    let wall_repeat = definitions.get_wall_repeat(tile_selection);
+    let tile_size = definitions.get_tile_size();
+
+    let mut true_top: Option<Vector3D> = None;
+
+    let mut draw_top: bool = true;
+
+    // If a column extends all the way down to some visibile faces, the column must overlap the face of the tile it "lands" on, with the given sides
+    let mut must_connect: Option<HashSet<Point>> = None;
+
+    let point_sides = get_open_sides(chunks, octree, point, checked);
+
+    let point_above = point + Point::y();
+
+    //If this tile does not match any of the conditions that would make it a top facing tile
+    if let false = match octree.query_point(point_above) {
+        //the shaped tile above covers the top, but the walls still have to reach up to it
+        Some(tile_above) if !tile_above.get_shape().is_full() => {
+            draw_top = false;
+            true
+        },
+        Some(_) => {
+            let curr_sides = get_open_sides(chunks, octree, point_above, checked);
+
+            if curr_sides.symmetric_difference(&point_sides).count() > 0 {
+                //if there are more point_sides than curr_sides, ie: if more sides are covered as we go up
+                if curr_sides.difference(&point_sides).count() == 0 {
+                    draw_top = false;
+                } else {
+                    must_connect = Some(curr_sides);
+                }
+                true
+            } else {
+
+                let point_y_in_world = point_above.y as f32 * TILE_DIMENSIONS.y;
+                let subdivide_for_repeat = is_a_subdivision(point_y_in_world, wall_repeat);
+
+                if subdivide_for_repeat {
+                    draw_top = false;
+                    true
+                } else {
+                    let tt = map_coords_to_world(get_true_top(point, chunks, octree));
+                    true_top = Some(tt);
+
+                    let diff = tt.y - 1. - map_coords_to_world(point_above).y;
+
+                    //if approx zero
+                    if diff > -std::f32::EPSILON && diff < std::f32::EPSILON {
+                        draw_top = false;
+                        true
+                    } else {
+                        false
+                    }
+                }
+            }
+        },
+        None if point_above.y > max.y => {
+
+            let chunk_point_above = get_chunk_point(octree)+Point::y();
+
+            if let Some(octree) = chunks.get(&chunk_point_above) {
+                if octree.query_point(point_above).map_or(false, |tile_above| !tile_above.get_shape().is_full()) {
+                    draw_top = false;
+                } else if octree.query_point(point_above).is_some() {
+
+                    let curr_sides = get_open_sides(chunks, octree, point_above, checked);
+
+                    if curr_sides.symmetric_difference(&point_sides).count() > 0 {
+                        //if there are more point_sides than curr_sides, ie: if more sides are covered as we go up
+                        if curr_sides.difference(&point_sides).count() == 0 {
+                            draw_top = false;
+                        }
+
+                    } else {
+                        draw_top = false;
+                    }
+                }
+            }
+            true
+        }
+        None => true
+    } {
+        return None
+    }
+
+    let mut offset = 0;
+
+    // We do this as a SLIGHT optimization, there's no sense in calculating this for EVERY tile if it's not going to be worked on
+    // but it's possible it was already calculated when determining the top. If it wasn't, we have to do it now
+    if true_top.is_none() {
+        true_top = Some(map_coords_to_world(get_true_top(point, chunks, octree)));
+    }
+
+    let mut bottom = point;
+
+    //Get the bottom of this piece
+    for y in (min.y-1..point.y).rev() {
+
+        let point_below = Point::new(point.x, y, point.z);
+
+        match octree.query_point(point_below) {
+            Some(tile_below) if !tile_below.get_shape().is_full() => break,
+            Some(_) => {
+
+                let curr_sides = get_open_sides(chunks, octree, point_below, checked);
+
+                if curr_sides.symmetric_difference(&point_sides).count() > 0 {
+                    break;
+                } else {
+
+                    let point_y_in_world = bottom.y as f32 * TILE_DIMENSIONS.y;
+                    let subdivide_for_repeat = is_a_subdivision(point_y_in_world, wall_repeat);
+
+                    if subdivide_for_repeat {
+                        break;
+                    }
+                    let tt = true_top.unwrap();
+                    if map_coords_to_world(point).y >= tt.y - 1. && tt.y - 1. > map_coords_to_world(point_below).y {
+                        break;
+                    }
+                }
+
+                checked_tx.send(point_below).unwrap();
+                bottom = point_below;
+            },
+            None if y < min.y => {
+
+                let chunk_point_below = get_chunk_point(octree) - Point::y();
+
+                if let Some(octree) = chunks.get(&chunk_point_below) {
+
+                    if octree.query_point(point_below).map_or(false, |tile_below| tile_below.get_shape().is_full()) {
+                        let curr_sides = get_open_sides(chunks, octree, point_below, checked);
+
+                        if curr_sides.symmetric_difference(&point_sides).count() > 0 {
+
+                            //if there are more points in point_sides than the current_sides. ie: if sides are getting covered as we go down
+                            if point_sides.difference(&curr_sides).count() > 0 {
+                                bottom = point_below;
+                            }
+                        }
+                    }
+                }
+            },
+            None => break
+        }
+    }
+
+    let world_point = map_coords_to_world(point);
+
+    let top_left = Vector3D::new(world_point.x, world_point.y+TILE_DIMENSIONS.y, world_point.z+TILE_DIMENSIONS.z);
+    let top_right = Vector3D::new(world_point.x+TILE_DIMENSIONS.x, world_point.y+TILE_DIMENSIONS.y, world_point.z+TILE_DIMENSIONS.z);
+    let bottom_left = Vector3D::new(world_point.x, world_point.y+TILE_DIMENSIONS.y, world_point.z);
+    let bottom_right = Vector3D::new(world_point.x+TILE_DIMENSIONS.x, world_point.y+TILE_DIMENSIONS.y, world_point.z);
+
+    let center = bottom_left + (top_right - bottom_left) / 2.;
+
+    let mut vertex_data = VertexData::default();
+
+    let tile_offset = definitions.get_uv_offset(definitions.get_top(tile_selection));
+    let (tile_col_offset, tile_row_offset) = (tile_offset.x, tile_offset.y);
+
+    // if there are no open sides, all we have to draw is a simple 2 triangle face
+    if point_sides.is_empty() {
+
+        if draw_top {
+
+            vertex_data.verts.extend(&[
+                top_right,
+                top_left,
+                bottom_left,
+                bottom_right
+            ]);
+
+            vertex_data.uvs.extend(&[
+                Vector2D::new(tile_size + tile_col_offset, tile_size + tile_row_offset),
+                Vector2D::new(tile_col_offset, tile_size + tile_row_offset),
+                Vector2D::new(tile_col_offset, tile_row_offset),
+                Vector2D::new(tile_size + tile_col_offset, tile_row_offset)
+            ]);
+
+            vertex_data.uv2s.extend(&[
+                Vector2D::zeros(),
+                Vector2D::zeros(),
+                Vector2D::zeros(),
+                Vector2D::zeros(),
+            ]);
+
+            vertex_data.normals.extend(&[
+                Vector3D::y(),
+                Vector3D::y(),
+                Vector3D::y(),
+                Vector3D::y(),
+            ]);
+
+            vertex_data.indices.extend(&[
+                2,0,1,
+                3,0,2
+            ]);
+        }
+    } else { //if open_sides is not empty, draw a more complex face to account for the bevel
+        let corners = [
+            top_right,
+            top_left,
+            bottom_left,
+            bottom_right
+        ];
+
+        let mut connect_points: Vec<Vector3D> = Vec::with_capacity(12);
+        let mut face_points: Vec<Vector3D> = Vec::with_capacity(12);
+
+        let corners_len = corners.len();
+        for i in 0..corners_len {
+
+            let right = corners[i];
+            let left = corners[(i + 1) % corners_len];
+
+            define_verts_from_sides(&point_sides, left, right, center, &mut face_points);
+
+            if let Some(sides) = &must_connect {
+                define_verts_from_sides(sides, left, right, center, &mut connect_points);
+            }
+        }
+
+        let mut face_points_final: Vec<Vector3D> = Vec::with_capacity(12);
+        //keep track of the indices of the face points so that we can use them again
+        // in the bezel curve for the top face
+        let mut face_point_indices: Vec<i32> = Vec::with_capacity(12);
+
+        let face_points_len = face_points.len();
+        for i in 0..face_points_len {
+
+            let right = face_points[i];
+            let left = face_points[(i + 1) % face_points_len];
+
+            if (right - left).norm() > std::f32::EPSILON {
+                face_points_final.push(right);
+            }
+        }
+
+        vertex_data.verts.push(center);
+        vertex_data.uvs.push(Vector2D::new(tile_size / 2. + tile_col_offset, tile_size / 2. + tile_row_offset));
+        vertex_data.uv2s.push(Vector2D::zeros());
+        vertex_data.normals.push(Vector3D::y());
+        offset += 1;
+
+        let face_points_final_len = face_points_final.len();
+        let begin = offset;
+        for (i, right) in face_points_final.iter().enumerate() {
+
+            let u = (right.x - world_point.x).abs() * tile_size;
+            let v = (right.z - world_point.z).abs() * tile_size;
+
+            if draw_top {
+                vertex_data.verts.push(*right);
+                vertex_data.uvs.push(Vector2D::new(u + tile_col_offset, v + tile_row_offset));
+                vertex_data.uv2s.push(Vector2D::zeros());
+                vertex_data.normals.push(Vector3D::y());
+
+                face_point_indices.push(begin + i as i32);
+
+                offset += 1;
+
+                if i > 0 && i < face_points_final_len - 1 {
+                    vertex_data.indices.push(begin);
+                    vertex_data.indices.push(begin + i as i32);
+                    vertex_data.indices.push(begin + (i as i32 + 1) % face_points_final_len as i32);
+                }
+            }
+        }
+
+        let connect_points_len = connect_points.len();
+
+        let mut connect_points_final: Vec<Vector3D> = Vec::with_capacity(connect_points_len * 2);
+
+        if let Some(sides) = &must_connect {
+            (0..connect_points_len).for_each(|i| {
+                let right = connect_points[i];
+                let left = connect_points[(i + 1) % connect_points_len];
+
+                let (dir, right_dir, left_dir, right_diag, _) = get_edge_dirs(right, left, center);
+
+                let original_scaled_right = scale_from_origin(right, center, 1./(1.-BEVEL_SIZE));
+                let original_scaled_left = scale_from_origin(left, center, 1./(1.-BEVEL_SIZE));
+
+                //change the origin of our scale for when certain sides are exposed or not
+                let (scaled_left, scaled_right) = adjust_scaled_pts(sides, dir, right_dir, left_dir, right_diag, left, right, center, original_scaled_left, original_scaled_right);
+
+                connect_points_final.extend(&[scaled_right, scaled_left]);
+            });
+        }
+
+        let mut border_points = Vec::with_capacity(face_points_final_len);
+
+        //defining the curve to the top face
+        let begin = offset;
+        for right_index in 0..face_points_final_len {
+
+            let left_index = (right_index + 1) % face_points_final_len;
+
+            let right = face_points_final[right_index];
+            let left = face_points_final[left_index];
+
+            let (dir, right_dir, left_dir, right_diag, left_diag) = get_edge_dirs(right, left, center);
+
+            let original_scaled_right = scale_from_origin(right, center, 1./(1.-BEVEL_SIZE));
+            let original_scaled_left = scale_from_origin(left, center, 1./(1.-BEVEL_SIZE));
+
+            //change the origin of our scale for when certain sides are exposed or not
+            let (mut scaled_left, mut scaled_right) = adjust_scaled_pts(&point_sides, dir, right_dir, left_dir, right_diag, left, right, center, original_scaled_left, original_scaled_right);
+
+            //draw the curves
+            if draw_top {
+
+                let mut scaled_left = scaled_left;
+                let mut scaled_right = scaled_right;
+
+                scaled_right.y -= BEVEL_HEIGHT;
+                scaled_left.y -= BEVEL_HEIGHT;
+
+                let u = (scaled_right.x - world_point.x).abs() * tile_size;
+                let v = (scaled_right.z - world_point.z).abs() * tile_size;
+
+                vertex_data.verts.push(scaled_right);
+                vertex_data.uvs.push(Vector2D::new(u + tile_col_offset, v + tile_row_offset));
+                vertex_data.uv2s.push(Vector2D::zeros());
+
+                let mut normal = (scaled_right + scaled_left) / 2.;
+                normal.y = center.y;
+                normal = (normal - center).normalize();
+
+                vertex_data.normals.push(normal.normalize());
+
+                offset += 1;
+
+                let face_right_index = face_point_indices[right_index];
+                let face_left_index = face_point_indices[left_index];
+
+                if point_sides.contains(&dir) || (!point_sides.contains(&right_dir) && point_sides.contains(&right_diag)) || (!point_sides.contains(&left_dir) && point_sides.contains(&left_diag)){
+                    vertex_data.indices.push(face_left_index);
+                    vertex_data.indices.push(face_right_index);
+                    vertex_data.indices.push(begin + left_index as i32);
+
+                    vertex_data.indices.push(face_right_index);
+                    vertex_data.indices.push(begin + right_index as i32);
+                    vertex_data.indices.push(begin + left_index as i32);
+                }
+            }
+
+            if draw_top {
+                scaled_left.y -= BEVEL_HEIGHT;
+                scaled_right.y -= BEVEL_HEIGHT;
+            }
+
+            border_points.push(scaled_right);
+            border_points.push(scaled_left);
+        }
+
+        let true_top = true_top.unwrap().y;
+
+        if let Some(sides) = &must_connect {
+            let bottom = center.y - BEVEL_HEIGHT;
+            draw_walls(&connect_points_final, sides, &mut vertex_data, center, point, world_point, bottom, true_top, &mut offset, tile_selection, definitions);
+        }
+
+        draw_walls(&border_points, &point_sides, &mut vertex_data, center, point, world_point, map_coords_to_world(bottom).y, true_top, &mut offset, tile_selection, definitions);
+    }
+
+    if is_overhanging(chunks, octree, bottom) {
+        push_underside(&mut vertex_data, map_coords_to_world(bottom), definitions.get_uv_offset(definitions.get_bottom(tile_selection)), tile_size);
+    }
+
+    Some(vertex_data)
+}
+
+/// Returns the vertices of a tile that isn't full, which has no bevels. Its top is drawn unless there is a tile above it, and each of its sides
+/// is drawn unless the neighbouring tile on that side covers it.
+fn mesh_shaped_tile(chunks: &ChunkOctrees, octree: &Octree, tile: TileData, definitions: &TileDefinitions) -> VertexData {
+    let point = tile.get_point();
+    let shape = tile.get_shape();
+
+    let world_point = map_coords_to_world(point);
+    let tile_size = definitions.get_tile_size();
+    let tile_offset = definitions.get_uv_offset(definitions.get_top(tile.get_tile()));
+    let side_offset = definitions.get_uv_offset(definitions.get_side(tile.get_tile()));
+
+    let mut vertex_data = VertexData::default();
+
+    if get_tile(chunks, octree, point + Point::y()).is_none() {
+        shape.get_top_triangles(world_point).iter().for_each(|triangle| {
+            push_triangle(&mut vertex_data, triangle, |vert| {
+                Vector2D::new((vert.x - world_point.x) * tile_size, (vert.z - world_point.z) * tile_size) + tile_offset
+            });
+        });
+    }
+
+    let vert_offset = get_wall_v_offset(world_point.y, definitions.get_wall_repeat(tile.get_tile()), side_offset.y, tile_size);
+
+    NEIGHBOR_DIRS.iter().take(4).for_each(|dir| {
+        let covered = get_tile(chunks, octree, point + dir).map_or(false, |neighbor| shape.is_side_covered_by(*dir, neighbor.get_shape()));
+
+        if !covered {
+            shape.get_side_triangles(world_point, *dir).iter().for_each(|triangle| {
+                push_triangle(&mut vertex_data, triangle, |vert| {
+                    let u = if dir.x != 0 { vert.z } else { vert.x };
+
+                    Vector2D::new(side_offset.x + u * tile_size, -1. - vert.y * tile_size + vert_offset)
+                });
+            });
+        }
+    });
+
+    if is_overhanging(chunks, octree, point) {
+        push_underside(&mut vertex_data, world_point, definitions.get_uv_offset(definitions.get_bottom(tile.get_tile())), tile_size);
+    }
+
+    vertex_data
+}
+
+/// Adds a triangle with its own vertices so that it has a flat normal, getting the uv of each vertex from uv
+fn push_triangle<F: Fn(Vector3D) -> Vector2D>(vertex_data: &mut VertexData, triangle: &[Vector3D; 3], uv: F) {
+    let begin = vertex_data.verts.len() as i32;
+    let normal = shape::get_normal(triangle);
+
+    triangle.iter().for_each(|vert| {
+        vertex_data.verts.push(*vert);
+        vertex_data.normals.push(normal);
+        vertex_data.uvs.push(uv(*vert));
+        vertex_data.uv2s.push(Vector2D::zeros());
+    });
+
+    vertex_data.indices.extend(&[begin, begin + 1, begin + 2]);
+}
+
+/// Adds the underside of the tile whose minimum corner is at min, wound to face down, with the cell starting at cell_offset stretched over it
+fn push_underside(vertex_data: &mut VertexData, min: Vector3D, cell_offset: Vector2D, tile_size: f32) {
+    let (x, z) = (Vector3D::new(TILE_DIMENSIONS.x, 0., 0.), Vector3D::new(0., 0., TILE_DIMENSIONS.z));
+
+    [[min, min + z, min + x + z], [min, min + x + z, min + x]].iter().for_each(|triangle| {
+        push_triangle(vertex_data, triangle, |vert| {
+            Vector2D::new((vert.x - min.x) * tile_size, (vert.z - min.z) * tile_size) + cell_offset
+        });
+    });
+}
+
+/// Returns whether the piece with its bottom at point hangs over another tile further down its column, which is when its underside can be
+/// seen. Looks down through the chunks below for as long as there are chunks.
+fn is_overhanging(chunks: &ChunkOctrees, octree: &Octree, point: Point) -> bool {
+    if get_tile(chunks, octree, point - Point::y()).is_some() {
+        return false
+    }
+
+    let dimensions = octree.get_aabb().dimensions;
+    let mut chunk_point = Point::new(
+        (point.x as f32 / dimensions.x as f32).floor() as i32,
+        (point.y as f32 / dimensions.y as f32).floor() as i32,
+        (point.z as f32 / dimensions.z as f32).floor() as i32,
+    );
+
+    let mut top = point.y - 2;
+
+    while let Some(octree) = chunks.get(&chunk_point) {
+        let min_y = octree.get_aabb().get_min().y;
+
+        if top >= min_y && !octree.query_range(AABB::from_extents(Point::new(point.x, min_y, point.z), Point::new(point.x, top, point.z))).is_empty() {
+            return true
+        }
+
+        top = top.min(min_y - 1);
+        chunk_point.y -= 1;
+    }
+
+    false
+}
+
+/// Get the true top of this vertical column of tiles regardless of chunk subdivisions
+pub fn get_true_top(pt: Point, chunks: &ChunkOctrees, octree: &Octree) -> Point {
+    let mut true_top = pt;
+
+    let chunk_max = octree.get_aabb().get_max();
+    let chunk_min = octree.get_aabb().get_min();
+
+    if true_top.y < chunk_min.y + 1 {
+        true_top.y = chunk_min.y;
+    }
+
+    while true_top.y <= chunk_max.y+1 {
+
+        match octree.query_point(true_top) {
+            Some(_) => {},
+            None if true_top.y > chunk_max.y => {
+                let chunk_pt_above = get_chunk_point(octree)+Point::y();
+
+                match chunks.get(&chunk_pt_above) {
+                    Some(octree) => {
+                        return get_true_top(true_top, chunks, octree);
+                    },
+                    None => break
+                }
+            },
+            None => break
+        }
+
+        true_top.y += 1;
+    }
+
+    true_top
+}
+
+pub fn scale_from_origin(pt: Vector3D, origin: Vector3D, scale_amount: f32) -> Vector3D {
+    let mut pt = pt;
+    pt -= origin;
+    pt *= scale_amount;
+    pt + origin
+}
+
+/// Returns the directions from NEIGHBOR_DIRS that have nothing next to point, looking into neighbouring chunks across the chunk's border.
+/// Anything in checked is known to be a tile already, so it doesn't need to be looked up.
+pub fn get_open_sides(chunks: &ChunkOctrees, octree: &Octree, point: Point, checked: &HashSet<Point>) -> HashSet<Point> {
+    let chunk_max = octree.get_aabb().get_max();
+    let chunk_min = octree.get_aabb().get_min();
+
+    NEIGHBOR_DIRS.iter().filter(|dir| {
+
+        let neighbor = point + **dir;
+
+        //tiles that aren't full leave the side open, since they don't reach all the way up it
+        if checked.contains(&neighbor) || octree.query_point(neighbor).map_or(false, |tile| tile.get_shape().is_full()) {
+            return false
+        }
+
+        if octree.get_aabb().contains_point(neighbor) {
+            return true
+        }
+
+        let mut adj_dir = **dir;
+
+        if neighbor.x > chunk_max.x {
+            adj_dir.x = 1;
+        } else if neighbor.x < chunk_min.x {
+            adj_dir.x = -1;
+        } else {
+            adj_dir.x = 0;
+        }
+
+        if neighbor.z > chunk_max.z {
+            adj_dir.z = 1;
+        } else if neighbor.z < chunk_min.z {
+            adj_dir.z = -1;
+        } else {
+            adj_dir.z = 0;
+        }
+
+        let chunk_point_dir = get_chunk_point(octree) + adj_dir;
+
+        match chunks.get(&chunk_point_dir) {
+            Some(octree) => octree.query_point(neighbor).map_or(true, |tile| !tile.get_shape().is_full()),
+            None => true
+        }
+    }).copied().collect()
+}
+
+fn is_a_subdivision(point_y: f32, wall_repeat: WallRepeat) -> bool {
+    (point_y >= wall_repeat.start_above && (point_y % wall_repeat.repeat_above - wall_repeat.start_above) % wall_repeat.repeat_above == 0.)
+        || (point_y <= wall_repeat.start_below && point_y % wall_repeat.repeat_below == 0.)
+}
+
+/// Get the direction the average of two points are from the center. For calculating the orthogonal direction of edges.
+fn get_direction_of_edge(pt1: Vector3D, pt2: Vector3D, center: Vector3D) -> Point {
+    let right_dir = Vector3D::new(1.,0.,0.);
+    let forward_dir = Vector3D::new(0.,0.,1.);
+    let back_dir = -forward_dir;
+    let left_dir = -right_dir;
+
+    let mut average = (pt1 + pt2) / 2.;
+    average.y = 0.;
+
+    let heading = average - center;
+
+    //because normalize returns NaN if the distance btwn points is zero
+    if heading.norm() < std::f32::EPSILON {
+        return Point::zeros();
+    }
+
+    let average_dir = heading.normalize();
+
+    let compare = |lh: &Vector3D, rh: &Vector3D| lh.dot(&average_dir).partial_cmp(&rh.dot(&average_dir)).unwrap();
+
+    let dir = std::cmp::max_by(forward_dir,
+        std::cmp::max_by(left_dir,
+            std::cmp::max_by(back_dir, right_dir, compare),
+            compare),
+        compare
+    );
+
+    Point::new(dir.x as i32, dir.y as i32, dir.z as i32)
+}
+
+/// Returns the direction of the edge between right and left, then the directions a quarter turn to either side of it, then the diagonals an
+/// eighth of a turn to either side of it
+fn get_edge_dirs(right: Vector3D, left: Vector3D, center: Vector3D) -> (Point, Point, Point, Point, Point) {
+    let dir = get_direction_of_edge(right, left, center);
+    let dir_f = Vector3D::new(dir.x as f32, dir.y as f32, dir.z as f32);
+
+    let right_dir = nalgebra::Rotation3::<f32>::from_axis_angle(&Vector3D::y_axis(), std::f32::consts::FRAC_PI_2) * dir_f;
+    let right_dir = Point::new(right_dir.x as i32, right_dir.y as i32, right_dir.z as i32);
+
+    let left_dir = -right_dir;
+
+    let right_diag = nalgebra::Rotation3::<f32>::from_axis_angle(&Vector3D::y_axis(), std::f32::consts::FRAC_PI_4) * dir_f;
+    let right_diag = Point::new(right_diag.x.round() as i32, right_diag.y.round() as i32, right_diag.z.round() as i32);
+
+    let left_diag = nalgebra::Rotation3::<f32>::from_axis_angle(&Vector3D::y_axis(), -std::f32::consts::FRAC_PI_4) * dir_f;
+    let left_diag = Point::new(left_diag.x.round() as i32, left_diag.y.round() as i32, left_diag.z.round() as i32);
+
+    (dir, right_dir, left_dir, right_diag, left_diag)
+}
+
+/// Returns the point halfway along the side of the tile in direction dir, turned by angle about the center
+fn get_rotated_middle(dir: Point, center: Vector3D, angle: f32) -> Vector3D {
+    let middle = if dir.x.abs() > dir.z.abs() {
+        Vector3D::new(dir.x as f32, dir.y as f32, dir.z as f32) * TILE_DIMENSIONS.x / 2.
+    } else {
+        Vector3D::new(dir.x as f32, dir.y as f32, dir.z as f32) * TILE_DIMENSIONS.z / 2.
+    };
+
+    nalgebra::Rotation3::<f32>::from_axis_angle(&Vector3D::y_axis(), angle) * middle + center
+}
+
+//allowed because this is just a function that gets called here
+#[allow(clippy::too_many_arguments)] fn adjust_scaled_pts(open_sides: &HashSet<Point>,
+    dir: Point,
+    right_dir: Point,
+    left_dir: Point,
+    right_diag: Point,
+    left: Vector3D,
+    right: Vector3D,
+    center: Vector3D,
+    scaled_left: Vector3D,
+    scaled_right: Vector3D,
+) -> (Vector3D, Vector3D) {
+
+    let mut scaled_left = scaled_left;
+    let mut scaled_right = scaled_right;
+
+    let quarter_turn = std::f32::consts::FRAC_PI_2;
+
+    if !open_sides.contains(&dir) && (open_sides.contains(&right_dir) || open_sides.contains(&right_diag)){
+
+        let middle = get_rotated_middle(right_dir, center, -quarter_turn);
+
+        scaled_right = scale_from_origin(right, middle,  1./(1.-BEVEL_SIZE));
+
+    } else if open_sides.contains(&dir) && !open_sides.contains(&right_dir) && (left-right).norm() > 0.5 {
+
+        let middle = get_rotated_middle(dir, center, quarter_turn);
+
+        scaled_right = scale_from_origin(right, middle, 1./(1.-BEVEL_SIZE));
+
+    }
+
+    if open_sides.contains(&dir) && !open_sides.contains(&left_dir) && (left-right).norm() > 0.5 {
+
+        let middle = get_rotated_middle(dir, center, -quarter_turn);
+
+        scaled_left = scale_from_origin(left, middle,  1./(1.-BEVEL_SIZE));
+    }
+
+    (scaled_left, scaled_right)
+}
+
+fn define_verts_from_sides(sides: &HashSet<Point>, left: Vector3D, right: Vector3D, center: Vector3D, points: &mut Vec<Vector3D>) {
+    let (dir, right_dir, left_dir, right_diag, left_diag) = get_edge_dirs(right, left, center);
+    let bevel = Vector3D::new(dir.x as f32, dir.y as f32, dir.z as f32) * BEVEL_SIZE / 2.;
+
+    let mut scale_origin = center;
+    let mut scale_size = 1.-BEVEL_SIZE * 2.;
+    let corner_scale = scale_size * 1.2;
+
+    // Define top face points based on which sides are exposed or not.
+    if sides.contains(&dir) {
+
+        let mut adj: Vector3D = bevel;
+        let mut corner: Option<Vector3D> = None;
+
+        if !sides.contains(&right_dir) && !sides.contains(&left_dir) {
+            scale_size = 1.;
+            scale_origin = (left + right) / 2.;
+            adj = -bevel;
+
+        } else if !sides.contains(&right_dir) {
+            scale_origin = right;
+            scale_size = 1.-BEVEL_SIZE;
+            corner = Some(scale_from_origin(left, center, corner_scale));
+            adj = -bevel;
+
+        } else if !sides.contains(&left_dir) {
+            scale_origin = left;
+            scale_size = 1.-BEVEL_SIZE;
+            adj = -bevel;
+
+        } else {
+            corner = Some(scale_from_origin(left, scale_origin, corner_scale));
+        }
+
+        let mut scaled_right = scale_from_origin(right, scale_origin, scale_size);
+        let mut scaled_left = scale_from_origin(left, scale_origin, scale_size);
+
+        scaled_right += adj;
+        scaled_left += adj;
+
+        match corner {
+            Some(corner) => {
+                points.extend(&[scaled_right, scaled_left, corner])
+            },
+            None => {
+                points.extend(&[scaled_right, scaled_left])
+            }
+        }
+
+    } else {
+
+        let mut adj: Option<Vector3D> = None;
+
+        if !sides.contains(&left_dir) && !sides.contains(&right_dir) {
+            if !sides.contains(&right_diag) && !sides.contains(&left_diag) {
+                scale_size = 1.;
+
+            } else if !sides.contains(&left_diag) {
+                scale_origin = left;
+                scale_size = 1.-BEVEL_SIZE / 2.;
+            } else {
+                scale_origin = (right + left) / 2.;
+                scale_size = 1.-BEVEL_SIZE;
+            }
+        } else if !sides.contains(&left_dir) {
+            if !sides.contains(&left_diag){
+                scale_origin = left;
+                scale_size = 1.-BEVEL_SIZE / 2.;
+            } else {
+                scale_origin = left;
+                adj = Some(Vector3D::new(right_dir.x as f32, right_dir.y as f32, right_dir.z as f32) * BEVEL_SIZE / 2.);
+                scale_size = 1.-BEVEL_SIZE;
+            }
+        } else {
+            scale_origin = (right + left) / 2.;
+            scale_size = 1.-BEVEL_SIZE;
+        }
+
+        let mut scaled_right = scale_from_origin(right, scale_origin, scale_size);
+        let mut scaled_left = scale_from_origin(left, scale_origin, scale_size);
+
+        if let Some(adj) = adj {
+            scaled_right += adj;
+            scaled_left += adj;
+        }
+
+        points.extend(&[scaled_right, scaled_left]);
+
+    }
+}
+
+/// Returns how far the v coordinates of a wall with its bottom at the given height get moved, so that the wall texture repeats in the same
+/// places on every wall, starting from the row of the side cell at side_v
+fn get_wall_v_offset(bottom: f32, wall_repeat: WallRepeat, side_v: f32, tile_size: f32) -> f32 {
+    let vert_offset = if bottom < wall_repeat.start_below  {
+        (bottom / wall_repeat.repeat_below).floor() * wall_repeat.repeat_below * tile_size
+    } else if bottom - wall_repeat.start_above >= wall_repeat.start_below {
+        ((((bottom - wall_repeat.start_above) / wall_repeat.repeat_above).floor() * wall_repeat.repeat_above) - wall_repeat.repeat_below) * tile_size
+    } else {
+        - wall_repeat.repeat_below * tile_size
+    };
+
+    vert_offset - side_v
+}
+
+/// Returns the u coordinates of a wall from border_point to next_point, which wrap around the tile from start_u
+fn get_wall_us(start_u: f32, dir: Point, diff: Vector3D, border_point: Vector3D, next_point: Vector3D, world_point: Vector3D, tile_size: f32) -> Option<(f32, f32)> {
+    let (mut u, mut next_u) = if dir.z.abs() > 0 {
+        (
+            start_u + (border_point.x - world_point.x).abs() * tile_size,
+            start_u + (next_point.x - world_point.x).abs() * tile_size
+        )
+    } else if dir.x.abs() > 0 {
+        (
+            start_u + (border_point.z - world_point.z).abs() * tile_size,
+            start_u + (next_point.z - world_point.z).abs() * tile_size
+        )
+    } else {
+        return None
+    };
+
+    if (dir.z.abs() > 0 && diff.x > 0.) || (dir.z.abs() == 0 && diff.z > 0.) {
+        u = -u;
+        next_u = -next_u;
+    }
+
+    Some((u, next_u))
+}
+
+//allowed because this is just a function that gets called here
+#[allow(clippy::too_many_arguments)] fn draw_walls(
+    points: &[Vector3D],
+    sides: &HashSet<Point>,
+    vertex_data: &mut VertexData,
+    center: Vector3D,
+    point: Point,
+    world_point: Vector3D,
+    bottom: f32,
+    true_top: f32,
+    offset: &mut i32,
+    tile: u32,
+    definitions: &TileDefinitions
+) {
+    let tile_size = definitions.get_tile_size();
+    let wall_repeat = definitions.get_wall_repeat(tile);
+    let side_offset = definitions.get_uv_offset(definitions.get_side(tile));
+
+    //define the vertices for the walls
+    let border_points_len = points.len();
+
+    if border_points_len > 0 {
+
+        let top = points[0].y;
+        let height = top - bottom;
+
+        let begin = *offset;
+        let indices_len = border_points_len as i32 * 2;
+
+        let mut center = center;
+
+        center.y = 0.;
+
+        //define the sides
+        for (i, border_point) in points.iter().enumerate() {
+
+            let border_point = *border_point;
+
+            //get the direction
+            let next_point = points[(i+1) % border_points_len];
+
+            let dir = get_direction_of_edge(border_point, next_point, center);
+
+            //top
+            vertex_data.verts.push(border_point);
+
+            //bottom
+            let bottom_point = border_point - Vector3D::new(0., height, 0.);
+            vertex_data.verts.push(bottom_point);
+
+            //define the uvs for the walls on every other iteration
+            if i % 2 == 0 {
+
+                let diff = next_point - border_point;
+
+                let mut normal_origin = (next_point + border_point) / 2.;
+                normal_origin.y = center.y;
+                normal_origin = (normal_origin - center).normalize();
+
+                vertex_data.normals.push(normal_origin.normalize());
+                vertex_data.normals.push(normal_origin.normalize());
+
+                vertex_data.normals.push(normal_origin.normalize());
+                vertex_data.normals.push(normal_origin.normalize());
+
+                let start_u = if dir.z.abs() > 0 {
+                    world_point.x * tile_size
+                } else {
+                    world_point.z * tile_size
+                };
+
+                let (u, next_u) = get_wall_us(start_u, dir, diff, border_point, next_point, world_point, tile_size).unwrap_or((1., 1.));
+
+                let mut vert_offset = get_wall_v_offset(bottom, wall_repeat, side_offset.y, tile_size);
+
+                if true_top - bottom > 1.0 {
+                    vert_offset += 1.0;
+                }
+
+                //define the uvs for the grass overhang textures
+                if map_coords_to_world(point).y + std::f32::EPSILON > true_top - 1. {
+
+                    let (mut u, mut next_u) = get_wall_us(tile_size, dir, diff, border_point, next_point, world_point, tile_size).unwrap_or((u, next_u));
+
+                    if u < 0. {
+                        u = (1. - u) % 1.;
+                    }
+
+                    if next_u < 0. {
+                        next_u = (1. - next_u) % 1.;
+                    }
+
+                    let top_v = tile_size * (true_top - top);
+                    let bottom_v = tile_size * (true_top - bottom);
+
+                    vertex_data.uv2s.push(Vector2D::new(u, top_v));
+                    vertex_data.uv2s.push(Vector2D::new(u, bottom_v));
+
+                    vertex_data.uv2s.push(Vector2D::new(next_u, top_v));
+                    vertex_data.uv2s.push(Vector2D::new(next_u, bottom_v));
+
+                } else {
+                    vertex_data.uv2s.push(Vector2D::zeros());
+                    vertex_data.uv2s.push(Vector2D::zeros());
+
+                    vertex_data.uv2s.push(Vector2D::zeros());
+                    vertex_data.uv2s.push(Vector2D::zeros());
+                }
+
+                let (u, next_u) = (side_offset.x + u, side_offset.x + next_u);
+
+                vertex_data.uvs.push(Vector2D::new(u,-1.-height * tile_size - bottom * tile_size + vert_offset)); //bottom of face
+                vertex_data.uvs.push(Vector2D::new(u,-1.-bottom * tile_size + vert_offset)); //top of face
+
+                vertex_data.uvs.push(Vector2D::new(next_u,-1.-height * tile_size - bottom * tile_size + vert_offset)); //bottom of face
+                vertex_data.uvs.push(Vector2D::new(next_u,-1.-bottom * tile_size + vert_offset)); //top of face
+
+            }
+
+            //if there are only 2 border points, only draw from the first index to avoid drawing both sides since the index will loop around
+            //and only add indices for points that aren't overlapping
+            if (border_points_len > 2 || i < border_points_len-1) && (next_point - border_point).norm() > std::f32::EPSILON && sides.contains(&dir) {
+
+                let j = *offset - begin;
+
+                vertex_data.indices.push(j % indices_len + begin);
+                vertex_data.indices.push((j+1) % indices_len + begin);
+                vertex_data.indices.push((j+2) % indices_len + begin);
+
+                vertex_data.indices.push((j+2) % indices_len + begin);
+                vertex_data.indices.push((j+1) % indices_len + begin);
+                vertex_data.indices.push((j+3) % indices_len + begin);
+            }
+
+            *offset += 2;
+
+        }
+    }
+
+}
@@ -1,14 +1,25 @@
 pub mod mesh;
+pub mod collision;
+pub mod mesher;
+pub mod obj;
 pub mod document;
+pub mod clipboard;
+pub mod region;
+pub mod flood;
+pub mod generator;
+pub mod heightmap;
+pub mod prefab;
+pub mod shape;
+pub mod symmetry;
+pub mod tile_definitions;
+pub mod vox;
 
 use gdnative::prelude::*;
+use gdnative::api::File;
 
-use std::sync::mpsc;
-use std::collections::HashSet;
 use std::collections::HashMap;
 use legion::*;
 use serde::{Serialize, Deserialize};
-use rayon::prelude::*;
 use cobalt::{
     BinaryRateLimiter, NoopPacketModifier, Server
 };
@@ -62,6 +73,89 @@ pub enum MapChange {
         tile_data: TileData
     },
     MapRemoval(AABB),
+    /// Changes the tile of every existing cell within the AABB, leaving empty cells empty
+    MapRepaint{
+        aabb: AABB,
+        tile: u32
+    },
+    /// Pastes the contents of a clipboard into a selection box centered at center, turned by the selection box's rotation
+    MapPaste{
+        clipboard: clipboard::Clipboard,
+        center: Point,
+        rotation: nalgebra::Rotation3<f32>
+    },
+    /// Rotates or mirrors the tiles within the AABB in place
+    MapTransform{
+        aabb: AABB,
+        transform: region::RegionTransform
+    },
+    /// Repaints every tile connected to start that has the same tile as it, keeping their shapes
+    MapFloodFill{
+        start: Point,
+        tile: u32,
+        connectivity: flood::Connectivity,
+        max_volume: usize
+    },
+    /// Replaces the range starting from min with the columns of a heightmap
+    MapHeightmap{
+        min: Point,
+        heightmap: heightmap::Heightmap
+    },
+    /// Makes change only to the tiles within the mask
+    MapMasked{
+        change: Box<MapChange>,
+        mask: flood::SelectionMask
+    },
+}
+
+impl MapChange {
+
+    /// Returns the octree that the change applies to the map, which for some changes depends on what is already in the map
+    pub fn get_octree(&self, world: &mut World, resources: &Resources) -> Option<Octree> {
+        match self {
+            MapChange::MapInsertion { aabb, tile_data } => Some(fill_octree_from_aabb(*aabb, Some(*tile_data))),
+            MapChange::MapRemoval(aabb) => Some(fill_octree_from_aabb(*aabb, None)),
+            MapChange::MapRepaint { aabb, tile } => get_repainted_octree(world, resources, *aabb, *tile),
+            MapChange::MapPaste { clipboard, center, rotation } => {
+                let octree = clipboard.get_pasted_octree(*center, *rotation);
+
+                match clipboard.get_pasted_mask(*center, *rotation) {
+                    Some(mask) => mask_octree(world, resources, &octree, &mask),
+                    None => Some(octree)
+                }
+            },
+            MapChange::MapTransform { aabb, transform } => transform.get_transformed_octree(world, resources, *aabb),
+            MapChange::MapFloodFill { start, tile, connectivity, max_volume } => 
+                flood::get_flood_fill_octree(world, resources, *start, *tile, *connectivity, *max_volume),
+            MapChange::MapHeightmap { min, heightmap } => heightmap.get_octree(*min),
+            MapChange::MapMasked { change, mask } => {
+                let octree = change.get_octree(world, resources)?;
+
+                mask_octree(world, resources, &octree, mask)
+            },
+        }
+    }
+
+    /// Returns the change limited to mask, if there is one
+    pub fn masked(self, mask: Option<flood::SelectionMask>) -> Self {
+        match mask {
+            Some(mask) => MapChange::MapMasked {
+                change: Box::new(self),
+                mask
+            },
+            None => self
+        }
+    }
+}
+
+/// Returns octree with every cell outside of mask put back the way it is in the map
+fn mask_octree(world: &mut World, resources: &Resources, octree: &Octree, mask: &flood::SelectionMask) -> Option<Octree> {
+    let map = resources.get::<Map>().map(|map| *map)?;
+    let chunk_index = resources.get::<MapChunkIndex>()?;
+
+    let existing = map.query_chunk_range(world, &chunk_index, octree.get_aabb());
+
+    Some(mask.get_masked_octree(octree, existing))
 }
 
 pub struct TileDimensions {
@@ -93,6 +187,30 @@ impl Default for CoordPos {
 
 pub const TILE_DIMENSIONS: TileDimensions = TileDimensions {x: 1.0, y: 0.25, z: 1.0};
 
+thread_local! {
+    /// What every tile id looks like, which the mesher and the palette both go by
+    pub static TILE_DEFINITIONS: tile_definitions::TileDefinitions = {
+        let file = File::new();
+        match file.open(tile_definitions::TILE_DEFINITIONS_PATH, File::READ) {
+            Ok(_) => {
+                let file_string = file.get_as_text().to_string();
+                file.close();
+                match tile_definitions::TileDefinitions::from_ron(file_string.as_str()) {
+                    Ok(definitions) => definitions,
+                    Err(err) => {
+                        godot_print!("Failed to deserialize {}: {:?}", tile_definitions::TILE_DEFINITIONS_PATH, err);
+                        tile_definitions::TileDefinitions::default()
+                    }
+                }
+            },
+            Err(err) => {
+                godot_print!("Failed to open {}: {:?}", tile_definitions::TILE_DEFINITIONS_PATH, err);
+                tile_definitions::TileDefinitions::default()
+            }
+        }
+    };
+}
+
 /// Applies the const TILE_DIMENSIONS to each map coord to get its conversion in 3D space.
 pub fn map_coords_to_world(map_coord: Point) -> nalgebra::Vector3<f32> {
     nalgebra::Vector3::<f32>::new(
@@ -102,25 +220,75 @@ pub fn map_coords_to_world(map_coord: Point) -> nalgebra::Vector3<f32> {
     )
 }
 
-#[derive(Copy, Clone)]
+/// The configuration of the map, which is saved with the Document and sent to clients when they connect
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Map {
     chunk_dimensions: Point,
+    bounds: Option<AABB>,
 }
 
 impl Default for Map {
     fn default() -> Self {
         Map { 
-            chunk_dimensions: Point::new(10,10,10)
+            chunk_dimensions: Point::new(10,10,10),
+            bounds: None,
         }
     }
 }
 
 impl Map {
 
-    /// Executes changes to the world map in octree. Takes an optional u32 as a client_id for store_history
-    pub fn change(&self, world: &mut legion::world::World, octree: Octree, store_history: Option<u32>) {
+    /// Creates a map configuration with the given chunk dimensions. If bounds is Some, no tiles can be placed outside of it
+    pub fn new(chunk_dimensions: Point, bounds: Option<AABB>) -> Self {
+        Map {
+            chunk_dimensions,
+            bounds
+        }
+    }
+
+    pub fn get_chunk_dimensions(&self) -> Point {
+        self.chunk_dimensions
+    }
+
+    pub fn get_bounds(&self) -> Option<AABB> {
+        self.bounds
+    }
+
+    /// Returns the part of octree that fits within the map bounds, or an error if none of it does
+    pub fn clip_to_bounds(&self, octree: Octree) -> Result<Octree, Error> {
+        match self.bounds {
+            Some(bounds) if !bounds.contains_aabb(octree.get_aabb()) => {
+                let aabb = octree.get_aabb();
+
+                if !bounds.intersects_bounds(aabb) {
+                    return Err(Error::new(ErrorKind::InvalidInput, "The change is entirely outside of the map bounds"));
+                }
+
+                let intersection = bounds.get_intersection(aabb);
+                let mut clipped = Octree::new(intersection, octree::DEFAULT_MAX);
+
+                octree.iter_range(intersection).for_each(|tile_data| {
+                    clipped.insert(*tile_data).ok();
+                });
+
+                clipped.compress();
 
-        match self.can_change(world, octree.clone()) {
+                Ok(clipped)
+            },
+            _ => Ok(octree)
+        }
+    }
+
+    /// Executes changes to the world map in octree. Takes an optional u32 as a client_id for store_history. Anything outside of the
+    /// map bounds is ignored.
+    pub fn change(&self, world: &mut legion::world::World, resources: &Resources, octree: Octree, store_history: Option<u32>) {
+
+        let octree = match self.clip_to_bounds(octree) {
+            Ok(octree) => octree,
+            Err(_) => return
+        };
+
+        match self.can_change(world, resources, octree.clone()) {
             Err(_) => return,
             Ok((original_state, new_state)) => {
                 if let Some(client_id) = store_history {
@@ -135,38 +303,35 @@ impl Map {
             }
         }
 
+        let mut chunk_index = match resources.get_mut::<MapChunkIndex>() {
+            Some(chunk_index) => chunk_index,
+            None => return
+        };
+
         let mut entities: HashMap<Entity, MapChunkData> = HashMap::new();
 
         let aabb = octree.get_aabb();
 
         self.range_sliced_to_chunks(aabb).into_iter().for_each(|(pt, _)| {
     
-            let mut map_chunk_exists_query = <(Entity, Read<MapChunkData>, Read<Point>)>::query();
-    
-            let mut exists = false;
-    
-            if let Some((entity, map_chunk, _)) = map_chunk_exists_query.iter(world).find(|(_, _, chunk_pt)| **chunk_pt == pt) {
-                println!("Map chunk exists already");
-                entities.insert(*entity, map_chunk.clone());
-                exists = true;
-            }
+            let existing = chunk_index.get(pt)
+                .and_then(|entity| get_map_chunk_data(world, entity).map(|map_data| (entity, map_data)));
+
+            match existing {
+                Some((entity, map_data)) => {
+                    println!("Map chunk exists already");
+                    entities.insert(entity, map_data);
+                },
+                None => {
+                    println!("Creating a new map chunk at {:?}", pt);
     
-            if !exists {
-                println!("Creating a new map chunk at {:?}", pt);
-    
-                let (entity, map_data) = self.insert_mapchunk_with_octree(
-                    &Octree::new(AABB::new(
-                        Point::new(
-                            pt.x * self.chunk_dimensions.x + self.chunk_dimensions.x/2,
-                            pt.y * self.chunk_dimensions.y + self.chunk_dimensions.y/2,
-                            pt.z * self.chunk_dimensions.z + self.chunk_dimensions.z/2,
-                        ),
-                        self.chunk_dimensions
-                    ), octree::DEFAULT_MAX), 
-                    world, false
-                );
-    
-                entities.insert(entity, map_data);
+                    let (entity, map_data) = self.insert_mapchunk_with_octree(
+                        &Octree::new(self.get_chunk_aabb(pt), octree::DEFAULT_MAX), 
+                        world, &mut chunk_index, false
+                    );
+
+                    entities.insert(entity, map_data);
+                }
             }
         });
     
@@ -175,28 +340,26 @@ impl Map {
             let map_aabb = map_data.octree.get_aabb();
             let overlap_aabb = aabb.get_intersection(map_aabb);
     
-            let map_query_range = map_data.octree.query_range(overlap_aabb);
-            let input_query_range = octree.query_range(overlap_aabb);
-    
-            let set = input_query_range.into_iter().collect::<HashSet<TileData>>();
-            let map_set = map_query_range.into_iter().collect::<HashSet<TileData>>();
+            let mut map_overlap = Octree::new(overlap_aabb, octree::DEFAULT_MAX);
+            let mut input_overlap = Octree::new(overlap_aabb, octree::DEFAULT_MAX);
+
+            map_data.octree.iter_range(overlap_aabb).for_each(|tile_data| {
+                map_overlap.insert(*tile_data).ok();
+            });
+
+            octree.iter_range(overlap_aabb).for_each(|tile_data| {
+                input_overlap.insert(*tile_data).ok();
+            });
+
+            let diff = map_overlap.diff(&input_overlap);
     
-            if set.symmetric_difference(&map_set).count() == 0 {
-                println!("Set and map_set were symmetrically the same");
+            if diff.is_empty() {
+                println!("Input and map data were the same");
                 continue
             }
     
-            //Remove any data that is in map_set but not set
-            let difference = map_set.difference(&set);
-            for item in difference {
-                map_data.octree.remove_item(item);
-            }
-    
-            //Add any data that is in set but not map_set
-            let difference = set.difference(&map_set);
-            for item in difference {
-                map_data.octree.insert(*item).unwrap();
-            }
+            map_data.octree.apply(&diff).unwrap();
+            map_data.octree.compress();
     
             // And the range of change to the ManuallyChange component if it exists, otherwise, make it exist
             if let Some(mut entry) = world.entry(*entity) {
@@ -215,43 +378,65 @@ impl Map {
         
     }
 
-    /// Returns AABBs that are subdivided to fit into the constraints of the chunk dimensions, as well as the chunk pt they'd fit in
-    pub fn range_sliced_to_chunks(&self, aabb: AABB) -> Vec<(Point, AABB)> {    
+    /// Returns the range of chunk points that aabb touches
+    pub fn get_chunk_range(&self, aabb: AABB) -> AABB {
         let min = aabb.get_min();
         let max = aabb.get_max();
-    
-        let x_min_chunk = (min.x as f32 / self.chunk_dimensions.x as f32).floor() as i32;
-        let y_min_chunk = (min.y as f32 / self.chunk_dimensions.y as f32).floor() as i32;
-        let z_min_chunk = (min.z as f32 / self.chunk_dimensions.z as f32).floor() as i32;
-    
-        let x_max_chunk = (max.x as f32/ self.chunk_dimensions.x as f32).floor() as i32 + 1;
-        let y_max_chunk = (max.y as f32/ self.chunk_dimensions.y as f32).floor() as i32 + 1;
-        let z_max_chunk = (max.z as f32/ self.chunk_dimensions.z as f32).floor() as i32 + 1;
-        
-        let min_chunk = Point::new(x_min_chunk, y_min_chunk, z_min_chunk);
-    
-        let dimensions = Point::new(x_max_chunk, y_max_chunk, z_max_chunk) - min_chunk;
-    
-        let volume = dimensions.x * dimensions.y * dimensions.z;
 
-        let mut results = Vec::new();
-        
-        for i in 0..volume {
-            let x = x_min_chunk + i % dimensions.x;
-            let y = y_min_chunk + (i / dimensions.x) % dimensions.y;
-            let z = z_min_chunk + i / (dimensions.x * dimensions.y);
-    
-            let min = Point::new(x * self.chunk_dimensions.x, y * self.chunk_dimensions.y, z * self.chunk_dimensions.z);
-            let max = min + self.chunk_dimensions;
+        AABB::from_extents(
+            Point::new(
+                (min.x as f32 / self.chunk_dimensions.x as f32).floor() as i32,
+                (min.y as f32 / self.chunk_dimensions.y as f32).floor() as i32,
+                (min.z as f32 / self.chunk_dimensions.z as f32).floor() as i32
+            ),
+            Point::new(
+                (max.x as f32 / self.chunk_dimensions.x as f32).floor() as i32,
+                (max.y as f32 / self.chunk_dimensions.y as f32).floor() as i32,
+                (max.z as f32 / self.chunk_dimensions.z as f32).floor() as i32
+            )
+        )
+    }
 
-            results.push((Point::new(x,y,z), AABB::from_extents(min, max).get_intersection(aabb)));
-        }
+    /// Returns the AABB covered by the chunk at chunk_pt
+    pub fn get_chunk_aabb(&self, chunk_pt: Point) -> AABB {
+        let min = Point::new(
+            chunk_pt.x * self.chunk_dimensions.x, 
+            chunk_pt.y * self.chunk_dimensions.y, 
+            chunk_pt.z * self.chunk_dimensions.z
+        );
 
-        results
+        AABB::from_extents(min, min + self.chunk_dimensions - Point::new(1,1,1))
+    }
+
+    /// Returns AABBs that are subdivided to fit into the constraints of the chunk dimensions, as well as the chunk pt they'd fit in
+    pub fn range_sliced_to_chunks(&self, aabb: AABB) -> Vec<(Point, AABB)> {
+        self.get_chunk_range(aabb).iter_points()
+            .map(|chunk_pt| (chunk_pt, self.get_chunk_aabb(chunk_pt).get_intersection(aabb)))
+            .collect()
+    }
+
+    /// Returns the tiles in octrees split up into octrees that each cover a whole chunk, along with the chunk point they belong at
+    pub fn get_chunked_octrees(&self, octrees: &[Octree]) -> HashMap<Point, Octree> {
+        let mut chunks: HashMap<Point, Octree> = HashMap::new();
+
+        octrees.iter().for_each(|octree| {
+            self.range_sliced_to_chunks(octree.get_aabb()).into_iter().for_each(|(chunk_pt, range)| {
+                let chunk = chunks.entry(chunk_pt)
+                    .or_insert_with(|| Octree::new(self.get_chunk_aabb(chunk_pt), octree::DEFAULT_MAX));
+
+                octree.iter_range(range).for_each(|tile_data| {
+                    chunk.insert(*tile_data).ok();
+                });
+            });
+        });
+
+        chunks.values_mut().for_each(|octree| octree.compress());
+
+        chunks
     }
 
-    /// Deletes all entities for the map chunks, removes the mesh nodes from the node cache
-    pub fn free(&self, world: &mut legion::world::World) {
+    /// Deletes all entities for the map chunks, removes the mesh nodes from the node cache and empties the MapChunkIndex
+    pub fn free(&self, world: &mut legion::world::World, resources: &Resources) {
 
         let mut map_chunk_query = <Read<NodeRef>>::query()
             .filter(component::<MapChunkData>());
@@ -263,59 +448,40 @@ impl Map {
         for node in results {
             node::free(world, node); 
         }
+
+        //chunks that haven't had their nodes created yet still need to be removed
+        if let Some(mut chunk_index) = resources.get_mut::<MapChunkIndex>() {
+            chunk_index.drain().for_each(|(_, entity)| {
+                world.remove(entity);
+            });
+        }
     }
 
     /// Does a query range on every chunk that fits within the range
-    pub fn query_chunk_range<T: IntoIterator<Item=(Entity, MapChunkData, Point)> + Clone>(&self, map_datas: T, range: AABB) -> Vec<TileData> {
+    pub fn query_chunk_range(&self, world: &mut World, chunk_index: &MapChunkIndex, range: AABB) -> Vec<TileData> {
     
         let mut results = Vec::new();
 
-        self.chunks_in_range(map_datas, range).iter().for_each(|(_, map_data)| {
-            results.extend(map_data.octree.query_range(range))
+        self.chunks_in_range(chunk_index, range).into_iter().for_each(|(_, entity)| {
+            if let Some(entry) = world.entry(entity) {
+                if let Ok(map_data) = entry.get_component::<MapChunkData>() {
+                    results.extend(map_data.octree.query_range(range))
+                }
+            }
         });
 
         results
     }
 
-    pub fn chunks_in_range<T: IntoIterator<Item=(Entity, MapChunkData, Point)> + Clone>(&self, map_datas: T, range: AABB) -> Vec<(Entity, MapChunkData)> {
-        
-        let min = range.get_min();
-        let max = range.get_max();
-
-        let x_min_chunk = (min.x as f32 / self.chunk_dimensions.x as f32).floor() as i32;
-        let y_min_chunk = (min.y as f32 / self.chunk_dimensions.y as f32).floor() as i32;
-        let z_min_chunk = (min.z as f32 / self.chunk_dimensions.z as f32).floor() as i32;
-
-        let x_max_chunk = (max.x as f32/ self.chunk_dimensions.x as f32).floor() as i32 + 1;
-        let y_max_chunk = (max.y as f32/ self.chunk_dimensions.y as f32).floor() as i32 + 1;
-        let z_max_chunk = (max.z as f32/ self.chunk_dimensions.z as f32).floor() as i32 + 1;
-
-        let min_chunk = Point::new(x_min_chunk, y_min_chunk, z_min_chunk);
-
-        let dimensions = Point::new(x_max_chunk, y_max_chunk, z_max_chunk) - min_chunk;
-
-        let volume = dimensions.x * dimensions.y * dimensions.z;
-        
-        let mut results: Vec<(Entity, MapChunkData)> = Vec::new();
-
-        for i in 0..volume {
-            let x = x_min_chunk + i % dimensions.x;
-            let y = y_min_chunk + (i / dimensions.x) % dimensions.y;
-            let z = z_min_chunk + i / (dimensions.x * dimensions.y);
-
-            let point = Point::new(x,y,z);
-
-            map_datas.clone().into_iter().filter(|(_, _, pt)| *pt == point).for_each(|(entity, map_data, _)| {
-                results.push((entity, map_data));
-            });
-
-        }
-
-        results
+    /// Returns the chunk points and entities of every existing chunk that fits within the range
+    pub fn chunks_in_range(&self, chunk_index: &MapChunkIndex, range: AABB) -> Vec<(Point, Entity)> {
+        self.get_chunk_range(range).iter_points()
+            .filter_map(|pt| chunk_index.get(pt).map(|entity| (pt, entity)))
+            .collect()
     }
 
-    /// Inserts a new mapchunk with the octree data into world
-    pub fn insert_mapchunk_with_octree(self, octree: &Octree, world: &mut World, changed: bool) -> (Entity, MapChunkData) {
+    /// Inserts a new mapchunk with the octree data into world and adds it to chunk_index
+    pub fn insert_mapchunk_with_octree(self, octree: &Octree, world: &mut World, chunk_index: &mut MapChunkIndex, changed: bool) -> (Entity, MapChunkData) {
         let map_data = MapChunkData{
             octree: octree.clone(),
         };
@@ -324,8 +490,8 @@ impl Map {
 
         let area = self.chunk_dimensions.x * self.chunk_dimensions.z;
 
-        if changed {
-            (world.push(
+        let entity = if changed {
+            world.push(
                 (
                     ManuallyChange{
                         ranges: vec![ChangeType::Direct(octree.get_aabb())]
@@ -334,35 +500,41 @@ impl Map {
                     map_data.clone(),
                     #[cfg(not(test))]
                     MeshData::new(),
-                    mesh::MapMeshData::new((0..area).map(|_| mesh::VertexData::default()).collect()),
+                    mesh::MapMeshData::new(area as usize),
+                    mesh::MapCollisionData::new(area as usize),
+                    mesh::MaterialSurfaces::default(),
                     custom_mesh::RequiresManualChange{}
                 )
-            ), map_data)
+            )
         } else {
-            (world.push(
+            world.push(
                 (
                     chunk_pt,
                     map_data.clone(),
                     #[cfg(not(test))]
                     MeshData::new(),
-                    mesh::MapMeshData::new((0..area).map(|_| mesh::VertexData::default()).collect()),
+                    mesh::MapMeshData::new(area as usize),
+                    mesh::MapCollisionData::new(area as usize),
+                    mesh::MaterialSurfaces::default(),
                     custom_mesh::RequiresManualChange{}
                 )
-            ), map_data)
-        }
+            )
+        };
+
+        chunk_index.insert(chunk_pt, entity);
+
+        (entity, map_data)
     }
 
     /// Returns two octrees: the original state of the map that it compared against on the left, and the new octree input on the right
-    pub fn can_change(&self, world: &mut World, octree: Octree) -> Result<(Octree, Octree), Error> {
+    pub fn can_change(&self, world: &mut World, resources: &Resources, octree: Octree) -> Result<(Octree, Octree), Error> {
 
         let aabb = octree.get_aabb();
 
-        let mut map_query = <(Entity, Read<MapChunkData>, Read<Point>)>::query();
-        let results = map_query.iter(world)
-            .map(|(entity, map_data, pt)| (*entity, (*map_data).clone(), *pt))
-            .collect::<Vec<(Entity, MapChunkData, Point)>>();
-
-        let existing_data = self.query_chunk_range(results, aabb);
+        let existing_data = match resources.get::<MapChunkIndex>() {
+            Some(chunk_index) => self.query_chunk_range(world, &chunk_index, aabb),
+            None => Vec::new()
+        };
 
         let mut existing_octree = Octree::new(aabb, octree::DEFAULT_MAX);
 
@@ -370,7 +542,7 @@ impl Map {
             existing_octree.insert(tile_data).ok();
         });
 
-        if existing_octree.clone().into_iter().collect::<HashSet<TileData>>().symmetric_difference(&octree.clone().into_iter().collect::<HashSet<TileData>>()).count() == 0 {
+        if existing_octree.diff(&octree).is_empty() {
             return Err(Error::new(ErrorKind::AlreadyExists, "There is no symmetric difference between existing data and insertion"));
         }
 
@@ -378,6 +550,50 @@ impl Map {
     }
 }
 
+/// Resource that maps chunk points to the entities of the map chunks, so that chunks can be found without querying every one of them
+#[derive(Debug, Clone, Default)]
+pub struct MapChunkIndex {
+    chunks: HashMap<Point, Entity>,
+}
+
+impl MapChunkIndex {
+    pub fn get(&self, chunk_pt: Point) -> Option<Entity> {
+        self.chunks.get(&chunk_pt).copied()
+    }
+
+    pub fn insert(&mut self, chunk_pt: Point, entity: Entity) {
+        self.chunks.insert(chunk_pt, entity);
+    }
+
+    pub fn remove(&mut self, chunk_pt: Point) -> Option<Entity> {
+        self.chunks.remove(&chunk_pt)
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Empties the index, returning every chunk point and entity that was in it
+    pub fn drain(&mut self) -> std::collections::hash_map::Drain<'_, Point, Entity> {
+        self.chunks.drain()
+    }
+}
+
+/// Returns a clone of the MapChunkData belonging to entity, if it still exists
+fn get_map_chunk_data(world: &mut World, entity: Entity) -> Option<MapChunkData> {
+    if let Some(entry) = world.entry(entity) {
+        if let Ok(map_data) = entry.get_component::<MapChunkData>() {
+            return Some(map_data.clone())
+        }
+    }
+
+    None
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MapChunkData {
     pub octree: Octree,
@@ -391,22 +607,15 @@ impl MapChunkData {
     }
 
     pub fn get_chunk_point(&self) -> Point {
-        let aabb = self.octree.get_aabb();
-        let min = aabb.get_min();
-        let dimensions = aabb.dimensions;
-
-        Point::new(
-            (min.x as f32 / dimensions.x as f32).floor() as i32,
-            (min.y as f32 / dimensions.y as f32).floor() as i32,
-            (min.z as f32 / dimensions.z as f32).floor() as i32,
-        )
+        mesher::get_chunk_point(&self.octree)
     }
 }
 
 #[derive(Serialize, Deserialize, Eq, Hash, PartialEq, Clone, Debug)]
 pub struct TileData {
     tile: u32,
-    point: Point
+    point: Point,
+    shape: shape::TileShape,
 }
 
 impl Copy for TileData {}
@@ -415,51 +624,79 @@ impl TileData {
     pub fn new(tile: u32, point: Point) -> Self {
         TileData {
             tile,
-            point
+            point,
+            shape: shape::TileShape::Full,
+        }
+    }
+
+    /// Returns the same tile at the same point and with the same shape, but a different tile id
+    pub fn with_tile(&self, tile: u32) -> Self {
+        TileData {
+            tile,
+            ..*self
+        }
+    }
+
+    /// Returns the same tile with a different shape
+    pub fn with_shape(&self, shape: shape::TileShape) -> Self {
+        TileData {
+            shape,
+            ..*self
         }
     }
 
     pub fn get_tile(&self) -> u32 {
         self.tile
     }
+
+    pub fn get_shape(&self) -> shape::TileShape {
+        self.shape
+    }
 }
 
 impl crate::collections::octree::PointData<i32> for TileData {
     fn get_point(&self) -> Point {
         self.point
     }
+
+    fn with_point(&self, point: Point) -> Self {
+        TileData {
+            point,
+            ..*self
+        }
+    }
 }
 
 pub fn fill_octree_from_aabb(aabb: AABB, tile_data: Option<TileData>) -> Octree {
     let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
 
-    let min = aabb.get_min();
+    if let Some(tile_data) = tile_data {
+        octree.fill(aabb, tile_data);
+    }
+
+    octree
 
-    let dimensions = aabb.dimensions.abs();
+}
 
-    let volume = dimensions.x * dimensions.y * dimensions.z;
+/// Returns an octree over aabb in which every tile that already exists in the map is changed to tile, keeping its shape
+pub fn get_repainted_octree(world: &mut World, resources: &Resources, aabb: AABB, tile: u32) -> Option<Octree> {
+    let map = resources.get::<Map>().map(|map| *map)?;
+    let chunk_index = resources.get::<MapChunkIndex>()?;
 
-    let (tx, rx) = mpsc::channel::<TileData>();
+    Some(repaint_octree(aabb, map.query_chunk_range(world, &chunk_index, aabb), tile))
+}
 
-    if let Some(tile_data) = tile_data {
-        (0..volume).into_par_iter().for_each_with(tx, move |tx, i| {
-            let x = min.x + i % dimensions.x;
-            let y = min.y + (i / dimensions.x) % dimensions.y;
-            let z = min.z + i / (dimensions.x * dimensions.y);
-
-            tx.send(TileData {
-                point: Point::new(x,y,z),
-                ..tile_data
-            }).unwrap();
-        });
-    }
+/// Returns an octree over aabb holding every one of tiles changed to tile, keeping its point and shape
+pub fn repaint_octree<I: IntoIterator<Item = TileData>>(aabb: AABB, tiles: I, tile: u32) -> Octree {
+    let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
 
-    rx.try_iter().for_each(|item| {
-        octree.insert(item).ok();
+    tiles.into_iter().for_each(|tile_data| {
+        octree.insert(TileData::new(tile, tile_data.get_point()).with_shape(tile_data.get_shape())).ok();
     });
 
-    octree
+    octree.compress();
 
+    octree
 }
 
 pub fn send_reset_message(world: &mut World) {
@@ -487,10 +724,39 @@ pub fn send_reset_message(world: &mut World) {
     world.extend(extension);
 }
 
+/// Replaces the Map resource with map. If the chunk dimensions differ, the existing tiles get moved into chunks of the new size.
+pub fn set_map_config(world: &mut World, resources: &mut Resources, map: Map) {
+
+    let previous = resources.get::<Map>().map(|previous| *previous);
+
+    resources.insert(map);
+
+    if let Some(mut document) = resources.get_mut::<document::Document>() {
+        document.set_map(map);
+    }
+
+    if let Some(previous) = previous {
+        if previous.chunk_dimensions != map.chunk_dimensions {
+            let mut query = <Read<MapChunkData>>::query();
+
+            let octrees = query.iter(world)
+                .map(|map_data| map_data.octree.clone())
+                .collect::<Vec<Octree>>();
+
+            previous.free(world, resources);
+
+            map.get_chunked_octrees(&octrees).into_iter().for_each(|(_, octree)| map.change(world, resources, octree, None));
+        }
+    }
+}
+
+/// Frees every chunk of the map and puts the map configuration back to that of a new document, so that the chunk dimensions and bounds
+/// of whatever document came before don't carry over
 pub fn map_reset(world: &mut World, resources: &mut Resources) {
 
     if let Some(map) = resources.get::<Map>() {
-        map.free(world);
+        map.free(world, resources);
     }
 
+    set_map_config(world, resources, document::Document::default().get_map());
 }
\ No newline at end of file
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+use super::{
+    mesher::{self, ChunkOctrees, VertexData},
+    tile_definitions::TileDefinitions,
+};
+
+type Point = nalgebra::Vector3<i32>;
+
+/// The name of the material every chunk uses in the .mtl file
+pub const MATERIAL_NAME: &str = "ground";
+
+/// The file name the tile sheet gets saved as next to the exported .obj, which the .mtl file points to
+pub const TEXTURE_FILE_NAME: &str = "ground.png";
+
+/// Meshes every chunk the same way the map is drawn, ordered by chunk point so that the same map always exports the same way. Every material
+/// ends up in the one mesh, since the .mtl file only has the tile sheet.
+pub fn mesh_map(chunks: &ChunkOctrees, definitions: &TileDefinitions) -> Vec<(Point, VertexData)> {
+    let mut chunk_points = chunks.keys().copied().collect::<Vec<Point>>();
+    chunk_points.sort_by_key(|point| (point.x, point.y, point.z));
+
+    chunk_points.into_iter()
+        .map(|chunk_point| (chunk_point, mesher::mesh_chunk(chunks, &chunks[&chunk_point], definitions)))
+        .filter(|(_, vertex_data)| !vertex_data.verts.is_empty())
+        .collect()
+}
+
+/// Returns the contents of a Wavefront .obj file with one object per chunk, all using the material from mtl_file_name. Only the first set of
+/// UVs is kept, so the grass overhang on the edges of walls is left out.
+pub fn write_obj(chunks: &[(Point, VertexData)], mtl_file_name: &str) -> String {
+    let mut obj = String::new();
+
+    writeln!(obj, "mtllib {}", mtl_file_name).unwrap();
+
+    //indices in .obj files count from 1 across the whole file
+    let mut offset = 1;
+
+    chunks.iter().for_each(|(chunk_point, vertex_data)| {
+        writeln!(obj, "o chunk_{}_{}_{}", chunk_point.x, chunk_point.y, chunk_point.z).unwrap();
+        writeln!(obj, "usemtl {}", MATERIAL_NAME).unwrap();
+
+        vertex_data.verts.iter().for_each(|vert| {
+            writeln!(obj, "v {} {} {}", vert.x, vert.y, vert.z).unwrap();
+        });
+
+        //the tile sheet's v goes down from the top, but .obj files count up from the bottom
+        vertex_data.uvs.iter().for_each(|uv| {
+            writeln!(obj, "vt {} {}", uv.x, 1. - uv.y).unwrap();
+        });
+
+        vertex_data.normals.iter().for_each(|normal| {
+            writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z).unwrap();
+        });
+
+        //Godot treats clockwise triangles as front facing where .obj files use counter clockwise, so each triangle is flipped
+        vertex_data.indices.chunks_exact(3).for_each(|triangle| {
+            let (a, b, c) = (triangle[0] + offset, triangle[2] + offset, triangle[1] + offset);
+
+            writeln!(obj, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c).unwrap();
+        });
+
+        offset += vertex_data.verts.len() as i32;
+    });
+
+    obj
+}
+
+/// Returns the contents of the .mtl file that goes with write_obj, with the tile sheet as the diffuse texture
+pub fn write_mtl() -> String {
+    let mut mtl = String::new();
+
+    writeln!(mtl, "newmtl {}", MATERIAL_NAME).unwrap();
+    writeln!(mtl, "Ka 1 1 1").unwrap();
+    writeln!(mtl, "Kd 1 1 1").unwrap();
+    writeln!(mtl, "Ks 0 0 0").unwrap();
+    writeln!(mtl, "illum 1").unwrap();
+    writeln!(mtl, "map_Kd {}", TEXTURE_FILE_NAME).unwrap();
+
+    mtl
+}
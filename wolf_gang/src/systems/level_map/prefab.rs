@@ -0,0 +1,190 @@
+use gdnative::prelude::*;
+use gdnative::api::{
+    Directory,
+    File,
+};
+use legion::*;
+use nalgebra::{Rotation3, Vector3};
+use serde::{Serialize, Deserialize};
+
+use crate::systems::{
+    actor,
+    actor::ActorChange,
+    history,
+    transform::rotation::Rotation,
+};
+
+use super::{CoordPos, Map, clipboard::Clipboard, document::vec_to_byte_array};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Where prefabs get saved to and loaded from
+pub const PREFAB_DIRECTORY: &str = "user://prefabs";
+pub const PREFAB_EXTENSION: &str = "wgp";
+
+/// A region of terrain and the actors in it, saved so that it can be stamped back into the map. Actor positions are stored relative to
+/// the minimum corner of the saved range, the same as the tiles in the clipboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prefab {
+    name: String,
+    clipboard: Clipboard,
+    actor_data: Option<Vec<u8>>,
+}
+
+impl Prefab {
+
+    /// Creates a prefab out of everything within range
+    pub fn from_range<S: ToString>(world: &mut World, resources: &mut Resources, name: S, range: AABB, rotation: Rotation3<f32>) -> Option<Self> {
+        let clipboard = Clipboard::from_range(world, resources, range, rotation, None)?;
+
+        let min = range.get_min();
+        let mut actor_world = World::default();
+
+        actor::MERGER.with(|m| {
+            let mut merger = m.borrow_mut();
+
+            actor::select_actors_from_range(world, resources, range).into_iter().for_each(|entity| {
+                actor_world.clone_from_single(world, entity, &mut *merger);
+            });
+        });
+
+        let mut query = <Write<CoordPos>>::query();
+        query.iter_mut(&mut actor_world).for_each(|coord_pos| {
+            coord_pos.value -= min;
+        });
+
+        let actor_data = if actor_world.is_empty() {
+            None
+        } else {
+            actor::serialize_actors_in_world(&mut actor_world).ok()
+        };
+
+        Some(Prefab {
+            name: name.to_string(),
+            clipboard,
+            actor_data
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_clipboard(&self) -> &Clipboard {
+        &self.clipboard
+    }
+
+    /// Returns the actors to insert when stamping into a selection box centered at center with the given rotation, each given a new
+    /// ActorID so that stamping more than once doesn't conflict with the actors already placed
+    pub fn get_stamped_actors(&self, center: Point, rotation: Rotation3<f32>) -> Option<Vec<u8>> {
+        let mut actor_world = actor::deserialize_actors(self.actor_data.as_ref()?).ok()?;
+
+        let turn = Rotation3::from_axis_angle(
+            &Vector3::y_axis(),
+            self.clipboard.get_quarter_turns_since_copy(rotation) as f32 * std::f32::consts::FRAC_PI_2
+        );
+
+        <Write<actor::ActorID>>::query().iter_mut(&mut actor_world).for_each(|actor_id| {
+            *actor_id = actor::ActorID::new();
+        });
+
+        <Write<CoordPos>>::query().iter_mut(&mut actor_world).for_each(|coord_pos| {
+            coord_pos.value = self.clipboard.get_pasted_point(coord_pos.value, center, rotation);
+        });
+
+        <Write<Rotation>>::query().iter_mut(&mut actor_world).for_each(|actor_rotation| {
+            actor_rotation.value = turn * actor_rotation.value;
+        });
+
+        actor::serialize_actors_in_world(&mut actor_world).ok()
+    }
+
+    /// Returns the path that a prefab with the given name gets saved to
+    pub fn get_file_path(name: &str) -> String {
+        format!("{}/{}.{}", PREFAB_DIRECTORY, name, PREFAB_EXTENSION)
+    }
+
+    pub fn save(&self) -> Result<(), GodotError> {
+        let directory = Directory::new();
+        if !directory.dir_exists(PREFAB_DIRECTORY) {
+            directory.make_dir_recursive(PREFAB_DIRECTORY)?;
+        }
+
+        let file_path = Self::get_file_path(&self.name);
+
+        godot_print!("Saving prefab {}", file_path);
+
+        let file = File::new();
+        file.open(file_path, File::WRITE)?;
+
+        file.store_buffer(vec_to_byte_array(bincode::serialize(self).unwrap()));
+        file.close();
+
+        Ok(())
+    }
+
+    pub fn from_file<S: ToString>(file_path: S) -> Option<Self> {
+        let file = File::new();
+
+        file.open(file_path.to_string(), File::READ).ok()?;
+
+        let byte_array = file.get_buffer(file.get_len());
+        file.close();
+
+        let raw = (0..byte_array.len()).map(|i| byte_array.get(i)).collect::<Vec<u8>>();
+
+        bincode::deserialize::<Self>(&raw).ok()
+    }
+}
+
+/// Returns the names of every prefab in the prefab directory, sorted alphabetically
+pub fn get_prefab_names() -> Vec<String> {
+    let directory = Directory::new();
+
+    let mut names = Vec::new();
+
+    if directory.open(PREFAB_DIRECTORY).is_ok() && directory.list_dir_begin(true, true).is_ok() {
+        let extension = format!(".{}", PREFAB_EXTENSION);
+
+        loop {
+            let file_name = directory.get_next().to_string();
+
+            if file_name.is_empty() {
+                break
+            }
+
+            if !directory.current_is_dir() && file_name.ends_with(&extension) {
+                names.push(file_name.trim_end_matches(&extension).to_string());
+            }
+        }
+
+        directory.list_dir_end();
+    }
+
+    names.sort();
+
+    names
+}
+
+/// Returns whether name can be used as the file name of a prefab
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains(|c: char| "/\\:*?\"<>|".contains(c) || c.is_control())
+}
+
+/// Pastes clipboard into a selection box centered at center with the given rotation, along with any actors, as a single step in the
+/// history of store_history
+pub fn stamp(world: &mut World, resources: &mut Resources, clipboard: &Clipboard, center: Point, rotation: Rotation3<f32>, actors: Option<ActorChange>, store_history: Option<u32>) {
+    let map = match resources.get::<Map>() {
+        Some(map) => *map,
+        None => return
+    };
+
+    history::group_steps(world, resources, store_history, |world, resources| {
+        map.change(world, resources, clipboard.get_pasted_octree(center, rotation), store_history);
+
+        if let Some(actors) = &actors {
+            actor::change(world, resources, actors, store_history);
+        }
+    });
+}
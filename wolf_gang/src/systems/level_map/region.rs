@@ -0,0 +1,86 @@
+use legion::*;
+use serde::{Serialize, Deserialize};
+
+use crate::collections::octree::{self, PointData};
+
+use super::{Map, MapChunkIndex, TileData, clipboard::rotate_point, shape::TileShape};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Ways that the tiles of a selected region can be rearranged in place
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RegionTransform {
+    /// Quarter turns about the y axis, positive being the same direction as a positive angle in Rotation3::from_axis_angle
+    Rotate(i32),
+    MirrorX,
+    MirrorZ,
+}
+
+impl RegionTransform {
+
+    /// Returns the range that the tiles within aabb end up in. Rotations keep the same center, so a region that isn't square turns on the spot.
+    pub fn get_transformed_aabb(&self, aabb: AABB) -> AABB {
+        match self {
+            RegionTransform::Rotate(quarter_turns) => AABB::new(aabb.center, rotate_point(aabb.dimensions, *quarter_turns).abs()),
+            _ => aabb
+        }
+    }
+
+    /// Returns where point within aabb ends up after the transform
+    pub fn transform_point(&self, aabb: AABB, point: Point) -> Point {
+        let min = aabb.get_min();
+        let max = aabb.get_max();
+
+        match self {
+            RegionTransform::Rotate(quarter_turns) => {
+                let target_min = self.get_transformed_aabb(aabb).get_min();
+                let corner = rotate_point(max - min, *quarter_turns);
+
+                rotate_point(point - min, *quarter_turns) + target_min - Point::new(corner.x.min(0), corner.y.min(0), corner.z.min(0))
+            },
+            RegionTransform::MirrorX => Point::new(min.x + max.x - point.x, point.y, point.z),
+            RegionTransform::MirrorZ => Point::new(point.x, point.y, min.z + max.z - point.z),
+        }
+    }
+
+    /// Returns the shape a tile ends up with after the transform
+    pub fn transform_shape(&self, shape: TileShape) -> TileShape {
+        match self {
+            RegionTransform::Rotate(quarter_turns) => shape.rotated(*quarter_turns),
+            RegionTransform::MirrorX => shape.mirrored(true, false),
+            RegionTransform::MirrorZ => shape.mirrored(false, true),
+        }
+    }
+
+    /// Returns the octree to change the map with, which covers both the original and the transformed range so that anything left behind
+    /// by a rotation gets removed in the same change. The corners of that range which are in neither of them are kept as they are.
+    pub fn get_transformed_octree(&self, world: &mut World, resources: &Resources, aabb: AABB) -> Option<Octree> {
+        let map = resources.get::<Map>().map(|map| *map)?;
+        let chunk_index = resources.get::<MapChunkIndex>()?;
+
+        let transformed_aabb = self.get_transformed_aabb(aabb);
+        let range = aabb.union(transformed_aabb);
+
+        let mut octree = Octree::new(range, octree::DEFAULT_MAX);
+
+        let existing = map.query_chunk_range(world, &chunk_index, range);
+
+        existing.iter()
+            .filter(|tile_data| !aabb.contains_point(tile_data.get_point()) && !transformed_aabb.contains_point(tile_data.get_point()))
+            .for_each(|tile_data| {
+                octree.insert(*tile_data).ok();
+            });
+
+        existing.into_iter().filter(|tile_data| aabb.contains_point(tile_data.get_point())).for_each(|tile_data| {
+            let tile_data = tile_data.with_point(self.transform_point(aabb, tile_data.get_point()));
+
+            octree.insert(tile_data.with_shape(self.transform_shape(tile_data.get_shape()))).ok();
+        });
+
+        octree.compress();
+
+        Some(octree)
+    }
+}
@@ -0,0 +1,213 @@
+//! The shapes that tiles can have. Every shape fills the bottom of its tile's cell and is described by how high each corner of its top
+//! reaches, which is all the mesher and the collision shapes need to know about it.
+
+use serde::{Serialize, Deserialize};
+
+use super::TILE_DIMENSIONS;
+
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+/// The corners of a tile as offsets in x and z from its minimum corner, in the order that corner heights are given in. A quarter turn
+/// moves each corner onto the one before it.
+pub const CORNERS: [(i32, i32); 4] = [(0, 0), (1, 0), (1, 1), (0, 1)];
+
+/// Every shape in the order they are listed in the palette
+pub const TILE_SHAPES: [TileShape; 14] = [
+    TileShape::Full,
+    TileShape::Half,
+    TileShape::Ramp(0),
+    TileShape::Ramp(1),
+    TileShape::Ramp(2),
+    TileShape::Ramp(3),
+    TileShape::Corner(0),
+    TileShape::Corner(1),
+    TileShape::Corner(2),
+    TileShape::Corner(3),
+    TileShape::InnerCorner(0),
+    TileShape::InnerCorner(1),
+    TileShape::InnerCorner(2),
+    TileShape::InnerCorner(3),
+];
+
+/// The shape of a tile. Sloped shapes hold how many quarter turns they have been turned about the y axis, going the same direction as
+/// clipboard::rotate_point.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TileShape {
+    Full,
+    /// Half as tall as a full tile
+    Half,
+    /// Slopes up from the bottom of the tile to its full height, rising towards +x before being turned
+    Ramp(u8),
+    /// Slopes up to the full height at a single corner, the +x +z corner before being turned
+    Corner(u8),
+    /// Full height except for a single corner that slopes down to the bottom, the -x -z corner before being turned
+    InnerCorner(u8),
+}
+
+impl Default for TileShape {
+    fn default() -> Self {
+        TileShape::Full
+    }
+}
+
+impl TileShape {
+
+    pub fn is_full(&self) -> bool {
+        *self == TileShape::Full
+    }
+
+    /// Returns the name the shape is listed with in the palette
+    pub fn get_name(&self) -> String {
+        let sides = ["+x", "-z", "-x", "+z"];
+        let corners = ["-x -z", "+x -z", "+x +z", "-x +z"];
+
+        match *self {
+            TileShape::Full => "Full".to_string(),
+            TileShape::Half => "Half".to_string(),
+            TileShape::Ramp(turns) => format!("Ramp {}", sides[turns as usize & 3]),
+            TileShape::Corner(turns) => format!("Corner {}", corners[(2 + 4 - (turns as usize & 3)) % 4]),
+            TileShape::InnerCorner(turns) => format!("Inner corner {}", corners[(4 - (turns as usize & 3)) % 4]),
+        }
+    }
+
+    /// Returns the shape turned about the y axis by the given amount of quarter turns
+    pub fn rotated(&self, quarter_turns: i32) -> Self {
+        let turn = |turns: u8| ((turns as i32 + quarter_turns) & 3) as u8;
+
+        match *self {
+            TileShape::Ramp(turns) => TileShape::Ramp(turn(turns)),
+            TileShape::Corner(turns) => TileShape::Corner(turn(turns)),
+            TileShape::InnerCorner(turns) => TileShape::InnerCorner(turn(turns)),
+            shape => shape
+        }
+    }
+
+    /// Returns the shape mirrored across x, z, or both
+    pub fn mirrored(&self, mirror_x: bool, mirror_z: bool) -> Self {
+        let heights = self.get_corner_heights();
+        let mut mirrored = [0.; 4];
+
+        CORNERS.iter().enumerate().for_each(|(i, (x, z))| {
+            let corner = (if mirror_x { 1 - x } else { *x }, if mirror_z { 1 - z } else { *z });
+
+            mirrored[get_corner_index(corner)] = heights[i];
+        });
+
+        //every kind of shape is its own mirror image once turned the right way
+        (0..4).map(|quarter_turns| self.rotated(quarter_turns))
+            .find(|shape| shape.get_corner_heights().iter().zip(mirrored.iter()).all(|(a, b)| (a - b).abs() < std::f32::EPSILON))
+            .unwrap_or(*self)
+    }
+
+    /// Returns how high each corner of the top reaches as a fraction of the tile's height, in the order of CORNERS
+    pub fn get_corner_heights(&self) -> [f32; 4] {
+        let (unturned, turns) = match *self {
+            TileShape::Full => return [1.; 4],
+            TileShape::Half => return [0.5; 4],
+            TileShape::Ramp(turns) => ([0., 1., 1., 0.], turns),
+            TileShape::Corner(turns) => ([0., 0., 1., 0.], turns),
+            TileShape::InnerCorner(turns) => ([0., 1., 1., 1.], turns),
+        };
+
+        let mut heights = [0.; 4];
+
+        (0..4).for_each(|i| heights[i] = unturned[(i + turns as usize) % 4]);
+
+        heights
+    }
+
+    /// Returns the corners of the top of a tile whose minimum corner is at min
+    pub fn get_corner_points(&self, min: Vector3D) -> [Vector3D; 4] {
+        let heights = self.get_corner_heights();
+        let mut points = [Vector3D::zeros(); 4];
+
+        CORNERS.iter().enumerate().for_each(|(i, (x, z))| {
+            points[i] = min + Vector3D::new(*x as f32 * TILE_DIMENSIONS.x, heights[i] * TILE_DIMENSIONS.y, *z as f32 * TILE_DIMENSIONS.z);
+        });
+
+        points
+    }
+
+    /// Returns the two triangles of the top of a tile whose minimum corner is at min, wound clockwise from above
+    pub fn get_top_triangles(&self, min: Vector3D) -> [[Vector3D; 3]; 2] {
+        let heights = self.get_corner_heights();
+        let [a, b, c, d] = self.get_corner_points(min);
+
+        //fold along whichever diagonal has both ends at the same height, so that a single raised or lowered corner slopes on its own
+        if (heights[1] - heights[3]).abs() < std::f32::EPSILON {
+            [wind([a, b, d], Vector3D::y()), wind([b, c, d], Vector3D::y())]
+        } else {
+            [wind([a, b, c], Vector3D::y()), wind([a, c, d], Vector3D::y())]
+        }
+    }
+
+    /// Returns the triangles of the side of a tile facing dir, from the bottom of the tile up to the edge of its top, wound clockwise from
+    /// outside. A corner with no height leaves a single triangle, and an edge with no height leaves nothing.
+    pub fn get_side_triangles(&self, min: Vector3D, dir: Point) -> Vec<[Vector3D; 3]> {
+        let points = self.get_corner_points(min);
+        let [i, j] = get_side_corners(dir);
+
+        let (top_i, top_j) = (points[i], points[j]);
+        let bottom_i = Vector3D::new(top_i.x, min.y, top_i.z);
+        let bottom_j = Vector3D::new(top_j.x, min.y, top_j.z);
+
+        let normal = Vector3D::new(dir.x as f32, 0., dir.z as f32);
+
+        let mut triangles = Vec::with_capacity(2);
+
+        if top_i.y - min.y > std::f32::EPSILON {
+            triangles.push(wind([bottom_i, bottom_j, top_i], normal));
+        }
+
+        if top_j.y - min.y > std::f32::EPSILON {
+            triangles.push(wind([bottom_j, top_j, top_i], normal));
+        }
+
+        triangles
+    }
+
+    /// Returns whether the side facing dir is hidden behind a neighbouring tile of the given shape, which it is when the neighbour reaches at
+    /// least as high everywhere along the edge they share
+    pub fn is_side_covered_by(&self, dir: Point, neighbor: TileShape) -> bool {
+        let heights = self.get_corner_heights();
+        let neighbor_heights = neighbor.get_corner_heights();
+
+        get_side_corners(dir).iter().all(|i| {
+            let (x, z) = CORNERS[*i];
+
+            neighbor_heights[get_corner_index((x - dir.x, z - dir.z))] + std::f32::EPSILON >= heights[*i]
+        })
+    }
+}
+
+/// Returns the normal of a triangle wound clockwise from the side it faces, which is the side Godot draws
+pub fn get_normal(triangle: &[Vector3D; 3]) -> Vector3D {
+    let [a, b, c] = *triangle;
+
+    -(b - a).cross(&(c - a)).normalize()
+}
+
+/// Orders the points of triangle so that it is wound clockwise when looked at from the side normal points to
+fn wind(triangle: [Vector3D; 3], normal: Vector3D) -> [Vector3D; 3] {
+    let [a, b, c] = triangle;
+
+    if (b - a).cross(&(c - a)).dot(&normal) > 0. {
+        [a, c, b]
+    } else {
+        [a, b, c]
+    }
+}
+
+fn get_corner_index(corner: (i32, i32)) -> usize {
+    CORNERS.iter().position(|c| *c == corner).unwrap()
+}
+
+/// Returns the indices of the two corners along the side of a tile facing dir, which should be one of the four sides
+fn get_side_corners(dir: Point) -> [usize; 2] {
+    let mut sides = CORNERS.iter().enumerate()
+        .filter(|(_, (x, z))| (dir.x != 0 && *x == (dir.x + 1) / 2) || (dir.z != 0 && *z == (dir.z + 1) / 2))
+        .map(|(i, _)| i);
+
+    [sides.next().unwrap(), sides.next().unwrap()]
+}
@@ -0,0 +1,414 @@
+use legion::*;
+use nalgebra::{Rotation3, Vector3};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::{
+        actor,
+        actor::{ActorChange, ActorID, ActorSceneKey, Bounds},
+        history,
+        input,
+        networking::{ClientID, DataType, MessageSender},
+        selection_box::{Active, SelectionBox},
+        transform::rotation::Rotation,
+    },
+};
+
+use super::{
+    CoordPos, Map, MapChange, TileData,
+    clipboard::{Clipboard, get_y_angle},
+    flood::SelectionMask,
+    region::RegionTransform,
+    shape::TileShape,
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// How many mirrored copies get made of every edit
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SymmetryMode {
+    Off,
+    /// Mirrors across the x axis
+    MirrorX,
+    /// Mirrors across the z axis
+    MirrorZ,
+    /// Turns edits halfway around the axis, the usual symmetry for maps with two opposing sides
+    Point,
+    /// Mirrors across both axes, making three copies
+    FourWay,
+}
+
+impl SymmetryMode {
+    /// Returns the next mode, going from Off through to FourWay and back around
+    pub fn next(&self) -> Self {
+        match self {
+            SymmetryMode::Off => SymmetryMode::MirrorX,
+            SymmetryMode::MirrorX => SymmetryMode::MirrorZ,
+            SymmetryMode::MirrorZ => SymmetryMode::Point,
+            SymmetryMode::Point => SymmetryMode::FourWay,
+            SymmetryMode::FourWay => SymmetryMode::Off,
+        }
+    }
+
+    /// Returns the transforms that make each of the mirrored copies
+    pub fn get_transforms(&self) -> Vec<SymmetryTransform> {
+        match self {
+            SymmetryMode::Off => Vec::new(),
+            SymmetryMode::MirrorX => vec![SymmetryTransform::MirrorX],
+            SymmetryMode::MirrorZ => vec![SymmetryTransform::MirrorZ],
+            SymmetryMode::Point => vec![SymmetryTransform::Point],
+            SymmetryMode::FourWay => vec![SymmetryTransform::MirrorX, SymmetryTransform::MirrorZ, SymmetryTransform::Point],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SymmetryTransform {
+    MirrorX,
+    MirrorZ,
+    Point,
+}
+
+/// Resource holding the symmetry that this client's edits get made with
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    /// Twice the coordinates that edits get mirrored about, so that the axis can lie on a tile or between two of them. Only x and z are used.
+    pub axis: Point,
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Symmetry {
+            mode: SymmetryMode::Off,
+            axis: Point::zeros(),
+        }
+    }
+}
+
+impl Symmetry {
+
+    /// Sets the axis to the center of range
+    pub fn set_axis_from_range(&mut self, range: AABB) {
+        self.axis = range.get_min() + range.get_max();
+    }
+
+    pub fn transform_point(&self, transform: SymmetryTransform, point: Point) -> Point {
+        match transform {
+            SymmetryTransform::MirrorX => Point::new(self.axis.x - point.x, point.y, point.z),
+            SymmetryTransform::MirrorZ => Point::new(point.x, point.y, self.axis.z - point.z),
+            SymmetryTransform::Point => Point::new(self.axis.x - point.x, point.y, self.axis.z - point.z),
+        }
+    }
+
+    pub fn transform_shape(&self, transform: SymmetryTransform, shape: TileShape) -> TileShape {
+        match transform {
+            SymmetryTransform::MirrorX => shape.mirrored(true, false),
+            SymmetryTransform::MirrorZ => shape.mirrored(false, true),
+            SymmetryTransform::Point => shape.rotated(2),
+        }
+    }
+
+    pub fn transform_aabb(&self, transform: SymmetryTransform, aabb: AABB) -> AABB {
+        let min = self.transform_point(transform, aabb.get_min());
+        let max = self.transform_point(transform, aabb.get_max());
+
+        AABB::from_extents(min.inf(&max), min.sup(&max))
+    }
+
+    pub fn transform_octree(&self, transform: SymmetryTransform, octree: &Octree) -> Octree {
+        let mut transformed = Octree::new(self.transform_aabb(transform, octree.get_aabb()), octree::DEFAULT_MAX);
+
+        octree.iter().for_each(|tile_data| {
+            let tile_data = tile_data.with_point(self.transform_point(transform, tile_data.get_point()));
+
+            transformed.insert(tile_data.with_shape(self.transform_shape(transform, tile_data.get_shape()))).ok();
+        });
+
+        transformed.compress();
+
+        transformed
+    }
+
+    pub fn transform_mask(&self, transform: SymmetryTransform, mask: &SelectionMask) -> SelectionMask {
+        SelectionMask::new(
+            self.transform_aabb(transform, mask.get_aabb()),
+            mask.get_points().iter().map(|point| self.transform_point(transform, *point)).collect()
+        )
+    }
+
+    /// Returns the region transform that does the mirror image of what region_transform does, turning the other way when mirrored
+    pub fn transform_region_transform(&self, transform: SymmetryTransform, region_transform: RegionTransform) -> RegionTransform {
+        match (transform, region_transform) {
+            (SymmetryTransform::MirrorX, RegionTransform::Rotate(quarter_turns)) | (SymmetryTransform::MirrorZ, RegionTransform::Rotate(quarter_turns)) => 
+                RegionTransform::Rotate(-quarter_turns),
+            (_, region_transform) => region_transform
+        }
+    }
+
+    /// Returns the change that makes the mirror image of change on the other side of the axis. Changes that depend on what is in the map, 
+    /// like repainting, flood fills and region transforms, get made again from the tiles on that side. Changes that don't, like pastes and
+    /// heightmaps, are turned into a paste of their mirrored tiles.
+    pub fn transform_change(&self, transform: SymmetryTransform, change: &MapChange) -> Option<MapChange> {
+        match change {
+            MapChange::MapInsertion { aabb, tile_data } => Some(MapChange::MapInsertion {
+                aabb: self.transform_aabb(transform, *aabb),
+                tile_data: tile_data.with_shape(self.transform_shape(transform, tile_data.get_shape()))
+            }),
+            MapChange::MapRemoval(aabb) => Some(MapChange::MapRemoval(self.transform_aabb(transform, *aabb))),
+            MapChange::MapRepaint { aabb, tile } => Some(MapChange::MapRepaint {
+                aabb: self.transform_aabb(transform, *aabb),
+                tile: *tile
+            }),
+            MapChange::MapPaste { clipboard, center, rotation } => {
+                let mask = clipboard.get_pasted_mask(*center, *rotation).map(|mask| self.transform_mask(transform, &mask));
+
+                Some(self.get_mirrored_paste(transform, &clipboard.get_pasted_octree(*center, *rotation)).masked(mask))
+            },
+            MapChange::MapTransform { aabb, transform: region_transform } => Some(MapChange::MapTransform {
+                aabb: self.transform_aabb(transform, *aabb),
+                transform: self.transform_region_transform(transform, *region_transform)
+            }),
+            MapChange::MapFloodFill { start, tile, connectivity, max_volume } => Some(MapChange::MapFloodFill {
+                start: self.transform_point(transform, *start),
+                tile: *tile,
+                connectivity: *connectivity,
+                max_volume: *max_volume
+            }),
+            MapChange::MapHeightmap { min, heightmap } => heightmap.get_octree(*min).map(|octree| self.get_mirrored_paste(transform, &octree)),
+            MapChange::MapMasked { change, mask } => self.transform_change(transform, change)
+                .map(|change| change.masked(Some(self.transform_mask(transform, mask)))),
+        }
+    }
+
+    /// Returns a paste of the mirror image of octree, for changes that replace a range with tiles regardless of what was there
+    fn get_mirrored_paste(&self, transform: SymmetryTransform, octree: &Octree) -> MapChange {
+        let transformed = self.transform_octree(transform, octree);
+        let aabb = transformed.get_aabb();
+        let min = aabb.get_min();
+
+        let mut copied = Octree::new(AABB::from_extents(Point::zeros(), aabb.get_max() - min), octree::DEFAULT_MAX);
+
+        transformed.iter().for_each(|tile_data| {
+            copied.insert(tile_data.with_point(tile_data.get_point() - min)).ok();
+        });
+
+        copied.compress();
+
+        MapChange::MapPaste {
+            clipboard: Clipboard::new(copied, Rotation3::identity()),
+            center: aabb.center,
+            rotation: Rotation3::identity()
+        }
+    }
+
+    /// Returns the rotation that faces the mirror image of the direction that rotation faces, assuming it only turns about the y axis
+    pub fn transform_rotation(&self, transform: SymmetryTransform, rotation: Rotation3<f32>) -> Rotation3<f32> {
+        let angle = get_y_angle(rotation);
+
+        let angle = match transform {
+            SymmetryTransform::MirrorX => -angle,
+            SymmetryTransform::MirrorZ => std::f32::consts::PI - angle,
+            SymmetryTransform::Point => angle + std::f32::consts::PI,
+        };
+
+        Rotation3::from_axis_angle(&Vector3::y_axis(), angle)
+    }
+
+    /// Returns where an actor at coord_pos ends up. The actor's bounds are mirrored rather than just its position, so that actors with an
+    /// even width end up covering the mirrored tiles.
+    fn transform_actor_coord_pos(&self, transform: SymmetryTransform, coord_pos: Point, bounds: Bounds, rotation: Rotation3<f32>) -> Point {
+        let mut aabb = bounds.get_scaled_and_rotated_aabb(rotation);
+        aabb.center = coord_pos;
+
+        self.transform_aabb(transform, aabb).center
+    }
+
+    /// Returns the actor changes that make the mirrored copies of change. Inserted copies are given new ActorIDs, and removals remove
+    /// whichever actors of the same kind sit where the mirror images of the removed actor would be.
+    pub fn get_mirrored_actor_changes(&self, world: &mut World, change: &ActorChange) -> Vec<ActorChange> {
+        match change {
+            ActorChange::ActorInsertion { serialized } => {
+                self.mode.get_transforms().into_iter().filter_map(|transform| {
+                    let mut actor_world = actor::deserialize_actors(serialized).ok()?;
+
+                    let mut query = <(Entity, Read<CoordPos>, Read<Bounds>, Read<Rotation>)>::query();
+
+                    query.iter(&actor_world)
+                        .map(|(entity, coord_pos, bounds, rotation)| {
+                            let coord_pos = self.transform_actor_coord_pos(transform, coord_pos.value, *bounds, rotation.value);
+
+                            (*entity, coord_pos, self.transform_rotation(transform, rotation.value))
+                        })
+                        .collect::<Vec<(Entity, Point, Rotation3<f32>)>>()
+                        .into_iter()
+                        .for_each(|(entity, coord_pos, rotation)| {
+                            if let Some(mut entry) = actor_world.entry(entity) {
+                                entry.add_component(ActorID::new());
+                                entry.add_component(CoordPos::new(coord_pos));
+                                entry.add_component(Rotation { value: rotation });
+                            }
+                        });
+
+                    actor::serialize_actors_in_world(&mut actor_world).ok()
+                        .map(|serialized| ActorChange::ActorInsertion { serialized })
+                })
+                .collect()
+            },
+            ActorChange::ActorRemoval(actor_id) => {
+                let mut query = <(Read<ActorID>, Read<ActorSceneKey>, Read<CoordPos>, Read<Bounds>, Read<Rotation>)>::query();
+
+                let removed = query.iter(world)
+                    .find(|(id, _, _, _, _)| id.val() == *actor_id)
+                    .map(|(_, key, coord_pos, bounds, rotation)| (key.0.clone(), coord_pos.value, *bounds, rotation.value));
+
+                let (key, coord_pos, bounds, rotation) = match removed {
+                    Some(removed) => removed,
+                    None => return Vec::new()
+                };
+
+                self.mode.get_transforms().into_iter().filter_map(|transform| {
+                    let mirrored = self.transform_actor_coord_pos(transform, coord_pos, bounds, rotation);
+
+                    query.iter(world)
+                        .find(|(id, other_key, other_coord_pos, _, _)|
+                            id.val() != *actor_id && other_key.0 == key && other_coord_pos.value == mirrored
+                        )
+                        .map(|(id, _, _, _, _)| ActorChange::ActorRemoval(id.val()))
+                })
+                .collect()
+            }
+        }
+    }
+}
+
+/// An edit along with what's needed to make its mirrored copies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SymmetricEdit {
+    /// Mirrored copies of map changes are made by each client as the change is applied, since the result can depend on what is in the map
+    Map(MapChange),
+    /// Actor changes including their mirrored copies, which are made by the sender so that every client has the same ActorIDs
+    Actor(Vec<ActorChange>),
+}
+
+/// Applies edit and its mirrored copies, which are stored in the history of store_history as a single step
+pub fn apply_symmetric_edit(world: &mut World, resources: &mut Resources, edit: SymmetricEdit, symmetry: Symmetry, store_history: Option<u32>) {
+    history::group_steps(world, resources, store_history, |world, resources| {
+        match edit {
+            SymmetricEdit::Map(change) => {
+                let map = match resources.get::<Map>() {
+                    Some(map) => *map,
+                    None => return
+                };
+
+                //the copies make the change again on the other side of the axis, going by the tiles that are there
+                let mirrored = symmetry.mode.get_transforms().into_iter()
+                    .filter_map(|transform| symmetry.transform_change(transform, &change))
+                    .collect::<Vec<MapChange>>();
+
+                std::iter::once(change).chain(mirrored).for_each(|change| {
+                    if let Some(octree) = change.get_octree(world, resources) {
+                        map.change(world, resources, octree, store_history);
+                    }
+                });
+            },
+            SymmetricEdit::Actor(changes) => {
+                changes.iter().for_each(|change| {
+                    actor::change(world, resources, change, store_history);
+                });
+            }
+        }
+    });
+}
+
+/// Creates the function that turns this client's outgoing map and actor changes into symmetric ones while symmetry is on. Changes that
+/// aren't stored in the history, like loading a document, are left alone.
+pub fn create_symmetry_fn() -> Box<dyn FnMut(&mut World, &mut Resources)> {
+
+    let mut query = <(Entity, Read<MessageSender>)>::query();
+
+    Box::new(move |world, resources| {
+        let symmetry = match resources.get::<Symmetry>() {
+            Some(symmetry) if symmetry.mode != SymmetryMode::Off => *symmetry,
+            _ => return
+        };
+
+        query.iter(world)
+            .filter(|(_, message_sender)| match message_sender.data_type {
+                DataType::MapChange { store_history: Some(_), .. } | DataType::ActorChange { store_history: Some(_), .. } => true,
+                _ => false
+            })
+            .map(|(entity, message_sender)| (*entity, message_sender.clone()))
+            .collect::<Vec<(Entity, MessageSender)>>()
+            .into_iter()
+            .for_each(|(entity, message_sender)| {
+
+                let data_type = match message_sender.data_type {
+                    DataType::MapChange { change, store_history } => DataType::SymmetricChange {
+                        edit: SymmetricEdit::Map(change),
+                        symmetry,
+                        store_history
+                    },
+                    DataType::ActorChange { change, store_history } => {
+                        let mut changes = symmetry.get_mirrored_actor_changes(world, &change);
+                        changes.insert(0, change);
+
+                        DataType::SymmetricChange {
+                            edit: SymmetricEdit::Actor(changes),
+                            symmetry,
+                            store_history
+                        }
+                    },
+                    data_type => data_type
+                };
+
+                if let Some(mut entry) = world.entry(entity) {
+                    entry.add_component(MessageSender {
+                        data_type,
+                        message_type: message_sender.message_type
+                    });
+                }
+            });
+    })
+}
+
+pub fn create_symmetry_input_system() -> impl systems::Runnable {
+    let cycle_symmetry = input::Action("cycle_symmetry".to_string());
+    let set_symmetry_axis = input::Action("set_symmetry_axis".to_string());
+
+    SystemBuilder::new("symmetry_input_system")
+        .read_resource::<ClientID>()
+        .with_query(<(Read<SelectionBox>, Read<CoordPos>, Read<ClientID>)>::query()
+            .filter(component::<Active>()))
+        .with_query(<(Read<input::InputActionComponent>, Read<input::Action>)>::query())
+        .build(move |commands, world, client_id, queries| {
+
+            let (selection_box_query, input_query) = queries;
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                (*a == &cycle_symmetry || *a == &set_symmetry_axis) && input_component.just_pressed()
+            }).for_each(|(_, action)| {
+
+                if action == &cycle_symmetry {
+                    commands.exec_mut(|_, resources| {
+                        if let Some(mut symmetry) = resources.get_mut::<Symmetry>() {
+                            symmetry.mode = symmetry.mode.next();
+                        }
+                    });
+                } else {
+                    selection_box_query.iter(world).filter(|(_, _, id)| id.val() == client_id.val()).for_each(|(selection_box, coord_pos, _)| {
+                        let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+
+                        commands.exec_mut(move |_, resources| {
+                            if let Some(mut symmetry) = resources.get_mut::<Symmetry>() {
+                                symmetry.set_axis_from_range(aabb);
+                            }
+                        });
+                    });
+                }
+            });
+        })
+}
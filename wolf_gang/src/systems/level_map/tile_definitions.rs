@@ -0,0 +1,141 @@
+//! What each tile id looks like, as read from config/tiles.ron. Tiles are cut out of a single sheet of square cells, and each tile names the
+//! cells its top, walls and underside come from, how its walls repeat, and the material it gets drawn with. Ids that aren't defined fall back
+//! to the cell with the same index on the sheet, so maps made before a tile was defined still draw.
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+
+type Vector2D = nalgebra::Vector2<f32>;
+
+/// A cell of the tile sheet as its column and row
+pub type Cell = (u32, u32);
+
+/// The cell that walls start from when a tile doesn't say otherwise
+pub const DEFAULT_SIDE_CELL: Cell = (0, 9);
+
+/// How the wall texture repeats going up and down walls, so that tall walls don't stretch it. Heights are in world units.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WallRepeat {
+    /// Walls at or above this height repeat every repeat_above
+    pub start_above: f32,
+    pub repeat_above: f32,
+    /// Walls at or below this height repeat every repeat_below
+    pub start_below: f32,
+    pub repeat_below: f32,
+}
+
+impl Default for WallRepeat {
+    fn default() -> Self {
+        WallRepeat {
+            start_above: 2.,
+            repeat_above: 2.,
+            start_below: 0.,
+            repeat_below: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileDefinition {
+    pub name: String,
+    pub top: Cell,
+    /// Walls run across the sheet from this cell by their position in the world, and up it by their height
+    pub side: Cell,
+    /// Drawn on the underside of tiles that overhang other tiles
+    pub bottom: Cell,
+    pub wall_repeat: WallRepeat,
+    /// The material to draw the tile with instead of the sheet's material
+    #[serde(default)]
+    pub material: Option<String>,
+    /// The cell shown for the tile in the palette
+    pub icon: Cell,
+}
+
+pub const TILE_DEFINITIONS_PATH: &str = "res://config/tiles.ron";
+
+/// Resource holding the tile sheet and the definition of every tile id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileDefinitions {
+    /// The image the cells are cut out of, which the palette takes its icons from and the .obj export saves next to the model
+    pub sheet: String,
+    /// The material tiles get drawn with unless they name their own
+    pub material: String,
+    pub tile_pixels: f32,
+    pub sheet_pixels: f32,
+    pub tiles: HashMap<u32, TileDefinition>,
+}
+
+impl Default for TileDefinitions {
+    fn default() -> Self {
+        TileDefinitions {
+            sheet: "res://images/ground.png".to_string(),
+            material: "res://materials/ground.material".to_string(),
+            tile_pixels: 128.,
+            sheet_pixels: 2048.,
+            tiles: HashMap::new(),
+        }
+    }
+}
+
+impl TileDefinitions {
+
+    pub fn from_ron(s: &str) -> Result<Self, ron::Error> {
+        ron::de::from_str::<TileDefinitions>(s)
+    }
+
+    /// Returns the size of a cell as a fraction of the sheet, which is how far apart cells are in uv coordinates
+    pub fn get_tile_size(&self) -> f32 {
+        self.tile_pixels / self.sheet_pixels
+    }
+
+    /// Returns the ids of every defined tile in the order they are listed in the palette
+    pub fn get_ids(&self) -> Vec<u32> {
+        let mut ids = self.tiles.keys().copied().collect::<Vec<u32>>();
+        ids.sort();
+
+        ids
+    }
+
+    /// Returns the cell with the same index as tile, counting along each row of the sheet
+    fn get_indexed_cell(&self, tile: u32) -> Cell {
+        let columns = ((self.sheet_pixels / self.tile_pixels) as u32).max(1);
+
+        (tile % columns, tile / columns)
+    }
+
+    pub fn get_name(&self, tile: u32) -> String {
+        self.tiles.get(&tile).map_or_else(|| format!("Tile {}", tile), |definition| definition.name.clone())
+    }
+
+    pub fn get_top(&self, tile: u32) -> Cell {
+        self.tiles.get(&tile).map_or_else(|| self.get_indexed_cell(tile), |definition| definition.top)
+    }
+
+    pub fn get_side(&self, tile: u32) -> Cell {
+        self.tiles.get(&tile).map_or(DEFAULT_SIDE_CELL, |definition| definition.side)
+    }
+
+    pub fn get_bottom(&self, tile: u32) -> Cell {
+        self.tiles.get(&tile).map_or_else(|| self.get_indexed_cell(tile), |definition| definition.bottom)
+    }
+
+    pub fn get_icon(&self, tile: u32) -> Cell {
+        self.tiles.get(&tile).map_or_else(|| self.get_indexed_cell(tile), |definition| definition.icon)
+    }
+
+    pub fn get_wall_repeat(&self, tile: u32) -> WallRepeat {
+        self.tiles.get(&tile).map_or_else(WallRepeat::default, |definition| definition.wall_repeat)
+    }
+
+    pub fn get_material(&self, tile: u32) -> &str {
+        self.tiles.get(&tile)
+            .and_then(|definition| definition.material.as_deref())
+            .unwrap_or(&self.material)
+    }
+
+    /// Returns where cell starts on the sheet in uv coordinates
+    pub fn get_uv_offset(&self, cell: Cell) -> Vector2D {
+        Vector2D::new(cell.0 as f32, cell.1 as f32) * self.get_tile_size()
+    }
+}
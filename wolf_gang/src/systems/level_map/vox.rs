@@ -0,0 +1,282 @@
+//! Reading and writing MagicaVoxel .vox files. A .vox file only keeps a palette index for each voxel, so tiles come in as full tiles on import
+//! and lose their shape on export; only the tile id survives the trip, by way of the palette index it maps to.
+
+use serde::{Serialize, Deserialize};
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    io::{Error, ErrorKind},
+};
+
+use crate::collections::octree::{self, PointData};
+
+use super::TileData;
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+const VOX_VERSION: i32 = 150;
+
+/// Where the mapping between palette indices and tile ids is read from
+pub const VOX_TILE_MAPPING_PATH: &str = "res://config/vox_tiles.ron";
+
+/// Models in a .vox file can't be bigger than this along any axis
+pub const MAX_VOX_DIMENSION: i32 = 256;
+
+/// One model from a .vox file. MagicaVoxel has z pointing up, so points are swapped over to our y up as they are read and written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxModel {
+    pub dimensions: Point,
+    /// The position and palette index of each voxel, where index 0 is never used since it means empty
+    pub voxels: Vec<(Point, u8)>,
+}
+
+/// The contents of a .vox file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vox {
+    pub models: Vec<VoxModel>,
+    /// RGBA for palette indices 1 to 255, or None for MagicaVoxel's default palette
+    pub palette: Option<Vec<[u8; 4]>>,
+}
+
+/// How palette indices in a .vox file map to tile ids. Anything not in tiles maps to the tile one less than the index, so that index 1
+/// is tile 0.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct VoxTileMapping {
+    #[serde(default)]
+    pub tiles: HashMap<u8, u32>,
+}
+
+impl VoxTileMapping {
+
+    pub fn from_ron(s: &str) -> Result<Self, ron::Error> {
+        ron::de::from_str::<VoxTileMapping>(s)
+    }
+
+    pub fn get_tile(&self, index: u8) -> u32 {
+        self.tiles.get(&index).copied().unwrap_or_else(|| index.saturating_sub(1) as u32)
+    }
+
+    /// Returns the palette index for tile, or None for tiles past the last index that aren't mapped to one
+    pub fn get_index(&self, tile: u32) -> Option<u8> {
+        self.tiles.iter()
+            .filter(|(_, mapped)| **mapped == tile)
+            .map(|(index, _)| *index)
+            .min()
+            .or_else(|| if tile < 255 { Some(tile as u8 + 1) } else { None })
+    }
+}
+
+impl VoxModel {
+
+    /// Returns the octree of the model's voxels as tiles, with the model's minimum corner at min. Every tile is a full tile.
+    pub fn to_octree(&self, min: Point, mapping: &VoxTileMapping) -> Octree {
+        let mut octree = Octree::new(AABB::from_extents(min, min + self.dimensions - Point::new(1,1,1)), octree::DEFAULT_MAX);
+
+        self.voxels.iter().for_each(|(point, index)| {
+            octree.insert(TileData::new(mapping.get_tile(*index), min + point)).ok();
+        });
+
+        octree.compress();
+
+        octree
+    }
+
+    /// Makes a model out of the tiles in octree, relative to the minimum corner of the octree's range. Tile shapes are dropped, and tiles
+    /// without a palette index are an error rather than being merged into another tile.
+    pub fn from_octree(octree: &Octree, mapping: &VoxTileMapping) -> Result<Self, Error> {
+        let aabb = octree.get_aabb();
+        let dimensions = aabb.dimensions;
+
+        if dimensions.iter().any(|dimension| *dimension > MAX_VOX_DIMENSION) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!(".vox models can't be bigger than {} along any axis", MAX_VOX_DIMENSION)))
+        }
+
+        let min = aabb.get_min();
+
+        let voxels = octree.iter()
+            .map(|tile_data| match mapping.get_index(tile_data.get_tile()) {
+                Some(index) => Ok((tile_data.get_point() - min, index)),
+                None => Err(Error::new(ErrorKind::InvalidInput, format!("Tile {} has no palette index in {}", tile_data.get_tile(), VOX_TILE_MAPPING_PATH)))
+            })
+            .collect::<Result<Vec<(Point, u8)>, Error>>()?;
+
+        Ok(VoxModel {
+            dimensions,
+            voxels
+        })
+    }
+}
+
+impl Vox {
+
+    /// Parses a .vox file. Transform and group nodes aren't read, so every model starts at the origin.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.read_bytes(4)? != b"VOX " {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a .vox file"))
+        }
+
+        //the version doesn't change anything about the chunks read here
+        reader.read_i32()?;
+
+        //MAIN has no content of its own, only children
+        let (id, _, children) = reader.read_chunk()?;
+
+        if id != b"MAIN" {
+            return Err(Error::new(ErrorKind::InvalidData, "Expected the MAIN chunk"))
+        }
+
+        let mut children = Reader { bytes: children, position: 0 };
+
+        let mut vox = Vox::default();
+        let mut dimensions: Option<Point> = None;
+
+        while !children.is_empty() {
+            let (id, content, _) = children.read_chunk()?;
+            let mut content = Reader { bytes: content, position: 0 };
+
+            match id {
+                b"SIZE" => {
+                    let (x, y, z) = (content.read_i32()?, content.read_i32()?, content.read_i32()?);
+                    dimensions = Some(Point::new(x, z, y));
+                },
+                b"XYZI" => {
+                    let dimensions = dimensions.take()
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "XYZI chunk came before its SIZE chunk"))?;
+
+                    let count = content.read_i32()?;
+
+                    //every voxel takes 4 bytes, so a count the chunk can't hold is rejected before anything gets allocated for it
+                    if count < 0 || count as usize > content.remaining() / 4 {
+                        return Err(Error::new(ErrorKind::InvalidData, "XYZI chunk has more voxels than it holds"))
+                    }
+
+                    let mut voxels = Vec::with_capacity(count as usize);
+
+                    for _ in 0..count {
+                        let voxel = content.read_bytes(4)?;
+                        voxels.push((Point::new(voxel[0] as i32, voxel[2] as i32, voxel[1] as i32), voxel[3]));
+                    }
+
+                    vox.models.push(VoxModel { dimensions, voxels });
+                },
+                b"RGBA" => {
+                    //the colour for index i is stored at i - 1, with the last entry going unused
+                    let palette = (0..255)
+                        .map(|_| content.read_bytes(4).map(|rgba| [rgba[0], rgba[1], rgba[2], rgba[3]]))
+                        .collect::<Result<Vec<[u8; 4]>, Error>>()?;
+
+                    vox.palette = Some(palette);
+                },
+                _ => {}
+            }
+        }
+
+        Ok(vox)
+    }
+
+    /// Returns the .vox file for self
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut children = Vec::new();
+
+        for model in &self.models {
+            if model.dimensions.iter().any(|dimension| *dimension < 1 || *dimension > MAX_VOX_DIMENSION) {
+                return Err(Error::new(ErrorKind::InvalidInput, format!(".vox models have to be from 1 to {} along every axis", MAX_VOX_DIMENSION)))
+            }
+
+            let mut size = Vec::with_capacity(12);
+            size.extend_from_slice(&model.dimensions.x.to_le_bytes());
+            size.extend_from_slice(&model.dimensions.z.to_le_bytes());
+            size.extend_from_slice(&model.dimensions.y.to_le_bytes());
+
+            write_chunk(&mut children, b"SIZE", &size, &[]);
+
+            let mut xyzi = Vec::with_capacity(4 + model.voxels.len() * 4);
+            xyzi.extend_from_slice(&(model.voxels.len() as i32).to_le_bytes());
+
+            for (point, index) in &model.voxels {
+                xyzi.extend_from_slice(&[point.x as u8, point.z as u8, point.y as u8, *index]);
+            }
+
+            write_chunk(&mut children, b"XYZI", &xyzi, &[]);
+        }
+
+        if let Some(palette) = &self.palette {
+            let mut rgba = Vec::with_capacity(256 * 4);
+
+            (0..256).for_each(|i| {
+                rgba.extend_from_slice(palette.get(i).unwrap_or(&[0, 0, 0, 0]));
+            });
+
+            write_chunk(&mut children, b"RGBA", &rgba, &[]);
+        }
+
+        let mut bytes = Vec::with_capacity(children.len() + 20);
+        bytes.extend_from_slice(b"VOX ");
+        bytes.extend_from_slice(&VOX_VERSION.to_le_bytes());
+
+        write_chunk(&mut bytes, b"MAIN", &[], &children);
+
+        Ok(bytes)
+    }
+}
+
+fn write_chunk(bytes: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    bytes.extend_from_slice(id);
+    bytes.extend_from_slice(&(content.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(&(children.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(content);
+    bytes.extend_from_slice(children);
+}
+
+/// The id, content and children of a chunk
+type Chunk<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+
+    fn read_bytes(&mut self, amount: usize) -> Result<&'a [u8], Error> {
+        let end = self.position + amount;
+
+        if end > self.bytes.len() {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "The .vox file ended early"))
+        }
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Error> {
+        self.read_bytes(4).map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_chunk(&mut self) -> Result<Chunk<'a>, Error> {
+        let id = self.read_bytes(4)?;
+        let content_size = self.read_i32()?;
+        let children_size = self.read_i32()?;
+
+        if content_size < 0 || children_size < 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Chunk has a negative size"))
+        }
+
+        Ok((id, self.read_bytes(content_size as usize)?, self.read_bytes(children_size as usize)?))
+    }
+}
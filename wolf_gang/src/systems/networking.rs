@@ -192,12 +192,35 @@ pub enum  DataType {
         change: crate::systems::actor::ActorChange,
         store_history: Option<u32>
     },
+    /// Sets the chunk dimensions and bounds of the map, sent before any map data when loading a document or joining a server
+    MapConfig(crate::systems::level_map::Map),
     MapInput(crate::collections::octree::Octree<i32, crate::systems::level_map::TileData>),
     ///Handles changes to map like insertion, removal, cutting, pasting, takes an optional u32 as store_history to store the change in the history for that client_id if need be
     MapChange{
         change: crate::systems::level_map::MapChange,
         store_history: Option<u32>
     },
+    /// A map or actor change made while symmetry was on, which gets applied along with its mirrored copies as a single history step
+    SymmetricChange{
+        edit: crate::systems::level_map::symmetry::SymmetricEdit,
+        symmetry: crate::systems::level_map::symmetry::Symmetry,
+        store_history: Option<u32>
+    },
+    /// Replaces everything within aabb with terrain generated from settings, along with any trees scattered over it, as a single history step
+    MapGenerate{
+        aabb: AABB,
+        settings: crate::systems::level_map::generator::GeneratorSettings,
+        trees: Option<crate::systems::actor::ActorChange>,
+        store_history: Option<u32>
+    },
+    /// Pastes a prefab's tiles into a selection box centered at center with the given rotation, along with its actors, as a single history step
+    PrefabStamp{
+        clipboard: crate::systems::level_map::clipboard::Clipboard,
+        center: Point,
+        rotation: nalgebra::Rotation3<f32>,
+        actors: Option<crate::systems::actor::ActorChange>,
+        store_history: Option<u32>
+    },
     MapNew,
     HistoryStep{
         amount: i32,
@@ -708,37 +731,39 @@ fn client_handle_data(data: DataType, world: &mut World, resources: &mut Resourc
                 actor,
             };
 
-            actor::change(world, &change, store_history);
+            actor::change(world, resources, &change, store_history);
 
         },
+        DataType::MapConfig(map) => {
+            crate::systems::level_map::set_map_config(world, resources, map);
+        },
         DataType::MapInput(r) => {
             if let Some(map) = resources.get::<crate::systems::level_map::Map>().map(|map| *map) {
-                map.change(world, r, None);
+                map.change(world, resources, r, None);
             }
         },
         DataType::MapChange{ change, store_history } => {
-
-            use crate::systems::{
-                level_map,
-                level_map::MapChange
-            };
-
             if let Some(map) = resources.get::<crate::systems::level_map::Map>().map(|map| *map) {
-
-                match change {
-                    MapChange::MapInsertion { aabb, tile_data } => {
-                        map.change(world, level_map::fill_octree_from_aabb(aabb, Some(tile_data)), store_history);
-                    },
-                    MapChange::MapRemoval(aabb) => {
-                        map.change(world, level_map::fill_octree_from_aabb(aabb, None), store_history)
-                    },
+                if let Some(octree) = change.get_octree(world, resources) {
+                    map.change(world, resources, octree, store_history);
                 }
-
             }
         },
+        DataType::SymmetricChange{ edit, symmetry, store_history } => {
+            crate::systems::level_map::symmetry::apply_symmetric_edit(world, resources, edit, symmetry, store_history);
+        },
+        DataType::MapGenerate{ aabb, settings, trees, store_history } => {
+            crate::systems::level_map::generator::generate(world, resources, aabb, &settings, trees, store_history);
+        },
+        DataType::PrefabStamp{ clipboard, center, rotation, actors, store_history } => {
+            crate::systems::level_map::prefab::stamp(world, resources, &clipboard, center, rotation, actors, store_history);
+        },
         DataType::MapNew => {
             crate::systems::level_map::map_reset(world, resources);
             crate::systems::actor::free_all(world);
+            if let Some(mut actor_index) = resources.get_mut::<crate::systems::actor::ActorIndex>() {
+                actor_index.clear();
+            }
             crate::systems::history::empty_all(world);
         },
         DataType::HistoryStep{ amount, client_id } => {
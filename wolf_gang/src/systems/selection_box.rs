@@ -11,12 +11,15 @@ use serde::{Serialize, Deserialize};
 use std::cmp::Ordering;
 
 use crate::{
-    geometry::aabb,
+    geometry::{aabb, ray::Ray},
     editor,
     node,
-    nodes::actor_palette::{
-        ActorPalette,
-        ENTITY_REFS,
+    nodes::{
+        actor_palette::{
+            ActorPalette,
+            ENTITY_REFS,
+        },
+        prefab_name_dialog::PrefabNameDialog,
     },
     systems::{
         actor,
@@ -636,26 +639,39 @@ pub fn create_actor_tool_system() -> impl systems::Runnable {
                             let coord_pos = coord_pos.value;
                             let dimensions = selection_box.aabb.dimensions;
                             let client_id = client_id.val();
-                            command.exec_mut(move |world, _| {
-                                actor::select_actors_from_range(world, AABB::new(coord_pos, dimensions))
-                                    .into_iter().for_each(|entity| {
-                                        if let Some(Some(actor_id)) = world.entry(entity).map(|entry| {
-                                                entry.get_component::<actor::ActorID>().ok().copied()
-                                            }
-                                        ) {
-                                            world.push(
-                                                (
-                                                    MessageSender{
-                                                        data_type: DataType::ActorChange {
-                                                            change: actor::ActorChange::ActorRemoval(actor_id.val()),
-                                                            store_history: Some(client_id)
-                                                        },
-                                                        message_type: MessageType::Ordered
-                                                    },
-                                                )
-                                            );
+                            command.exec_mut(move |world, resources| {
+                                let range = AABB::new(coord_pos, dimensions);
+
+                                let mut entities = actor::select_actors_from_range(world, resources, range);
+
+                                //with no actors inside of the box, the first one straight below it gets removed instead
+                                if entities.is_empty() {
+                                    let min = range.get_min();
+                                    let max = range.get_max();
+
+                                    let origin = Vector3D::new((min.x + max.x + 1) as f32 / 2., (max.y + 1) as f32, (min.z + max.z + 1) as f32 / 2.);
+
+                                    entities.extend(actor::pick_actor(world, resources, Ray::new(origin, -Vector3D::y())));
+                                }
+
+                                entities.into_iter().for_each(|entity| {
+                                    if let Some(Some(actor_id)) = world.entry(entity).map(|entry| {
+                                            entry.get_component::<actor::ActorID>().ok().copied()
                                         }
-                                    });
+                                    ) {
+                                        world.push(
+                                            (
+                                                MessageSender{
+                                                    data_type: DataType::ActorChange {
+                                                        change: actor::ActorChange::ActorRemoval(actor_id.val()),
+                                                        store_history: Some(client_id)
+                                                    },
+                                                    message_type: MessageType::Ordered
+                                                },
+                                            )
+                                        );
+                                    }
+                                });
                             })
                             
                         }
@@ -665,16 +681,33 @@ pub fn create_actor_tool_system() -> impl systems::Runnable {
         })
 }
 
-/// The system responsible for the tile tool functions, such as insertion, removal, and (to be added) copy, paste, painting
+/// The system responsible for the tile tool functions, such as insertion, removal, painting, copy, cut, paste, rotating, mirroring, flood fill and 
+/// magic wand selection
 pub fn create_tile_tool_system() -> impl systems::Runnable {
     let insertion = input::Action(("insertion").to_string());
     let removal = input::Action(("removal").to_string());
+    let paint = input::Action(("paint").to_string());
+    let copy = input::Action(("copy").to_string());
+    let cut = input::Action(("cut").to_string());
+    let paste = input::Action(("paste").to_string());
+    let rotate_terrain_left = input::Action(("rotate_terrain_left").to_string());
+    let rotate_terrain_right = input::Action(("rotate_terrain_right").to_string());
+    let mirror_terrain_x = input::Action(("mirror_terrain_x").to_string());
+    let mirror_terrain_z = input::Action(("mirror_terrain_z").to_string());
+    let flood_fill = input::Action(("flood_fill").to_string());
+    let magic_wand = input::Action(("magic_wand").to_string());
+    let cycle_connectivity = input::Action(("cycle_connectivity").to_string());
+    let save_prefab = input::Action(("save_prefab").to_string());
+    let stamp_prefab = input::Action(("stamp_prefab").to_string());
+    let generate_terrain = input::Action(("generate_terrain").to_string());
 
     SystemBuilder::new("tile_tool_system")
         .read_resource::<ClientID>()
         .read_resource::<level_map::Map>()
         .read_resource::<editor::PaletteSelection>()
-        .with_query(<(Read<SelectionBox>, Read<level_map::CoordPos>, Read<ClientID>)>::query() //all selection_boxes
+        .read_resource::<editor::ShapeSelection>()
+        .read_resource::<level_map::flood::FloodSettings>()
+        .with_query(<(Entity, Read<SelectionBox>, Read<level_map::CoordPos>, Read<ClientID>, Read<SelectionBoxRotation>, TryRead<level_map::flood::SelectionMask>)>::query() //all selection_boxes
             .filter(component::<TerrainToolBox>() & component::<Active>()))
         .with_query(<(Read<SelectionBox>, Read<level_map::CoordPos>, Read<ClientID>)>::query() //only moved selection_boxes
             .filter(component::<TerrainToolBox>() & component::<Active>() & maybe_changed::<level_map::CoordPos>()))
@@ -682,69 +715,323 @@ pub fn create_tile_tool_system() -> impl systems::Runnable {
         .build(move |commands, world, resources, queries| {
 
             let (selection_box_query, selection_box_moved_query, input_query) = queries;
-            let (client_id, map, tile_selection) = resources;
+            let (client_id, map, tile_selection, shape_selection, flood_settings) = resources;
 
             input_query.iter(world).filter(|(_, a)| {
-                *a == &insertion || *a == &removal
+                *a == &insertion || *a == &removal || *a == &paint
             }).for_each(|(input_component, action)|  {
-                selection_box_query.iter(world).filter(|(_, _, id)| id.val() == client_id.val()).for_each(|(selection_box, coord_pos, _)| {
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(_, selection_box, coord_pos, _, _, mask)| {
                     
                     let moved = selection_box_moved_query.iter(world).any(|(_, _, id)| id.val() == client_id.val());
 
                     if input_component.just_pressed() 
                     || (input_component.is_held() && moved) 
                     {
-                        if action == &insertion {
-                            let map = **map;
-                            let tile_selection = **tile_selection;
+                        let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
 
-                            let client_id = client_id.val();
-                            let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+                        let change = if action == &insertion {
+                            let tile_data = level_map::TileData::new(tile_selection.val(), Point::zeros()).with_shape(shape_selection.val());
 
-                            commands.exec_mut(move |world, _|{
-                
-                                let tile_data = level_map::TileData::new(tile_selection.val(), Point::zeros());
-            
-                                if map.can_change(world, level_map::fill_octree_from_aabb(aabb, Some(tile_data))).is_ok() {
-                                    world.push(
-                                        (
-                                            MessageSender{
-                                                data_type: DataType::MapChange{
-                                                    store_history: Some(client_id),
-                                                    change: level_map::MapChange::MapInsertion{ aabb, tile_data },                               
-                                                },
-                                                message_type: MessageType::Ordered
+                            level_map::MapChange::MapInsertion{ aabb, tile_data }
+                        } else if action == &removal {
+                            level_map::MapChange::MapRemoval(aabb)
+                        } else {
+                            level_map::MapChange::MapRepaint{ aabb, tile: tile_selection.val() }
+                        };
+
+                        let change = change.masked(mask.filter(|mask| mask.applies_to(aabb)).cloned());
+
+                        let map = **map;
+                        let client_id = client_id.val();
+
+                        commands.exec_mut(move |world, resources|{
+                            let can_change = change.get_octree(world, resources)
+                                .map_or(false, |octree| map.can_change(world, resources, octree).is_ok());
+
+                            if can_change {
+                                world.push(
+                                    (
+                                        MessageSender{
+                                            data_type: DataType::MapChange{
+                                                store_history: Some(client_id),
+                                                change: change.clone(),
                                             },
-                                        ),                  
-                                    );
+                                            message_type: MessageType::Ordered
+                                        },
+                                    ),
+                                );
+                            }
+                        });
+                    }
+                })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                (*a == &copy || *a == &cut || *a == &paste) && input_component.just_pressed()
+            }).for_each(|(_, action)| {
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(entity, selection_box, coord_pos, _, rotation, mask)| {
+
+                    let entity = *entity;
+                    let client_id = client_id.val();
+                    let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+                    let center = coord_pos.value;
+                    let rotation = rotation.value;
+
+                    if action == &copy || action == &cut {
+                        let map = **map;
+                        let is_cut = action == &cut;
+                        let mask = mask.filter(|mask| mask.applies_to(aabb)).cloned();
+
+                        commands.exec_mut(move |world, resources| {
+                            if let Some(clipboard) = level_map::clipboard::Clipboard::from_range(world, resources, aabb, rotation, mask.as_ref()) {
+                                if let Some(mut entry) = world.entry(entity) {
+                                    entry.add_component(clipboard);
                                 }
-                            });
+                            }
 
-                        } else if action == &removal {
-                            let map = **map;
-                            let client_id = client_id.val();
-                            let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+                            if !is_cut {
+                                return
+                            }
 
-                            commands.exec_mut(move |world, _|{
-                                if map.can_change(world, level_map::fill_octree_from_aabb(aabb, None)).is_ok() {
-                                    world.push(
-                                        (
-                                            MessageSender{
-                                                data_type: DataType::MapChange{
-                                                    store_history: Some(client_id),
-                                                    change: level_map::MapChange::MapRemoval(aabb),                               
-                                                },
-                                                message_type: MessageType::Ordered
+                            let change = level_map::MapChange::MapRemoval(aabb).masked(mask.clone());
+
+                            let can_change = change.get_octree(world, resources)
+                                .map_or(false, |octree| map.can_change(world, resources, octree).is_ok());
+
+                            if can_change {
+                                world.push(
+                                    (
+                                        MessageSender{
+                                            data_type: DataType::MapChange{
+                                                store_history: Some(client_id),
+                                                change,
                                             },
-                                        ),                  
-                                    );
-                                }
+                                            message_type: MessageType::Ordered
+                                        },
+                                    ),
+                                );
+                            }
+                        });
+
+                    } else if action == &paste {
+                        commands.exec_mut(move |world, _| {
+                            let clipboard = world.entry(entity).and_then(|entry| {
+                                entry.get_component::<level_map::clipboard::Clipboard>().ok().cloned()
                             });
-                        }
-                        
+
+                            if let Some(clipboard) = clipboard {
+                                world.push(
+                                    (
+                                        MessageSender{
+                                            data_type: DataType::MapChange{
+                                                store_history: Some(client_id),
+                                                change: level_map::MapChange::MapPaste{ clipboard, center, rotation },
+                                            },
+                                            message_type: MessageType::Ordered
+                                        },
+                                    ),
+                                );
+                            }
+                        });
                     }
                 })
-            })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                (*a == &save_prefab || *a == &stamp_prefab) && input_component.just_pressed()
+            }).for_each(|(_, action)| {
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(_, selection_box, coord_pos, _, rotation, _)| {
+
+                    let client_id = client_id.val();
+                    let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+                    let center = coord_pos.value;
+                    let rotation = rotation.value;
+
+                    if action == &save_prefab {
+                        //the prefab gets made once the dialog is confirmed, so that it has whatever is in the range at the time
+                        PrefabNameDialog::prompt(aabb, rotation);
+
+                    } else if action == &stamp_prefab {
+                        commands.exec_mut(move |world, resources| {
+                            let prefab = match resources.get::<level_map::prefab::Prefab>() {
+                                Some(prefab) => prefab.clone(),
+                                None => return
+                            };
+
+                            let actors = prefab.get_stamped_actors(center, rotation)
+                                .map(|serialized| actor::ActorChange::ActorInsertion{ serialized });
+
+                            world.push(
+                                (
+                                    MessageSender{
+                                        data_type: DataType::PrefabStamp{
+                                            clipboard: prefab.get_clipboard().clone(),
+                                            center,
+                                            rotation,
+                                            actors,
+                                            store_history: Some(client_id),
+                                        },
+                                        message_type: MessageType::Ordered
+                                    },
+                                ),
+                            );
+                        });
+                    }
+                })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                *a == &generate_terrain && input_component.just_pressed()
+            }).for_each(|_| {
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(_, selection_box, coord_pos, _, _, _)| {
+
+                    let client_id = client_id.val();
+                    let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+
+                    commands.exec_mut(move |world, resources| {
+                        let settings = match resources.get_mut::<level_map::generator::GeneratorSettings>() {
+                            Some(mut settings) => {
+                                let current = settings.clone();
+
+                                //pick a new seed so that generating again gives different terrain
+                                settings.seed = uuid::Uuid::new_v4().as_u128() as u64;
+
+                                current
+                            },
+                            None => return
+                        };
+
+                        let trees = settings.get_tree_change(aabb);
+
+                        world.push(
+                            (
+                                MessageSender{
+                                    data_type: DataType::MapGenerate{
+                                        aabb,
+                                        settings,
+                                        trees,
+                                        store_history: Some(client_id),
+                                    },
+                                    message_type: MessageType::Ordered
+                                },
+                            ),
+                        );
+                    });
+                })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                (*a == &rotate_terrain_left || *a == &rotate_terrain_right || *a == &mirror_terrain_x || *a == &mirror_terrain_z) 
+                && input_component.just_pressed()
+            }).for_each(|(_, action)| {
+
+                let transform = if action == &rotate_terrain_left {
+                    level_map::region::RegionTransform::Rotate(1)
+                } else if action == &rotate_terrain_right {
+                    level_map::region::RegionTransform::Rotate(-1)
+                } else if action == &mirror_terrain_x {
+                    level_map::region::RegionTransform::MirrorX
+                } else {
+                    level_map::region::RegionTransform::MirrorZ
+                };
+
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(_, selection_box, coord_pos, _, _, _)| {
+
+                    let client_id = client_id.val();
+                    let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+
+                    commands.push(
+                        (
+                            MessageSender{
+                                data_type: DataType::MapChange{
+                                    store_history: Some(client_id),
+                                    change: level_map::MapChange::MapTransform{ aabb, transform },
+                                },
+                                message_type: MessageType::Ordered
+                            },
+                        )
+                    );
+                })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                (*a == &flood_fill || *a == &magic_wand) && input_component.just_pressed()
+            }).for_each(|(_, action)| {
+                selection_box_query.iter(world).filter(|(_, _, _, id, _, _)| id.val() == client_id.val()).for_each(|(entity, selection_box, coord_pos, _, _, _)| {
+
+                    let entity = *entity;
+                    let client_id = client_id.val();
+                    let aabb = AABB::new(coord_pos.value, selection_box.aabb.dimensions);
+                    let tile = tile_selection.val();
+                    let connectivity = flood_settings.connectivity;
+                    let max_volume = flood_settings.max_volume;
+                    let is_fill = action == &flood_fill;
+
+                    commands.exec_mut(move |world, resources| {
+                        let start = match level_map::flood::get_start_in_range(world, resources, aabb) {
+                            Some(start) => start,
+                            None => return
+                        };
+
+                        if is_fill {
+                            world.push(
+                                (
+                                    MessageSender{
+                                        data_type: DataType::MapChange{
+                                            store_history: Some(client_id),
+                                            change: level_map::MapChange::MapFloodFill{ start, tile, connectivity, max_volume },
+                                        },
+                                        message_type: MessageType::Ordered
+                                    },
+                                ),
+                            );
+                        } else {
+                            let selected = level_map::flood::flood_select(world, resources, start, connectivity, max_volume);
+
+                            //select the bounds of everything that was picked, masking out the rest of the tiles within them
+                            if let Some(mask) = level_map::flood::SelectionMask::from_tiles(&selected) {
+                                let bounds = mask.get_aabb();
+                                let aabb = AABB::new(Point::zeros(), bounds.dimensions);
+
+                                if let Some(mut entry) = world.entry(entity) {
+                                    entry.add_component(mask);
+                                }
+
+                                world.push((
+                                    ClientID::new(client_id),
+                                    UpdateBounds{
+                                        coord_pos: bounds.center,
+                                        aabb
+                                    }
+                                ));
+
+                                world.push(
+                                    (
+                                        MessageSender{
+                                            data_type: DataType::UpdateSelectionBounds{
+                                                client_id,
+                                                coord_pos: bounds.center,
+                                                aabb
+                                            },
+                                            message_type: MessageType::Ordered
+                                        },
+                                    ),
+                                );
+                            }
+                        }
+                    });
+                })
+            });
+
+            input_query.iter(world).filter(|(input_component, a)| {
+                *a == &cycle_connectivity && input_component.just_pressed()
+            }).for_each(|_| {
+                commands.exec_mut(|_, resources| {
+                    if let Some(mut flood_settings) = resources.get_mut::<level_map::flood::FloodSettings>() {
+                        flood_settings.connectivity = flood_settings.connectivity.next();
+                    }
+                });
+            });
         })
 }
 
@@ -757,7 +1044,7 @@ pub fn create_rotation_system() -> impl systems::Runnable {
         .read_resource::<ClientID>()
         .with_query(<(Read<input::InputActionComponent>, Read<input::Action>)>::query())
         .with_query(<(Entity, Read<ClientID>)>::query()
-            .filter(component::<SelectionBox>() & component::<ActorToolBox>() & component::<Active>()))
+            .filter(component::<SelectionBox>() & (component::<ActorToolBox>() | component::<TerrainToolBox>()) & component::<Active>()))
         .build(move |commands, world, (time, client_id), queries| {
             let (input_query, selection_box_query) = queries;
 
@@ -791,12 +1078,34 @@ pub fn create_rotation_system() -> impl systems::Runnable {
                                 commands.exec_mut(move |world, _| {
                                     actor_tool_rotation(world, entity, rotation);
 
+                                    //terrain tool boxes only need their new bounds shared, the rotation itself is only used locally for pasting
+                                    let terrain_bounds = world.entry(entity).and_then(|entry| {
+                                        if entry.get_component::<TerrainToolBox>().is_err() {
+                                            return None
+                                        }
+
+                                        if let (Ok(coord_pos), Ok(selection_box)) = (entry.get_component::<level_map::CoordPos>(), entry.get_component::<SelectionBox>()) {
+                                            return Some((coord_pos.value, selection_box.aabb))
+                                        }
+
+                                        None
+                                    });
+
+                                    let data_type = match terrain_bounds {
+                                        Some((coord_pos, aabb)) => DataType::UpdateSelectionBounds {
+                                            client_id,
+                                            coord_pos,
+                                            aabb
+                                        },
+                                        None => DataType::ActorToolRotation {
+                                            client_id,
+                                            rotation
+                                        }
+                                    };
+
                                     world.push(
                                         (MessageSender{
-                                            data_type: DataType::ActorToolRotation {
-                                                client_id,
-                                                rotation
-                                            },
+                                            data_type,
                                             message_type: MessageType::Ordered
                                         },)
                                     );
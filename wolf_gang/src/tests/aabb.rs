@@ -1,7 +1,11 @@
-use crate::geometry::aabb;
+use crate::geometry::{
+    aabb,
+    ray::Ray,
+};
 
 type AABB = aabb::AABB<i32>;
 type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
 
 #[test]
 fn test_intersection() {
@@ -9,4 +13,110 @@ fn test_intersection() {
     let aabb2 = AABB::from_extents(Point::new(-1,-1,-1), Point::new(2,2,2));
 
     assert_eq!(AABB::from_extents(Point::new(0,0,0), Point::new(2,2,2)), aabb1.get_intersection(aabb2));
-}
\ No newline at end of file
+}
+
+#[test]
+fn ray_intersection() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+
+    let ray = Ray::new(Vector3D::new(-2., 0.5, 0.5), Vector3D::x());
+    let (near, far) = aabb.get_ray_intersection(ray).unwrap();
+
+    //integer boxes cover the whole cell of their max point
+    assert!((near - 2.).abs() < std::f32::EPSILON);
+    assert!((far - 6.).abs() < std::f32::EPSILON);
+}
+
+#[test]
+fn ray_intersection_miss() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+
+    //pointing away
+    assert!(aabb.get_ray_intersection(Ray::new(Vector3D::new(-2., 0.5, 0.5), -Vector3D::x())).is_none());
+    //parallel to the box but outside of it
+    assert!(aabb.get_ray_intersection(Ray::new(Vector3D::new(-2., 5., 0.5), Vector3D::x())).is_none());
+}
+
+#[test]
+fn ray_intersection_from_inside() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+
+    let (near, far) = aabb.get_ray_intersection(Ray::new(Vector3D::new(1., 1., 1.), Vector3D::y())).unwrap();
+
+    assert!(near.abs() < std::f32::EPSILON);
+    assert!((far - 3.).abs() < std::f32::EPSILON);
+}
+#[test]
+fn union() {
+    let aabb1 = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+    let aabb2 = AABB::from_extents(Point::new(-2,1,5), Point::new(1,2,6));
+
+    assert_eq!(AABB::from_extents(Point::new(-2,0,0), Point::new(3,3,6)), aabb1.union(aabb2));
+}
+
+#[test]
+fn contains_aabb() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+
+    assert!(aabb.contains_aabb(aabb));
+    assert!(aabb.contains_aabb(AABB::from_extents(Point::new(1,1,1), Point::new(3,2,3))));
+    assert!(!aabb.contains_aabb(AABB::from_extents(Point::new(1,1,1), Point::new(4,2,3))));
+}
+
+#[test]
+fn subtract() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(4,4,4));
+    let hole = AABB::from_extents(Point::new(1,1,1), Point::new(2,2,2));
+
+    let pieces = aabb.subtract(hole);
+    assert_eq!(6, pieces.len());
+
+    //the pieces are disjoint and cover everything in aabb except for the hole
+    let mut points = pieces.iter().flat_map(|piece| piece.iter_points()).collect::<Vec<Point>>();
+    let count = points.len();
+    points.sort_by_key(|pt| (pt.x, pt.y, pt.z));
+    points.dedup();
+
+    assert_eq!(count, points.len());
+    assert_eq!(aabb.get_volume() - hole.get_volume(), count);
+    assert!(points.iter().all(|pt| aabb.contains_point(*pt) && !hole.contains_point(*pt)));
+}
+
+#[test]
+fn subtract_edges() {
+    let aabb = AABB::from_extents(Point::new(0,0,0), Point::new(3,3,3));
+
+    //no overlap leaves the box untouched
+    assert_eq!(vec![aabb], aabb.subtract(AABB::from_extents(Point::new(5,5,5), Point::new(6,6,6))));
+    //covering the box removes all of it
+    assert!(aabb.subtract(AABB::from_extents(Point::new(-1,-1,-1), Point::new(4,4,4))).is_empty());
+    //cutting off one side leaves a single box
+    assert_eq!(
+        vec![AABB::from_extents(Point::new(2,0,0), Point::new(3,3,3))], 
+        aabb.subtract(AABB::from_extents(Point::new(-1,-1,-1), Point::new(1,4,4)))
+    );
+}
+
+#[test]
+fn subtract_float() {
+    let aabb = aabb::AABB::<f32>::from_extents(Vector3D::new(0.,0.,0.), Vector3D::new(4.,4.,4.));
+    let other = aabb::AABB::<f32>::from_extents(Vector3D::new(2.,-1.,-1.), Vector3D::new(5.,5.,5.));
+
+    assert_eq!(
+        vec![aabb::AABB::<f32>::from_extents(Vector3D::new(0.,0.,0.), Vector3D::new(2.,4.,4.))],
+        aabb.subtract(other)
+    );
+}
+
+#[test]
+fn iter_points() {
+    let aabb = AABB::from_extents(Point::new(-1,0,2), Point::new(1,1,2));
+
+    let points = aabb.iter_points().collect::<Vec<Point>>();
+
+    assert_eq!(aabb.get_volume(), points.len());
+    assert_eq!(Point::new(-1,0,2), points[0]);
+    assert_eq!(Point::new(0,0,2), points[1]);
+    assert_eq!(Point::new(-1,1,2), points[3]);
+    assert_eq!(Point::new(1,1,2), points[5]);
+}
@@ -0,0 +1,141 @@
+use legion::*;
+use nalgebra::Rotation3;
+
+use std::collections::HashSet;
+
+use crate::{
+    geometry::ray::Ray,
+    systems::{
+        actor,
+        actor::{Actor, ActorID, ActorIndex, ActorSceneKey, Bounds, ACTOR_INDEX_DIMENSIONS},
+        level_map::CoordPos,
+        transform::rotation::Rotation,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+fn get_actor(point: Point) -> (Actor, ActorID, ActorSceneKey, CoordPos, Bounds, Rotation) {
+    (
+        Actor("tree".to_string()),
+        ActorID::new(),
+        ActorSceneKey("tree".to_string()),
+        CoordPos::new(point),
+        Bounds(Vector3D::new(1., 0.25, 1.)),
+        Rotation { value: Rotation3::identity() }
+    )
+}
+
+/// Returns a world holding an actor at each of points, along with an ActorIndex that has all of them in it
+fn get_world(points: &[Point]) -> (World, Resources, Vec<Entity>) {
+    let mut world = World::default();
+    let mut resources = Resources::default();
+
+    let mut actor_index = ActorIndex::default();
+
+    let entities = points.iter().map(|point| {
+        let entity = world.push(get_actor(*point));
+
+        actor_index.update(entity, AABB::new(*point, Point::new(1, 1, 1)));
+
+        entity
+    }).collect::<Vec<Entity>>();
+
+    resources.insert(actor_index);
+
+    (world, resources, entities)
+}
+
+#[test]
+fn updating_the_index() {
+    let mut world = World::default();
+    let entity = world.push(get_actor(Point::zeros()));
+
+    let mut actor_index = ActorIndex::default();
+
+    actor_index.update(entity, AABB::new(Point::new(2, 0, 2), Point::new(1, 2, 1)));
+
+    let found = actor_index.query_range(AABB::from_extents(Point::new(2, 0, 2), Point::new(3, 3, 3)));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].get_entity(), entity);
+
+    //updating moves the actor rather than adding it again
+    actor_index.update(entity, AABB::new(Point::new(-5, 0, -5), Point::new(1, 2, 1)));
+
+    assert!(actor_index.query_range(AABB::from_extents(Point::new(2, 0, 2), Point::new(3, 3, 3))).is_empty());
+    assert_eq!(actor_index.query_range(AABB::from_extents(Point::new(-6, 0, -6), Point::new(-4, 0, -4))).len(), 1);
+}
+
+#[test]
+fn removing_from_the_index() {
+    let far = (ACTOR_INDEX_DIMENSIONS as i32) * 2;
+
+    let (_, resources, entities) = get_world(&[Point::new(1, 0, 1), Point::new(far, 0, far)]);
+
+    let mut actor_index = resources.get_mut::<ActorIndex>().unwrap();
+
+    //actors outside of the octree are still found
+    assert_eq!(actor_index.query_range(AABB::from_extents(Point::new(far - 1, 0, far - 1), Point::new(far + 1, 0, far + 1))).len(), 1);
+
+    entities.iter().for_each(|entity| actor_index.remove(*entity));
+
+    assert!(actor_index.query_range(AABB::from_extents(Point::zeros(), Point::new(2, 0, 2))).is_empty());
+    assert!(actor_index.query_range(AABB::from_extents(Point::new(far - 1, 0, far - 1), Point::new(far + 1, 0, far + 1))).is_empty());
+
+    //removing something that isn't there does nothing
+    actor_index.remove(entities[0]);
+}
+
+#[test]
+fn querying_a_range() {
+    let points = (0..10).flat_map(|x| (0..10).map(move |z| Point::new(x * 3, 0, z * 3))).collect::<Vec<Point>>();
+
+    let (mut world, mut resources, entities) = get_world(&points);
+
+    let range = AABB::from_extents(Point::new(2, 0, 2), Point::new(10, 0, 7));
+
+    let selected = actor::select_actors_from_range(&mut world, &mut resources, range).into_iter().collect::<HashSet<Entity>>();
+
+    let expected = entities.iter().zip(points.iter())
+        .filter(|(_, point)| range.contains_point(**point))
+        .map(|(entity, _)| *entity)
+        .collect::<HashSet<Entity>>();
+
+    assert_eq!(expected.len(), 3 * 2);
+    assert_eq!(selected, expected);
+
+    //actors that are no longer in the world get left out, and are dropped from the index
+    world.remove(*expected.iter().next().unwrap());
+
+    assert_eq!(actor::select_actors_from_range(&mut world, &mut resources, range).len(), 5);
+    assert_eq!(resources.get::<ActorIndex>().unwrap().query_range(range).len(), 5);
+}
+
+#[test]
+fn picking() {
+    let (mut world, mut resources, entities) = get_world(&[Point::new(0, 0, 0), Point::new(0, 4, 0), Point::new(5, 0, 0)]);
+
+    let down = Ray::new(Vector3D::new(0.5, 10., 0.5), -Vector3D::y());
+
+    let actor_index = resources.get::<ActorIndex>().unwrap();
+
+    assert_eq!(actor_index.pick(down).map(|indexed_actor| indexed_actor.get_entity()), Some(entities[1]));
+    assert!(actor_index.pick(Ray::new(Vector3D::new(3.5, 10., 0.5), -Vector3D::y())).is_none());
+
+    drop(actor_index);
+
+    assert_eq!(actor::pick_actor(&mut world, &mut resources, down), Some(entities[1]));
+
+    //a removed actor is skipped over in favor of the one behind it
+    world.remove(entities[1]);
+
+    assert_eq!(actor::pick_actor(&mut world, &mut resources, down), Some(entities[0]));
+
+    //picking works the same without an index
+    resources.remove::<ActorIndex>();
+
+    assert_eq!(actor::pick_actor(&mut world, &mut resources, down), Some(entities[0]));
+    assert_eq!(actor::pick_actor(&mut world, &mut resources, Ray::new(Vector3D::new(-5., 0.5, 0.5), Vector3D::x())), Some(entities[0]));
+}
\ No newline at end of file
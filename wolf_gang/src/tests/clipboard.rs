@@ -0,0 +1,146 @@
+use legion::*;
+use nalgebra::{Rotation3, Vector3};
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        Map,
+        MapChange,
+        MapChunkIndex,
+        TileData,
+        clipboard::{Clipboard, get_quarter_turns, rotate_point},
+        flood::SelectionMask,
+        shape::TileShape,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+fn get_rotation(quarter_turns: i32) -> Rotation3<f32> {
+    Rotation3::from_axis_angle(&Vector3::y_axis(), quarter_turns as f32 * std::f32::consts::FRAC_PI_2)
+}
+
+/// A clipboard that is 3 tiles across, 2 tall and 2 deep, with a different tile at every point and a ramp in one corner
+fn get_clipboard(rotation: Rotation3<f32>) -> Clipboard {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(2, 1, 1));
+    let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
+
+    aabb.iter_points().enumerate().for_each(|(tile, point)| {
+        octree.insert(TileData::new(tile as u32, point)).unwrap();
+    });
+
+    octree.insert(TileData::new(100, Point::new(2, 1, 0)).with_shape(TileShape::Ramp(0))).unwrap();
+
+    Clipboard::new(octree, rotation)
+}
+
+/// Returns a world holding tiles in chunks 4 tiles across
+fn get_world(tiles: &[TileData]) -> (World, Resources) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    let octree = tiles.iter().copied().collect::<Octree>();
+
+    map.get_chunked_octrees(&[octree]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    resources.insert(map);
+    resources.insert(chunk_index);
+
+    (world, resources)
+}
+
+#[test]
+fn quarter_turns() {
+    (-4..8).for_each(|quarter_turns| {
+        assert_eq!(get_quarter_turns(get_rotation(quarter_turns)), quarter_turns.rem_euclid(4), "{}", quarter_turns);
+    });
+
+    //a selection box that hasn't quite finished turning still counts as the nearest quarter turn
+    assert_eq!(get_quarter_turns(Rotation3::from_axis_angle(&Vector3::y_axis(), std::f32::consts::FRAC_PI_2 - 0.1)), 1);
+}
+
+#[test]
+fn rotating_points() {
+    let point = Point::new(3, 1, -2);
+
+    (0..4).for_each(|quarter_turns| {
+        let rotated = get_rotation(quarter_turns) * nalgebra::Vector3::new(point.x as f32, point.y as f32, point.z as f32);
+
+        assert_eq!(rotate_point(point, quarter_turns), rotated.map(|coord| coord.round() as i32), "{}", quarter_turns);
+        assert_eq!(rotate_point(point, quarter_turns), rotate_point(point, quarter_turns - 4));
+    });
+
+    assert_eq!(rotate_point(rotate_point(point, 1), 3), point);
+}
+
+#[test]
+fn pasting_with_quarter_turns() {
+    let clipboard = get_clipboard(get_rotation(1));
+    let center = Point::new(5, 2, -3);
+
+    (0..4).for_each(|quarter_turns| {
+        let rotation = get_rotation(1 + quarter_turns);
+
+        assert_eq!(clipboard.get_quarter_turns_since_copy(rotation), quarter_turns);
+
+        let range = clipboard.get_pasted_range(center, rotation);
+        let octree = clipboard.get_pasted_octree(center, rotation);
+
+        //the range swaps its width and depth every odd turn
+        assert_eq!(range.dimensions, if quarter_turns % 2 == 0 { Point::new(3, 2, 2) } else { Point::new(2, 2, 3) });
+        assert_eq!(octree.get_aabb(), range);
+        assert_eq!(octree.count(), clipboard.get_octree().count());
+
+        let origin = clipboard.get_pasted_point(Point::zeros(), center, rotation);
+
+        clipboard.get_octree().iter().for_each(|tile_data| {
+            let point = clipboard.get_pasted_point(tile_data.get_point(), center, rotation);
+
+            assert!(range.contains_point(point), "{:?} {:?}", point, range);
+            assert_eq!(point - origin, rotate_point(tile_data.get_point(), quarter_turns));
+
+            let pasted = octree.query_point(point).unwrap();
+
+            assert_eq!(pasted.get_tile(), tile_data.get_tile());
+            assert_eq!(pasted.get_shape(), tile_data.get_shape().rotated(quarter_turns));
+        });
+    });
+}
+
+#[test]
+fn pasting_with_mask() {
+    //a row of tile 1 to copy from, and a row of tile 2 to paste over
+    let tiles = AABB::from_extents(Point::new(0, 0, 0), Point::new(2, 0, 0)).iter_points().map(|point| TileData::new(1, point))
+        .chain(AABB::from_extents(Point::new(0, 0, 5), Point::new(2, 0, 5)).iter_points().map(|point| TileData::new(2, point)))
+        .collect::<Vec<TileData>>();
+
+    let (mut world, resources) = get_world(&tiles);
+
+    let range = AABB::from_extents(Point::new(0, 0, 0), Point::new(2, 0, 0));
+    let mask = SelectionMask::from_tiles(&tiles[1..3]).unwrap();
+    let clipboard = Clipboard::from_range(&mut world, &resources, range, get_rotation(0), Some(&mask)).unwrap();
+
+    assert_eq!(clipboard.get_octree().count(), 2);
+
+    let center = AABB::from_extents(Point::new(0, 0, 5), Point::new(2, 0, 5)).center;
+
+    let pasted_mask = clipboard.get_pasted_mask(center, get_rotation(0)).unwrap();
+
+    assert!(!pasted_mask.contains(Point::new(0, 0, 5)));
+    assert!(pasted_mask.contains(Point::new(1, 0, 5)));
+    assert!(pasted_mask.contains(Point::new(2, 0, 5)));
+
+    //the cell that was left out of the mask when copying keeps its tile instead of being cleared
+    let octree = MapChange::MapPaste { clipboard, center, rotation: get_rotation(0) }.get_octree(&mut world, &resources).unwrap();
+
+    assert_eq!(octree.query_point(Point::new(0, 0, 5)), Some(TileData::new(2, Point::new(0, 0, 5))));
+    assert_eq!(octree.query_point(Point::new(1, 0, 5)), Some(TileData::new(1, Point::new(1, 0, 5))));
+    assert_eq!(octree.query_point(Point::new(2, 0, 5)), Some(TileData::new(1, Point::new(2, 0, 5))));
+}
@@ -0,0 +1,230 @@
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        TileData,
+        collision,
+        mesher::ChunkOctrees,
+        shape::TileShape,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+const CHUNK_DIMENSIONS: i32 = 4;
+
+fn get_chunks(points: &[Point]) -> ChunkOctrees {
+    get_tile_chunks(&points.iter().map(|point| TileData::new(0, *point)).collect::<Vec<TileData>>())
+}
+
+fn get_tile_chunks(tiles: &[TileData]) -> ChunkOctrees {
+    let mut chunks = ChunkOctrees::new();
+
+    tiles.iter().for_each(|tile_data| {
+        let point = tile_data.get_point();
+        let chunk_point = point.map(|coord| (coord as f32 / CHUNK_DIMENSIONS as f32).floor() as i32);
+        let min = chunk_point * CHUNK_DIMENSIONS;
+
+        chunks.entry(chunk_point)
+            .or_insert_with(|| Octree::new(AABB::from_extents(min, min + Point::new(1,1,1) * (CHUNK_DIMENSIONS - 1)), octree::DEFAULT_MAX))
+            .insert(*tile_data)
+            .unwrap();
+    });
+
+    chunks
+}
+
+fn get_column(x: i32, z: i32, heights: std::ops::Range<i32>) -> Vec<Point> {
+    heights.map(|y| Point::new(x, y, z)).collect()
+}
+
+/// Returns the normals of every triangle, going by Godot's clockwise winding
+fn get_normals(faces: &[Vector3D]) -> Vec<Vector3D> {
+    faces.chunks_exact(3)
+        .map(|triangle| -(triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0])).normalize())
+        .collect()
+}
+
+/// Returns the triangles that lie flat against the plane x = wall_x
+fn get_triangles_at_x(faces: &[Vector3D], wall_x: f32) -> Vec<&[Vector3D]> {
+    faces.chunks_exact(3)
+        .filter(|triangle| triangle.iter().all(|point| (point.x - wall_x).abs() < 0.0001))
+        .collect()
+}
+
+#[test]
+fn column_is_a_box() {
+    let chunks = get_chunks(&get_column(1, 1, 0..4));
+    let faces = collision::collide_chunk(&chunks, &chunks[&Point::zeros()]);
+
+    //a top and four walls that each cover the whole column, without a bottom
+    assert_eq!(faces.len(), 5 * 6);
+
+    let normals = get_normals(&faces);
+
+    vec![Vector3D::x(), -Vector3D::x(), Vector3D::y(), Vector3D::z(), -Vector3D::z()].into_iter().for_each(|normal| {
+        assert_eq!(normals.iter().filter(|n| (**n - normal).norm() < 0.0001).count(), 2);
+    });
+
+    assert!(normals.iter().all(|normal| (normal + Vector3D::y()).norm() > 0.0001));
+
+    let top = faces.iter().map(|face| face.y).fold(std::f32::MIN, f32::max);
+    let bottom = faces.iter().map(|face| face.y).fold(std::f32::MAX, f32::min);
+
+    assert!((top - 1.).abs() < 0.0001);
+    assert!(bottom.abs() < 0.0001);
+}
+
+#[test]
+fn walls_stop_at_neighbours() {
+    let mut points = get_column(1, 1, 0..4);
+    points.extend(get_column(2, 1, 0..2));
+
+    let chunks = get_chunks(&points);
+    let octree = &chunks[&Point::zeros()];
+
+    let faces = collision::get_column_faces(&chunks, octree, 1, 1);
+
+    //the wall facing the lower column only covers the part above it
+    let wall = get_triangles_at_x(&faces, 2.);
+
+    assert_eq!(wall.len(), 2);
+    assert!(wall.iter().flat_map(|triangle| triangle.iter()).all(|point| point.y > 0.5 - 0.0001));
+}
+
+#[test]
+fn overhang_has_walls_and_tops() {
+    let mut points = get_column(1, 1, 0..2);
+    points.push(Point::new(1, 3, 1));
+
+    let chunks = get_chunks(&points);
+    let octree = &chunks[&Point::zeros()];
+
+    //two pieces, each with a wall on each side
+    assert_eq!(collision::get_column_faces(&chunks, octree, 1, 1).len(), 8 * 6);
+
+    //and each with a top, but nothing underneath the overhang
+    assert_eq!(collision::get_top_faces(&chunks, octree, 1).len(), 6);
+    assert_eq!(collision::get_top_faces(&chunks, octree, 3).len(), 6);
+    assert_eq!(collision::collide_chunk(&chunks, octree).len(), 10 * 6);
+}
+
+#[test]
+fn looks_across_chunks() {
+    let mut points = get_column(3, 1, 0..4);
+    points.extend(get_column(4, 1, 0..4));
+
+    let chunks = get_chunks(&points);
+
+    assert_eq!(chunks.len(), 2);
+
+    let faces = collision::get_column_faces(&chunks, &chunks[&Point::zeros()], 3, 1);
+
+    assert!(get_triangles_at_x(&faces, 4.).is_empty());
+}
+
+#[test]
+fn range_matches_chunk() {
+    let mut points = get_column(1, 1, 0..4);
+    points.extend(get_column(2, 2, 1..3));
+
+    let chunks = get_chunks(&points);
+    let octree = &chunks[&Point::zeros()];
+
+    let mut columns = collision::collide_range(&chunks, octree, octree.get_aabb());
+    columns.sort_by_key(|(index, _)| *index);
+
+    let mut tops = collision::collide_tops(&chunks, octree, octree.get_aabb());
+    tops.sort_by_key(|(y, _)| *y);
+
+    let mut faces = columns.into_iter().flat_map(|(_, faces)| faces).collect::<Vec<Vector3D>>();
+    faces.extend(tops.into_iter().flat_map(|(_, faces)| faces));
+
+    assert_eq!(faces, collision::collide_chunk(&chunks, octree));
+}
+
+#[test]
+fn ramps_keep_their_slope() {
+    let chunks = get_tile_chunks(&[TileData::new(0, Point::new(1, 0, 1)).with_shape(TileShape::Ramp(0))]);
+
+    let faces = collision::get_column_faces(&chunks, &chunks[&Point::zeros()], 1, 1);
+
+    //two triangles on top, two on the tall side and one on each sloped side
+    assert_eq!(faces.len(), 6 * 3);
+
+    let slope = Vector3D::new(-0.25, 1., 0.).normalize();
+
+    assert_eq!(get_normals(&faces).iter().filter(|normal| (**normal - slope).norm() < 0.0001).count(), 2);
+}
+
+#[test]
+fn walls_reach_past_shapes() {
+    let mut tiles = get_column(1, 1, 0..2).into_iter().map(|point| TileData::new(0, point)).collect::<Vec<TileData>>();
+    tiles.push(TileData::new(0, Point::new(2, 1, 1)).with_shape(TileShape::Half));
+
+    let chunks = get_tile_chunks(&tiles);
+
+    //the wall of the column goes all the way down past the half tile, which doesn't draw the side it shares with the column
+    let faces = collision::get_column_faces(&chunks, &chunks[&Point::zeros()], 1, 1);
+    let wall = get_triangles_at_x(&faces, 2.);
+
+    assert_eq!(wall.len(), 2);
+
+    let half_faces = collision::get_column_faces(&chunks, &chunks[&Point::zeros()], 2, 1);
+
+    assert!(get_triangles_at_x(&half_faces, 2.).is_empty());
+}
+
+
+#[test]
+fn flat_floor_is_one_quad_per_chunk() {
+    let size = CHUNK_DIMENSIONS * 2;
+
+    let points = (0..size * size).map(|i| Point::new(i % size, 0, i / size)).collect::<Vec<Point>>();
+    let chunks = get_chunks(&points);
+
+    assert_eq!(chunks.len(), 4);
+
+    chunks.values().for_each(|octree| {
+        let tops = collision::get_top_faces(&chunks, octree, 0);
+
+        assert_eq!(tops.len(), 2 * 3);
+        assert!(get_normals(&tops).iter().all(|normal| (normal - Vector3D::y()).norm() < 0.0001));
+    });
+}
+
+#[test]
+fn merged_tops_cover_every_open_tile() {
+    //a floor with a hole in it and a tile sitting on it, neither of which should be covered
+    let mut points = (0..CHUNK_DIMENSIONS * CHUNK_DIMENSIONS)
+        .map(|i| Point::new(i % CHUNK_DIMENSIONS, 0, i / CHUNK_DIMENSIONS))
+        .filter(|point| *point != Point::new(1, 0, 1))
+        .collect::<Vec<Point>>();
+    points.push(Point::new(2, 1, 2));
+
+    let chunks = get_chunks(&points);
+    let tops = collision::get_top_faces(&chunks, &chunks[&Point::zeros()], 0);
+
+    //fewer quads than half of the 14 open tiles
+    assert!(tops.len() <= 6 * 6);
+
+    let area: f32 = tops.chunks_exact(3)
+        .map(|triangle| (triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0])).norm() / 2.)
+        .sum();
+
+    assert!((area - 14.).abs() < 0.0001);
+
+    let covers = |x: f32, z: f32| tops.chunks_exact(3).any(|triangle| {
+        let min_x = triangle.iter().map(|point| point.x).fold(std::f32::MAX, f32::min);
+        let max_x = triangle.iter().map(|point| point.x).fold(std::f32::MIN, f32::max);
+        let min_z = triangle.iter().map(|point| point.z).fold(std::f32::MAX, f32::min);
+        let max_z = triangle.iter().map(|point| point.z).fold(std::f32::MIN, f32::max);
+
+        x > min_x && x < max_x && z > min_z && z < max_z
+    });
+
+    assert!(!covers(1.5, 1.5));
+    assert!(!covers(2.5, 2.5));
+}
\ No newline at end of file
@@ -0,0 +1,89 @@
+use crate::{
+    collections::octree,
+    systems::level_map::{
+        Map,
+        TileData,
+        document::{Document, FORMAT_VERSION},
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Point = nalgebra::Vector3<i32>;
+
+/// The layout documents were saved with before they had a header
+#[derive(serde::Serialize)]
+struct SavedDocument {
+    file_path: Option<String>,
+    title: String,
+    map_chunks: Vec<SavedOctree>,
+    actor_data: Option<Vec<u8>>,
+}
+
+#[derive(serde::Serialize)]
+struct SavedOctree {
+    aabb: AABB,
+    max_elements: usize,
+    elements: Vec<(u32, Point)>,
+    children: Vec<SavedOctree>,
+    paternity: SavedPaternity,
+}
+
+#[derive(serde::Serialize)]
+enum SavedPaternity {
+    #[allow(dead_code)]
+    ProudParent,
+    ChildFree,
+}
+
+#[test]
+fn round_trip() {
+    let mut document = Document::new(Some("res://level.wgm"), "Level");
+    document.set_map(Map::new(Point::new(4, 8, 4), Some(AABB::from_extents(Point::new(-8, 0, -8), Point::new(8, 16, 8)))));
+
+    let raw = document.to_raw();
+
+    assert!(raw.starts_with(b"WGM\0"));
+    assert_eq!(Document::from_raw(&raw).unwrap(), document);
+}
+
+#[test]
+fn unsupported_version() {
+    let mut raw = Document::default().to_raw();
+    raw[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+    assert!(Document::from_raw(&raw).is_err());
+    assert!(Document::from_raw(b"WGM\0").is_err());
+}
+
+#[test]
+fn legacy_document() {
+    let saved = SavedDocument {
+        file_path: Some("res://level.wgm".to_string()),
+        title: "Level".to_string(),
+        map_chunks: vec![
+            SavedOctree {
+                aabb: AABB::from_extents(Point::zeros(), Point::new(9, 9, 9)),
+                max_elements: octree::DEFAULT_MAX,
+                elements: vec![(3, Point::new(0, 0, 0)), (5, Point::new(2, 1, 4))],
+                children: Vec::new(),
+                paternity: SavedPaternity::ChildFree,
+            }
+        ],
+        actor_data: Some(vec![1, 2, 3]),
+    };
+
+    let document = Document::from_raw(&bincode::serialize(&saved).unwrap()).unwrap();
+
+    assert_eq!(document.file_path, saved.file_path);
+    assert_eq!(document.title, saved.title);
+    assert_eq!(document.get_map(), Map::default());
+
+    let chunks = document.get_map_chunks();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].get_aabb(), saved.map_chunks[0].aabb);
+
+    let mut tiles = chunks[0].iter().map(|tile_data| *tile_data).collect::<Vec<TileData>>();
+    tiles.sort_by_key(|tile_data| tile_data.get_tile());
+
+    assert_eq!(tiles, vec![TileData::new(3, Point::new(0, 0, 0)), TileData::new(5, Point::new(2, 1, 4))]);
+}
@@ -0,0 +1,164 @@
+use legion::*;
+
+use std::collections::HashSet;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        Map,
+        MapChunkIndex,
+        TileData,
+        fill_octree_from_aabb,
+        flood::{self, Connectivity, SelectionMask},
+        shape::TileShape,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Returns a world holding tiles in chunks 4 tiles across, so that fills have to cross between chunks
+fn get_world(tiles: &[TileData]) -> (World, Resources) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    let octree = tiles.iter().copied().collect::<Octree>();
+
+    map.get_chunked_octrees(&[octree]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    resources.insert(map);
+    resources.insert(chunk_index);
+
+    (world, resources)
+}
+
+/// A floor of tile 1, with a column of it going up from one corner, a tile of it that only touches the floor's far corner diagonally,
+/// and a tile of something else beside the floor
+fn get_tiles() -> Vec<TileData> {
+    let mut tiles = AABB::from_extents(Point::new(0, 0, 0), Point::new(5, 0, 2)).iter_points()
+        .map(|point| TileData::new(1, point))
+        .collect::<Vec<TileData>>();
+
+    tiles.push(TileData::new(1, Point::new(0, 1, 0)));
+    tiles.push(TileData::new(1, Point::new(0, 2, 0)));
+    tiles.push(TileData::new(1, Point::new(6, 1, 3)));
+    tiles.push(TileData::new(2, Point::new(1, 0, 3)));
+
+    tiles
+}
+
+fn get_points(tiles: &[TileData]) -> HashSet<Point> {
+    tiles.iter().map(|tile_data| tile_data.get_point()).collect()
+}
+
+#[test]
+fn connectivity() {
+    let (mut world, resources) = get_world(&get_tiles());
+
+    let four = flood::flood_select(&mut world, &resources, Point::zeros(), Connectivity::Four, 4096);
+    let six = flood::flood_select(&mut world, &resources, Point::zeros(), Connectivity::Six, 4096);
+    let twenty_six = flood::flood_select(&mut world, &resources, Point::zeros(), Connectivity::TwentySix, 4096);
+
+    //four stays on the floor, six climbs the column, and twenty six reaches the diagonal tile as well
+    assert_eq!(four.len(), 18);
+    assert_eq!(six.len(), 20);
+    assert_eq!(twenty_six.len(), 21);
+
+    assert!(get_points(&four).iter().all(|point| point.y == 0));
+    assert!(get_points(&six).contains(&Point::new(0, 2, 0)));
+    assert!(!get_points(&six).contains(&Point::new(6, 1, 3)));
+    assert!(get_points(&twenty_six).contains(&Point::new(6, 1, 3)));
+
+    [four, six, twenty_six].iter().for_each(|selected| {
+        assert!(selected.iter().all(|tile_data| tile_data.get_tile() == 1));
+        assert_eq!(get_points(selected).len(), selected.len());
+    });
+}
+
+#[test]
+fn volume_cap() {
+    let (mut world, resources) = get_world(&get_tiles());
+
+    [Connectivity::Four, Connectivity::Six, Connectivity::TwentySix].iter().for_each(|connectivity| {
+        let selected = flood::flood_select(&mut world, &resources, Point::zeros(), *connectivity, 5);
+
+        assert_eq!(selected.len(), 5);
+        assert!(selected.iter().all(|tile_data| tile_data.get_tile() == 1));
+    });
+}
+
+#[test]
+fn selecting_from_other_tiles() {
+    let (mut world, resources) = get_world(&get_tiles());
+
+    assert_eq!(flood::flood_select(&mut world, &resources, Point::new(1, 0, 3), Connectivity::TwentySix, 4096), vec![TileData::new(2, Point::new(1, 0, 3))]);
+    assert!(flood::flood_select(&mut world, &resources, Point::new(3, 3, 3), Connectivity::TwentySix, 4096).is_empty());
+}
+
+#[test]
+fn start_in_range() {
+    let (mut world, resources) = get_world(&get_tiles());
+
+    //the highest tile is picked, and then the one closest to the minimum corner
+    assert_eq!(flood::get_start_in_range(&mut world, &resources, AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 3, 3))), Some(Point::new(0, 2, 0)));
+    assert_eq!(flood::get_start_in_range(&mut world, &resources, AABB::from_extents(Point::new(2, 0, 0), Point::new(5, 0, 2))), Some(Point::new(2, 0, 0)));
+    assert_eq!(flood::get_start_in_range(&mut world, &resources, AABB::from_extents(Point::new(2, 1, 0), Point::new(5, 3, 2))), None);
+}
+
+#[test]
+fn bounds() {
+    assert_eq!(flood::get_bounds(&[]), None);
+    assert_eq!(flood::get_bounds(&get_tiles()), Some(AABB::from_extents(Point::new(0, 0, 0), Point::new(6, 2, 3))));
+    assert_eq!(flood::get_bounds(&[TileData::new(0, Point::new(-2, 1, 4))]), Some(AABB::from_extents(Point::new(-2, 1, 4), Point::new(-2, 1, 4))));
+}
+
+#[test]
+fn masking_changes() {
+    let aabb = AABB::from_extents(Point::new(0, 0, 0), Point::new(2, 0, 1));
+
+    let existing = AABB::from_extents(Point::new(0, 0, 0), Point::new(2, 0, 0)).iter_points()
+        .map(|point| TileData::new(1, point))
+        .collect::<Vec<TileData>>();
+
+    let mask = SelectionMask::from_tiles(&existing[1..]).unwrap();
+
+    assert_eq!(mask.get_aabb(), AABB::from_extents(Point::new(1, 0, 0), Point::new(2, 0, 0)));
+    assert!(mask.applies_to(mask.get_aabb()));
+    assert!(!mask.applies_to(aabb));
+
+    //removing only takes out the masked tiles
+    let removed = mask.get_masked_octree(&fill_octree_from_aabb(aabb, None), existing.clone());
+
+    assert_eq!(removed.get_aabb(), aabb);
+    assert_eq!(get_points(&removed.iter().map(|tile_data| *tile_data).collect::<Vec<TileData>>()), vec![Point::new(0, 0, 0)].into_iter().collect());
+
+    //filling only changes the masked tiles, leaving the empty cells around them empty
+    let filled = mask.get_masked_octree(&fill_octree_from_aabb(aabb, Some(TileData::new(5, Point::zeros()))), existing);
+
+    assert_eq!(filled.count(), 3);
+    assert_eq!(filled.query_point(Point::new(0, 0, 0)).map(|tile_data| tile_data.get_tile()), Some(1));
+    assert_eq!(filled.query_point(Point::new(1, 0, 0)).map(|tile_data| tile_data.get_tile()), Some(5));
+    assert_eq!(filled.query_point(Point::new(2, 0, 0)).map(|tile_data| tile_data.get_tile()), Some(5));
+}
+
+#[test]
+fn filling_keeps_shapes() {
+    let mut tiles = get_tiles();
+
+    tiles.push(TileData::new(1, Point::new(2, 1, 1)).with_shape(TileShape::Ramp(2)));
+
+    let (mut world, resources) = get_world(&tiles);
+
+    let octree = flood::get_flood_fill_octree(&mut world, &resources, Point::zeros(), 4, Connectivity::TwentySix, 4096).unwrap();
+
+    //the filled tiles change their tile but not their shape, and the tile of something else within the bounds is left alone
+    assert_eq!(octree.query_point(Point::new(2, 1, 1)), Some(TileData::new(4, Point::new(2, 1, 1)).with_shape(TileShape::Ramp(2))));
+    assert_eq!(octree.query_point(Point::new(3, 0, 1)), Some(TileData::new(4, Point::new(3, 0, 1))));
+    assert_eq!(octree.query_point(Point::new(1, 0, 3)), Some(TileData::new(2, Point::new(1, 0, 3))));
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        TileData,
+        generator::{self, GeneratorSettings, HeightLayer},
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+fn get_aabb() -> AABB {
+    AABB::from_extents(Point::new(-10, 3, 5), Point::new(21, 18, 30))
+}
+
+/// Returns the height of each column of octree from the bottom of aabb, going by the highest tile
+fn get_column_heights(octree: &Octree, aabb: AABB) -> HashMap<(i32, i32), i32> {
+    let mut heights = HashMap::new();
+
+    octree.iter().for_each(|tile_data| {
+        let point = tile_data.get_point();
+        let height = heights.entry((point.x, point.z)).or_insert(0);
+
+        *height = (*height).max(point.y - aabb.get_min().y + 1);
+    });
+
+    heights
+}
+
+#[test]
+fn fractal_noise_stays_in_range() {
+    (0..4).for_each(|octaves| {
+        (-50..50).for_each(|i| {
+            let noise = generator::fractal_noise(7, i as f32 * 0.37, i as f32 * -0.61, octaves);
+
+            assert!((0. ..=1.).contains(&noise), "{}", noise);
+        });
+    });
+
+    //the same seed and coordinates always give the same noise
+    assert_eq!(generator::fractal_noise(3, 1.5, 2.25, 4), generator::fractal_noise(3, 1.5, 2.25, 4));
+}
+
+#[test]
+fn same_seed_same_terrain() {
+    let settings = GeneratorSettings { seed: 12, scale: 8., ..GeneratorSettings::default() };
+    let aabb = get_aabb();
+
+    let octree = settings.get_generated_octree(aabb);
+
+    let mut tiles = octree.iter().map(|tile_data| *tile_data).collect::<Vec<TileData>>();
+    let mut again = settings.clone().get_generated_octree(aabb).iter().map(|tile_data| *tile_data).collect::<Vec<TileData>>();
+
+    tiles.sort_by_key(|tile_data| (tile_data.get_point().x, tile_data.get_point().y, tile_data.get_point().z));
+    again.sort_by_key(|tile_data| (tile_data.get_point().x, tile_data.get_point().y, tile_data.get_point().z));
+
+    assert_eq!(tiles, again);
+
+    let other = GeneratorSettings { seed: 13, ..settings.clone() };
+
+    assert_ne!(get_column_heights(&octree, aabb), get_column_heights(&other.get_generated_octree(aabb), aabb));
+}
+
+#[test]
+fn heights_stay_in_range() {
+    let aabb = get_aabb();
+
+    vec![1., 4., 32.].into_iter().for_each(|scale| {
+        let settings = GeneratorSettings { seed: 5, scale, ..GeneratorSettings::default() };
+
+        let min = aabb.get_min();
+        let max = aabb.get_max();
+
+        for x in min.x..=max.x {
+            for z in min.z..=max.z {
+                let height = settings.get_height(aabb, x, z);
+
+                assert!((1..=aabb.dimensions.y).contains(&height), "{}", height);
+            }
+        }
+
+        //every column gets filled from the bottom of the range up to its height
+        let octree = settings.get_generated_octree(aabb);
+
+        assert_eq!(octree.get_aabb(), aabb);
+
+        let heights = get_column_heights(&octree, aabb);
+
+        assert_eq!(heights.len() as i32, aabb.dimensions.x * aabb.dimensions.z);
+
+        heights.iter().for_each(|((x, z), height)| {
+            assert_eq!(*height, settings.get_height(aabb, *x, *z));
+            assert_eq!(octree.query_range(AABB::from_extents(Point::new(*x, min.y, *z), Point::new(*x, max.y, *z))).len() as i32, *height);
+        });
+    });
+}
+
+#[test]
+fn tiles_by_height() {
+    let settings = GeneratorSettings::default();
+
+    assert_eq!(settings.get_tile_at(0.), Some(0));
+    assert_eq!(settings.get_tile_at(0.5), Some(0));
+    assert_eq!(settings.get_tile_at(0.6), Some(2));
+    assert_eq!(settings.get_tile_at(0.9), Some(7));
+
+    //anything past the last layer uses the last layer's tile
+    assert_eq!(settings.get_tile_at(1.5), Some(7));
+
+    assert_eq!(GeneratorSettings { layers: Vec::new(), ..settings.clone() }.get_tile_at(0.5), None);
+
+    let aabb = get_aabb();
+    let settings = GeneratorSettings { seed: 9, scale: 6., ..settings };
+
+    settings.get_generated_octree(aabb).iter().for_each(|tile_data| {
+        let fraction = (tile_data.get_point().y - aabb.get_min().y + 1) as f32 / aabb.dimensions.y as f32;
+
+        assert_eq!(Some(tile_data.get_tile()), settings.get_tile_at(fraction));
+    });
+}
+
+#[test]
+fn trees_on_the_bottom_layer() {
+    let settings = GeneratorSettings {
+        seed: 3,
+        scale: 6.,
+        layers: vec![
+            HeightLayer { up_to: 0.4, tile: 1 },
+            HeightLayer { up_to: 1., tile: 2 },
+        ],
+        tree_density: 1.,
+        ..GeneratorSettings::default()
+    };
+
+    let aabb = get_aabb();
+    let octree = settings.get_generated_octree(aabb);
+    let heights = get_column_heights(&octree, aabb);
+
+    let points = settings.get_tree_points(aabb);
+
+    //with every bottom layer column getting a tree, there is a tree for each of them
+    let bottom_columns = heights.values().filter(|height| **height as f32 / aabb.dimensions.y as f32 <= 0.4).count();
+
+    assert!(bottom_columns > 0);
+    assert!(bottom_columns < heights.len());
+    assert_eq!(points.len(), bottom_columns);
+
+    points.iter().for_each(|point| {
+        //trees stand on top of the column
+        assert!(octree.query_point(*point).is_none());
+        assert_eq!(octree.query_point(point - Point::y()).map(|tile_data| tile_data.get_tile()), Some(1));
+    });
+
+    assert!(GeneratorSettings { tree_density: 0., ..settings.clone() }.get_tree_points(aabb).is_empty());
+
+    //fewer trees get placed with a lower density, on the same columns each time
+    let sparse = GeneratorSettings { tree_density: 0.2, ..settings };
+    let sparse_points = sparse.get_tree_points(aabb);
+
+    assert!(sparse_points.len() < points.len());
+    assert!(sparse_points.iter().all(|point| points.contains(point)));
+    assert_eq!(sparse_points, sparse.get_tree_points(aabb));
+}
@@ -0,0 +1,121 @@
+use legion::*;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        Map,
+        MapChunkIndex,
+        TileData,
+        heightmap::{self, Heightmap, HeightmapSettings},
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Returns a world holding octree in chunks 4 tiles across, so that columns have to cross between chunks
+fn get_world(octree: Octree) -> (World, Map) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    map.get_chunked_octrees(&[octree]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    (world, map)
+}
+
+/// A heightmap 3 columns across and 2 deep, with a different tile for each row
+fn get_heightmap() -> Heightmap {
+    Heightmap::new(3, 2, vec![1, 5, 2, 7, 3, 1], vec![1, 1, 1, 2, 2, 2])
+}
+
+#[test]
+fn tile_height_and_brightness() {
+    let settings = HeightmapSettings::default();
+
+    assert_eq!(settings.get_tile_height(0.), 0);
+    assert_eq!(settings.get_tile_height(0.5), 32);
+    assert_eq!(settings.get_tile_height(1.), 64);
+
+    (0..=64).for_each(|height| {
+        assert_eq!(settings.get_tile_height(settings.get_brightness(height)), height);
+    });
+
+    //columns too tall or short for the image get clamped
+    assert_eq!(settings.get_brightness(-3), 0.);
+    assert_eq!(settings.get_brightness(100), 1.);
+
+    let settings = HeightmapSettings { max_height: 4., ..settings };
+
+    assert_eq!(settings.get_tile_height(1.), 16);
+    assert_eq!(settings.get_brightness(8), 0.5);
+}
+
+#[test]
+fn colors_for_tiles() {
+    let settings = HeightmapSettings::default();
+
+    assert_eq!(settings.get_color_for_tile(2), Some([128, 128, 128]));
+    assert_eq!(settings.get_color_for_tile(5), None);
+
+    assert_eq!(heightmap::get_color_map_path("maps/hills.png"), "maps/hills_colors.png");
+}
+
+#[test]
+fn heights_to_octree() {
+    let min = Point::new(-2, 3, 1);
+    let octree = get_heightmap().get_octree(min).unwrap();
+
+    assert_eq!(octree.get_aabb(), AABB::from_extents(min, min + Point::new(2, 6, 1)));
+    assert_eq!(octree.count(), 1 + 5 + 2 + 7 + 3 + 1);
+
+    octree.iter().for_each(|tile_data| {
+        let point = tile_data.get_point() - min;
+
+        assert_eq!(tile_data.get_tile(), if point.z == 0 { 1 } else { 2 });
+    });
+
+    //the tallest column goes all the way up
+    assert!(octree.query_point(min + Point::new(0, 6, 1)).is_some());
+    assert!(octree.query_point(min + Point::new(1, 6, 1)).is_none());
+
+    assert!(Heightmap::new(2, 1, vec![0, 0], vec![1, 1]).get_octree(min).is_none());
+    assert!(Heightmap::new(0, 0, Vec::new(), Vec::new()).get_octree(min).is_none());
+}
+
+#[test]
+fn round_trip() {
+    let heightmap = get_heightmap();
+    let min = Point::new(-2, 3, 1);
+
+    let (world, map) = get_world(heightmap.get_octree(min).unwrap());
+
+    assert_eq!(Heightmap::from_map(&world, map), Some((heightmap, min)));
+}
+
+#[test]
+fn floating_tiles_are_left_out() {
+    let heightmap = get_heightmap();
+    let min = Point::new(0, 0, 0);
+
+    let octree = heightmap.get_octree(min).unwrap();
+
+    let aabb = octree.get_aabb();
+    let mut with_floating = Octree::new(AABB::from_extents(aabb.get_min(), aabb.get_max() + Point::y() * 3), octree::DEFAULT_MAX);
+
+    octree.iter().for_each(|tile_data| {
+        with_floating.insert(*tile_data).unwrap();
+    });
+
+    //a tile floating two above the first column
+    with_floating.insert(TileData::new(3, Point::new(0, 2, 0))).unwrap();
+
+    let (world, map) = get_world(with_floating);
+
+    assert_eq!(Heightmap::from_map(&world, map), Some((heightmap, min)));
+    assert_eq!(Heightmap::from_map(&World::default(), map), None);
+}
@@ -0,0 +1,169 @@
+use legion::*;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::{
+        history::{self, History, StepType},
+        level_map::{Map, MapChunkIndex, TileData},
+        networking::ClientID,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+const CLIENT_ID: u32 = 1;
+
+/// Returns an octree covering aabb that is filled with tile
+fn get_filled_octree(aabb: AABB, tile: u32) -> Octree {
+    aabb.iter_points().map(|point| TileData::new(tile, point)).collect()
+}
+
+/// Returns a step that changes aabb to be filled with tile, which is enough to tell steps apart
+fn get_step(aabb: AABB, tile: u32) -> StepType {
+    StepType::MapChange((Octree::new(aabb, octree::DEFAULT_MAX), get_filled_octree(aabb, tile)))
+}
+
+/// Returns the tile that each map change fills its range with, in order
+fn get_tiles(steps: &[&StepType]) -> Vec<u32> {
+    steps.iter().map(|step| match step {
+        StepType::MapChange((_, redo)) => redo.iter().next().unwrap().get_tile(),
+        _ => panic!("Expected a map change")
+    }).collect()
+}
+
+/// Returns a world with an empty map and a history for CLIENT_ID
+fn get_world() -> (World, Resources) {
+    let mut world = World::default();
+    let mut resources = Resources::default();
+
+    world.push((History::new(), ClientID::new(CLIENT_ID)));
+
+    resources.insert(Map::new(Point::new(4, 4, 4), None));
+    resources.insert(MapChunkIndex::default());
+
+    (world, resources)
+}
+
+fn get_map_tiles(world: &mut World, resources: &Resources, aabb: AABB) -> Vec<TileData> {
+    let map = *resources.get::<Map>().unwrap();
+    let chunk_index = resources.get::<MapChunkIndex>().unwrap();
+
+    let mut tiles = map.query_chunk_range(world, &chunk_index, aabb);
+    tiles.sort_by_key(|tile_data| (tile_data.get_point().x, tile_data.get_point().y, tile_data.get_point().z));
+
+    tiles
+}
+
+fn get_history(world: &World) -> History {
+    <Read<History>>::query().iter(world).next().unwrap().clone()
+}
+
+#[test]
+fn grouping_steps() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::zeros());
+
+    let mut history = History::new();
+
+    history.begin_group();
+    history.add_step(get_step(aabb, 1));
+    history.add_step(get_step(aabb, 2));
+
+    //nothing is in the history until the group ends
+    assert!(history.can_undo().is_err());
+
+    history.end_group();
+
+    match history.can_undo().unwrap() {
+        StepType::Group(steps) => assert_eq!(get_tiles(&steps.iter().collect::<Vec<&StepType>>()), vec![1, 2]),
+        _ => panic!("Expected a group")
+    }
+
+    //steps added after the group are steps of their own
+    history.add_step(get_step(aabb, 3));
+
+    assert_eq!(get_tiles(&[history.can_undo().unwrap()]), vec![3]);
+}
+
+#[test]
+fn grouping_too_few_steps() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::zeros());
+
+    let mut history = History::new();
+
+    history.begin_group();
+    history.end_group();
+
+    assert!(history.can_undo().is_err());
+
+    //a group of one is just that step
+    history.begin_group();
+    history.add_step(get_step(aabb, 1));
+    history.end_group();
+
+    assert_eq!(get_tiles(&[history.can_undo().unwrap()]), vec![1]);
+}
+
+#[test]
+fn ordering_grouped_steps() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::zeros());
+
+    let group = StepType::Group(vec![
+        get_step(aabb, 1),
+        StepType::Group(vec![get_step(aabb, 2), get_step(aabb, 3)]),
+        get_step(aabb, 4),
+    ]);
+
+    assert_eq!(get_tiles(&group.get_ordered_steps(true)), vec![1, 2, 3, 4]);
+    assert_eq!(get_tiles(&group.get_ordered_steps(false)), vec![4, 3, 2, 1]);
+
+    let step = get_step(aabb, 5);
+
+    assert_eq!(get_tiles(&step.get_ordered_steps(false)), vec![5]);
+}
+
+#[test]
+fn undoing_grouped_steps() {
+    let (mut world, mut resources) = get_world();
+    let map = *resources.get::<Map>().unwrap();
+
+    let first = AABB::from_extents(Point::new(0, 0, 0), Point::new(2, 0, 0));
+    let second = AABB::from_extents(Point::new(1, 0, 0), Point::new(5, 0, 0));
+    let whole = AABB::from_extents(Point::new(0, 0, 0), Point::new(5, 0, 0));
+
+    map.change(&mut world, &resources, get_filled_octree(first, 1), None);
+
+    let original = get_map_tiles(&mut world, &resources, whole);
+
+    //two overlapping changes, like an edit and its mirrored copy
+    history::group_steps(&mut world, &mut resources, Some(CLIENT_ID), |world, resources| {
+        map.change(world, resources, get_filled_octree(second, 2), Some(CLIENT_ID));
+        map.change(world, resources, get_filled_octree(first, 3), Some(CLIENT_ID));
+    });
+
+    let changed = get_map_tiles(&mut world, &resources, whole);
+
+    assert_ne!(changed, original);
+
+    let history = get_history(&world);
+    let step = history.can_undo().unwrap().clone();
+
+    let undo = |world: &mut World, steps: Vec<&StepType>| steps.into_iter().for_each(|step| match step {
+        StepType::MapChange((undo, _)) => map.change(world, &resources, undo.clone(), None),
+        _ => panic!("Expected a map change")
+    });
+
+    let redo = |world: &mut World, steps: Vec<&StepType>| steps.into_iter().for_each(|step| match step {
+        StepType::MapChange((_, redo)) => map.change(world, &resources, redo.clone(), None),
+        _ => panic!("Expected a map change")
+    });
+
+    undo(&mut world, step.get_ordered_steps(false));
+
+    assert_eq!(get_map_tiles(&mut world, &resources, whole), original);
+
+    redo(&mut world, step.get_ordered_steps(true));
+
+    assert_eq!(get_map_tiles(&mut world, &resources, whole), changed);
+}
@@ -0,0 +1,195 @@
+use legion::*;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        Map,
+        MapChunkData,
+        MapChunkIndex,
+        TileData,
+        document::Document,
+        map_reset,
+        repaint_octree,
+        shape::TileShape,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+fn get_octree(aabb: AABB) -> Octree {
+    let mut octree = Octree::new(aabb, octree::DEFAULT_MAX);
+
+    aabb.iter_points().for_each(|point| {
+        octree.insert(TileData::new((point.x + point.y + point.z).rem_euclid(64) as u32, point)).unwrap();
+    });
+
+    octree
+}
+
+#[test]
+fn repainting_keeps_points_and_shapes() {
+    let aabb = AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 3, 3));
+
+    let tiles = vec![
+        TileData::new(1, Point::new(0, 0, 0)),
+        TileData::new(2, Point::new(1, 0, 0)).with_shape(TileShape::Ramp(1)),
+        TileData::new(3, Point::new(3, 2, 1)).with_shape(TileShape::Corner(3)),
+    ];
+
+    let octree = repaint_octree(aabb, tiles.clone(), 7);
+
+    assert_eq!(octree.count(), tiles.len());
+
+    tiles.iter().for_each(|tile_data| {
+        let repainted = octree.query_point(tile_data.get_point()).unwrap();
+
+        assert_eq!(repainted.get_tile(), 7);
+        assert_eq!(repainted.get_shape(), tile_data.get_shape());
+    });
+}
+
+#[test]
+fn repainting_leaves_empty_cells_empty() {
+    let aabb = AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 3, 3));
+
+    let octree = repaint_octree(aabb, vec![TileData::new(1, Point::new(2, 2, 2))], 7);
+
+    (0..4).for_each(|x| (0..4).for_each(|y| (0..4).for_each(|z| {
+        let point = Point::new(x, y, z);
+
+        assert_eq!(octree.query_point(point).is_some(), point == Point::new(2, 2, 2), "{:?}", point);
+    })));
+
+    assert_eq!(repaint_octree(aabb, Vec::new(), 7).count(), 0);
+}
+
+#[test]
+fn clipping_without_bounds() {
+    let octree = get_octree(AABB::from_extents(Point::new(-20, 0, -20), Point::new(20, 3, 20)));
+
+    assert_eq!(Map::default().clip_to_bounds(octree.clone()).unwrap(), octree);
+}
+
+#[test]
+fn clipping_inside_bounds() {
+    let map = Map::new(Point::new(10, 10, 10), Some(AABB::from_extents(Point::new(-10, 0, -10), Point::new(10, 10, 10))));
+    let octree = get_octree(AABB::from_extents(Point::new(-10, 0, -10), Point::new(10, 3, 10)));
+
+    assert_eq!(map.clip_to_bounds(octree.clone()).unwrap(), octree);
+}
+
+#[test]
+fn clipping_across_bounds() {
+    let bounds = AABB::from_extents(Point::new(0, 0, 0), Point::new(9, 9, 9));
+    let map = Map::new(Point::new(10, 10, 10), Some(bounds));
+
+    let octree = get_octree(AABB::from_extents(Point::new(-5, -5, -5), Point::new(4, 4, 4)));
+    let clipped = map.clip_to_bounds(octree.clone()).unwrap();
+
+    assert_eq!(clipped.get_aabb(), AABB::from_extents(Point::new(0, 0, 0), Point::new(4, 4, 4)));
+    assert_eq!(clipped.count(), 5 * 5 * 5);
+    assert!(clipped.iter().all(|tile_data| bounds.contains_point(tile_data.get_point())));
+    assert!(clipped.iter().all(|tile_data| octree.query_point(tile_data.get_point()) == Some(*tile_data)));
+}
+
+#[test]
+fn clipping_outside_bounds() {
+    let map = Map::new(Point::new(10, 10, 10), Some(AABB::from_extents(Point::new(0, 0, 0), Point::new(9, 9, 9))));
+
+    assert!(map.clip_to_bounds(get_octree(AABB::from_extents(Point::new(10, 0, 0), Point::new(12, 2, 2)))).is_err());
+}
+
+#[test]
+fn rechunking() {
+    let previous = Map::new(Point::new(10, 10, 10), None);
+    let map = Map::new(Point::new(4, 3, 4), None);
+
+    //the chunks of the previous map, including some at negative points
+    let octrees = [Point::new(-1, 0, -1), Point::new(0, 0, 0), Point::new(1, 0, -1)].iter()
+        .map(|chunk_pt| {
+            let aabb = previous.get_chunk_aabb(*chunk_pt);
+
+            get_octree(AABB::from_extents(aabb.get_min(), aabb.get_max() - Point::new(0, 6, 0)))
+        })
+        .collect::<Vec<Octree>>();
+
+    let chunks = map.get_chunked_octrees(&octrees);
+
+    assert_eq!(chunks.values().map(|octree| octree.count()).sum::<usize>(), octrees.iter().map(|octree| octree.count()).sum::<usize>());
+
+    chunks.iter().for_each(|(chunk_pt, octree)| {
+        assert_eq!(octree.get_aabb(), map.get_chunk_aabb(*chunk_pt));
+
+        octree.iter().for_each(|tile_data| {
+            let original = octrees.iter().find_map(|original| original.query_point(tile_data.get_point()));
+
+            assert_eq!(original, Some(*tile_data));
+        });
+    });
+}
+
+#[test]
+fn freeing_chunks() {
+    let map = Map::new(Point::new(4, 3, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    let aabb = AABB::from_extents(Point::new(-5, -2, -3), Point::new(6, 4, 2));
+    let octree = get_octree(aabb);
+
+    let chunks = map.get_chunked_octrees(std::slice::from_ref(&octree));
+
+    chunks.values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    assert!(chunks.len() > 1);
+    assert_eq!(chunk_index.len(), chunks.len());
+
+    //a range within a single chunk only gets the tiles of that chunk, while one that covers them all gets every tile back
+    let within = map.get_chunk_aabb(Point::new(-1, 0, -1));
+
+    assert_eq!(map.query_chunk_range(&mut world, &chunk_index, within).len(), octree.query_range(within).len());
+
+    let mut found = map.query_chunk_range(&mut world, &chunk_index, aabb);
+    found.sort_by_key(|tile_data| (tile_data.get_point().x, tile_data.get_point().y, tile_data.get_point().z));
+
+    let mut expected = octree.into_iter().collect::<Vec<TileData>>();
+    expected.sort_by_key(|tile_data| (tile_data.get_point().x, tile_data.get_point().y, tile_data.get_point().z));
+
+    assert_eq!(found, expected);
+
+    resources.insert(chunk_index);
+
+    map.free(&mut world, &resources);
+
+    assert!(resources.get::<MapChunkIndex>().unwrap().is_empty());
+    assert_eq!(<Read<MapChunkData>>::query().iter(&world).count(), 0);
+    assert!(map.query_chunk_range(&mut world, &resources.get::<MapChunkIndex>().unwrap(), aabb).is_empty());
+}
+
+#[test]
+fn resetting_the_map() {
+    let map = Map::new(Point::new(4, 3, 4), Some(AABB::from_extents(Point::new(0, 0, 0), Point::new(7, 7, 7))));
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    map.get_chunked_octrees(&[get_octree(AABB::from_extents(Point::new(0, 0, 0), Point::new(5, 1, 5)))]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    resources.insert(map);
+    resources.insert(chunk_index);
+
+    map_reset(&mut world, &mut resources);
+
+    //the chunk dimensions and bounds of the previous document don't carry over into the new one
+    assert_eq!(*resources.get::<Map>().unwrap(), Document::default().get_map());
+    assert!(map.query_chunk_range(&mut world, &resources.get::<MapChunkIndex>().unwrap(), map.get_chunk_aabb(Point::zeros())).is_empty());
+}
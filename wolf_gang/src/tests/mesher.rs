@@ -0,0 +1,324 @@
+use crate::{
+    collections::octree::{self, PointData},
+    systems::level_map::{
+        TileData,
+        mesher::{self, ChunkOctrees, VertexData},
+        shape::{self, TileShape},
+        tile_definitions::{TileDefinition, TileDefinitions, WallRepeat},
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+const CHUNK_DIMENSIONS: i32 = 4;
+
+/// Compares the mesh against the file of the same name in the golden directory. Run the tests with UPDATE_GOLDEN set to write the current
+/// output to the files instead, then look over the differences before committing them.
+macro_rules! assert_golden {
+    ($name:literal, $vertex_data:expr) => {
+        let actual = dump(&$vertex_data);
+
+        if std::env::var("UPDATE_GOLDEN").is_ok() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/golden/", $name, ".txt");
+            std::fs::write(path, &actual).unwrap();
+        } else {
+            assert_eq!(actual, include_str!(concat!("golden/", $name, ".txt")), "{} doesn't match its golden output", $name);
+        }
+    };
+}
+
+/// Writes out every buffer with a few decimal places, which is enough to catch changes without tripping over float rounding
+fn dump(vertex_data: &VertexData) -> String {
+    let float = |f: f32| {
+        let formatted = format!("{:.4}", f);
+
+        if formatted == "-0.0000" { "0.0000".to_string() } else { formatted }
+    };
+
+    let mut out = String::new();
+
+    out.push_str("verts\n");
+    vertex_data.verts.iter().for_each(|v| out.push_str(&format!("{} {} {}\n", float(v.x), float(v.y), float(v.z))));
+
+    out.push_str("normals\n");
+    vertex_data.normals.iter().for_each(|n| out.push_str(&format!("{} {} {}\n", float(n.x), float(n.y), float(n.z))));
+
+    out.push_str("uvs\n");
+    vertex_data.uvs.iter().for_each(|uv| out.push_str(&format!("{} {}\n", float(uv.x), float(uv.y))));
+
+    out.push_str("uv2s\n");
+    vertex_data.uv2s.iter().for_each(|uv| out.push_str(&format!("{} {}\n", float(uv.x), float(uv.y))));
+
+    out.push_str("indices\n");
+    vertex_data.indices.chunks(3).for_each(|triangle| {
+        out.push_str(&triangle.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(" "));
+        out.push('\n');
+    });
+
+    out
+}
+
+/// Returns the octrees of the chunks the columns fall into, where each column is an x and z with the range of heights it fills
+fn get_chunks(columns: &[(i32, i32, std::ops::Range<i32>)]) -> ChunkOctrees {
+    let mut chunks = ChunkOctrees::new();
+
+    columns.iter().for_each(|(x, z, heights)| {
+        heights.clone().for_each(|y| insert_tile(&mut chunks, TileData::new(0, Point::new(*x, y, *z))));
+    });
+
+    chunks
+}
+
+/// Inserts tile_data into whichever chunk it falls into, making the chunk if it isn't there yet
+fn insert_tile(chunks: &mut ChunkOctrees, tile_data: TileData) {
+    let point = tile_data.get_point();
+    let chunk_point = point.map(|coord| (coord as f32 / CHUNK_DIMENSIONS as f32).floor() as i32);
+    let min = chunk_point * CHUNK_DIMENSIONS;
+
+    chunks.entry(chunk_point)
+        .or_insert_with(|| Octree::new(AABB::from_extents(min, min + Point::new(1,1,1) * (CHUNK_DIMENSIONS - 1)), octree::DEFAULT_MAX))
+        .insert(tile_data)
+        .unwrap();
+}
+
+/// Meshes every chunk, ordered by chunk point so that the output is the same every time
+fn mesh_all(chunks: &ChunkOctrees, definitions: &TileDefinitions) -> VertexData {
+    let mut chunk_points = chunks.keys().copied().collect::<Vec<Point>>();
+    chunk_points.sort_by_key(|point| (point.x, point.y, point.z));
+
+    let mut vertex_data = VertexData::default();
+
+    chunk_points.iter().for_each(|chunk_point| {
+        vertex_data.append(&mesher::mesh_chunk(chunks, &chunks[chunk_point], definitions));
+    });
+
+    vertex_data
+}
+
+fn assert_well_formed(vertex_data: &VertexData) {
+    let len = vertex_data.verts.len();
+
+    assert!(len > 0);
+    assert_eq!(vertex_data.normals.len(), len);
+    assert_eq!(vertex_data.uvs.len(), len);
+    assert_eq!(vertex_data.uv2s.len(), len);
+    assert_eq!(vertex_data.indices.len() % 3, 0);
+    assert!(vertex_data.indices.iter().all(|i| *i >= 0 && (*i as usize) < len));
+}
+
+#[test]
+fn single_column() {
+    let vertex_data = mesh_all(&get_chunks(&[(1, 1, 0..4)]), &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+    assert_golden!("single_column", vertex_data);
+}
+
+#[test]
+fn step() {
+    let vertex_data = mesh_all(&get_chunks(&[(1, 1, 0..4), (2, 1, 0..2)]), &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+    assert_golden!("step", vertex_data);
+}
+
+#[test]
+fn overhang() {
+    let vertex_data = mesh_all(&get_chunks(&[(1, 1, 0..4), (2, 1, 3..4)]), &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+    assert_golden!("overhang", vertex_data);
+}
+
+#[test]
+fn chunk_border_wall() {
+    let chunks = get_chunks(&[(3, 1, 0..4), (4, 1, 0..2)]);
+
+    assert_eq!(chunks.len(), 2);
+
+    let vertex_data = mesh_all(&chunks, &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+    assert_golden!("chunk_border_wall", vertex_data);
+}
+
+#[test]
+fn range_matches_chunk() {
+    let chunks = get_chunks(&[(1, 1, 0..4), (2, 1, 0..2), (2, 2, 0..3)]);
+    let octree = &chunks[&Point::zeros()];
+
+    let whole = mesher::mesh_range(&chunks, octree, octree.get_aabb(), &TileDefinitions::default());
+    let part = mesher::mesh_range(&chunks, octree, AABB::from_extents(Point::new(2, 0, 1), Point::new(2, 3, 2)), &TileDefinitions::default());
+
+    let mut indices = part.iter().map(|(index, _)| *index).collect::<Vec<usize>>();
+    indices.sort();
+
+    assert_eq!(indices, vec![mesher::get_column_index(octree, 2, 1), mesher::get_column_index(octree, 2, 2)]);
+
+    part.into_iter().for_each(|(index, column)| {
+        assert_eq!(whole.iter().find(|(whole_index, _)| *whole_index == index).map(|(_, column)| column), Some(&column));
+    });
+}
+
+#[test]
+fn enclosed_column_draws_only_its_top() {
+    //a column with a tile on every side has nothing to draw but its top
+    let mut columns = vec![(1, 1, 0..4)];
+    columns.extend(mesher::NEIGHBOR_DIRS.iter().map(|dir| (1 + dir.x, 1 + dir.z, 0..4)));
+
+    let chunks = get_chunks(&columns);
+    let octree = &chunks[&Point::zeros()];
+
+    let center = mesher::mesh_range(&chunks, octree, AABB::from_extents(Point::new(1, 0, 1), Point::new(1, 3, 1)), &TileDefinitions::default());
+    let column = mesher::merge_materials(&center[0].1);
+
+    assert_eq!(column.verts.len(), 4);
+    assert!(column.normals.iter().all(|normal| *normal == nalgebra::Vector3::y()));
+}
+
+#[test]
+fn ramp() {
+    //a ramp on top of a column, leading up to a taller column that covers its high side
+    let mut chunks = get_chunks(&[(1, 1, 0..2), (2, 1, 0..3)]);
+    insert_tile(&mut chunks, TileData::new(0, Point::new(1, 2, 1)).with_shape(TileShape::Ramp(0)));
+
+    let vertex_data = mesh_all(&chunks, &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+    assert_golden!("ramp", vertex_data);
+}
+
+#[test]
+fn shaped_tiles_are_flat_shaded() {
+    let mut chunks = ChunkOctrees::new();
+    insert_tile(&mut chunks, TileData::new(0, Point::new(1, 0, 1)).with_shape(TileShape::Ramp(0)));
+
+    let vertex_data = mesh_all(&chunks, &TileDefinitions::default());
+
+    assert_well_formed(&vertex_data);
+
+    //two triangles on top, two on the tall side, one on each sloped side and nothing on the side with no height
+    assert_eq!(vertex_data.indices.len(), 6 * 3);
+    assert_eq!(vertex_data.verts.len(), 6 * 3);
+
+    let slope = nalgebra::Vector3::new(-0.25, 1., 0.).normalize();
+
+    assert_eq!(vertex_data.normals.iter().filter(|normal| (**normal - slope).norm() < 0.0001).count(), 6);
+
+    //every triangle faces the way its normal points
+    vertex_data.indices.chunks(3).for_each(|triangle| {
+        let verts = [vertex_data.verts[triangle[0] as usize], vertex_data.verts[triangle[1] as usize], vertex_data.verts[triangle[2] as usize]];
+
+        assert!((shape::get_normal(&verts) - vertex_data.normals[triangle[0] as usize]).norm() < 0.0001);
+    });
+}
+
+#[test]
+fn shaped_sides_are_culled() {
+    let mut chunks = ChunkOctrees::new();
+    insert_tile(&mut chunks, TileData::new(0, Point::new(1, 0, 1)).with_shape(TileShape::Half));
+    insert_tile(&mut chunks, TileData::new(0, Point::new(2, 0, 1)).with_shape(TileShape::Half));
+
+    let vertex_data = mesh_all(&chunks, &TileDefinitions::default());
+
+    //each half tile has its top and three of its sides, since the sides they share are the same height
+    assert_eq!(vertex_data.indices.len(), 2 * (2 + 3 * 2) * 3);
+}
+
+#[test]
+fn full_tiles_keep_walls_beside_shapes() {
+    let mut chunks = get_chunks(&[(1, 1, 0..1)]);
+    insert_tile(&mut chunks, TileData::new(0, Point::new(2, 0, 1)).with_shape(TileShape::Half));
+
+    let octree = &chunks[&Point::zeros()];
+    let column = mesher::merge_materials(&mesher::mesh_range(&chunks, octree, AABB::from_extents(Point::new(1, 0, 1), Point::new(1, 0, 1)), &TileDefinitions::default()).remove(0).1);
+
+    assert!(column.normals.iter().any(|normal| (*normal - nalgebra::Vector3::x()).norm() < 0.0001));
+}
+
+fn get_definition(top: (u32, u32), bottom: (u32, u32), material: Option<&str>) -> TileDefinition {
+    TileDefinition {
+        name: "Test".to_string(),
+        top,
+        side: (0, 9),
+        bottom,
+        wall_repeat: WallRepeat::default(),
+        material: material.map(|material| material.to_string()),
+        icon: top,
+    }
+}
+
+/// Returns whether uv falls within the cell, allowing for the cell's edges
+fn is_in_cell(definitions: &TileDefinitions, uv: nalgebra::Vector2<f32>, cell: (u32, u32)) -> bool {
+    let min = definitions.get_uv_offset(cell);
+    let size = definitions.get_tile_size();
+
+    uv.x > min.x - 0.0001 && uv.x < min.x + size + 0.0001 && uv.y > min.y - 0.0001 && uv.y < min.y + size + 0.0001
+}
+
+#[test]
+fn tops_use_the_defined_cell() {
+    let mut definitions = TileDefinitions::default();
+    definitions.tiles.insert(0, get_definition((3, 1), (0, 0), None));
+
+    let vertex_data = mesh_all(&get_chunks(&[(1, 1, 0..1)]), &definitions);
+
+    let top_uvs = vertex_data.normals.iter().zip(vertex_data.uvs.iter())
+        .filter(|(normal, _)| **normal == nalgebra::Vector3::y())
+        .map(|(_, uv)| *uv)
+        .collect::<Vec<nalgebra::Vector2<f32>>>();
+
+    assert!(!top_uvs.is_empty());
+    assert!(top_uvs.iter().all(|uv| is_in_cell(&definitions, *uv, (3, 1))));
+}
+
+#[test]
+fn overhangs_have_an_underside() {
+    let mut definitions = TileDefinitions::default();
+    definitions.tiles.insert(0, get_definition((0, 0), (5, 2), None));
+
+    //the tile on the bottom has nothing under it to be seen from, so only the one hanging over it gets an underside
+    let vertex_data = mesh_all(&get_chunks(&[(1, 1, 0..1), (1, 1, 2..3)]), &definitions);
+
+    assert_well_formed(&vertex_data);
+
+    let underside = (0..vertex_data.verts.len())
+        .filter(|i| vertex_data.normals[*i] == -nalgebra::Vector3::y())
+        .collect::<Vec<usize>>();
+
+    assert_eq!(underside.len(), 2 * 3);
+    assert!(underside.iter().all(|i| (vertex_data.verts[*i].y - 0.5).abs() < 0.0001));
+    assert!(underside.iter().all(|i| is_in_cell(&definitions, vertex_data.uvs[*i], (5, 2))));
+}
+
+#[test]
+fn columns_are_split_by_material() {
+    let mut definitions = TileDefinitions::default();
+    definitions.tiles.insert(1, get_definition((1, 0), (1, 0), Some("res://materials/other.material")));
+
+    let mut chunks = get_chunks(&[(1, 1, 0..2)]);
+    insert_tile(&mut chunks, TileData::new(1, Point::new(2, 0, 1)));
+    insert_tile(&mut chunks, TileData::new(1, Point::new(1, 2, 1)).with_shape(TileShape::Half));
+
+    let octree = &chunks[&Point::zeros()];
+    let columns = mesher::mesh_range(&chunks, octree, octree.get_aabb(), &definitions);
+
+    let get_materials = |x: i32, z: i32| {
+        let index = mesher::get_column_index(octree, x, z);
+
+        columns.iter().find(|(column_index, _)| *column_index == index).unwrap().1.iter()
+            .map(|(material, _)| material.as_str())
+            .collect::<Vec<&str>>()
+    };
+
+    assert_eq!(get_materials(1, 1), vec!["res://materials/ground.material", "res://materials/other.material"]);
+    assert_eq!(get_materials(2, 1), vec!["res://materials/other.material"]);
+    assert!(get_materials(3, 1).is_empty());
+
+    //meshing the whole chunk keeps every material
+    let merged = columns.iter().map(|(_, column)| mesher::merge_materials(column).verts.len()).sum::<usize>();
+
+    assert_eq!(mesher::mesh_chunk(&chunks, octree, &definitions).verts.len(), merged);
+}
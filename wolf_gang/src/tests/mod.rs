@@ -2,4 +2,55 @@
 pub mod octree;
 
 #[cfg(test)]
-pub mod aabb;
\ No newline at end of file
+pub mod actor;
+
+#[cfg(test)]
+pub mod aabb;
+
+#[cfg(test)]
+pub mod clipboard;
+
+#[cfg(test)]
+pub mod region;
+
+#[cfg(test)]
+pub mod flood;
+
+#[cfg(test)]
+pub mod level_map;
+
+#[cfg(test)]
+pub mod symmetry;
+
+#[cfg(test)]
+pub mod history;
+
+#[cfg(test)]
+pub mod generator;
+
+#[cfg(test)]
+pub mod heightmap;
+
+#[cfg(test)]
+pub mod vox;
+
+#[cfg(test)]
+pub mod mesher;
+
+#[cfg(test)]
+pub mod obj;
+
+#[cfg(test)]
+pub mod collision;
+
+#[cfg(test)]
+pub mod shape;
+
+#[cfg(test)]
+pub mod tile_definitions;
+
+#[cfg(test)]
+pub mod document;
+
+#[cfg(test)]
+pub mod prefab;
\ No newline at end of file
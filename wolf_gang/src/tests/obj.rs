@@ -0,0 +1,81 @@
+use crate::{
+    collections::octree,
+    systems::level_map::{
+        TileData,
+        mesher::{ChunkOctrees, VertexData},
+        obj,
+        tile_definitions::TileDefinitions,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+type Vector2D = nalgebra::Vector2<f32>;
+
+fn get_triangle() -> VertexData {
+    VertexData {
+        verts: vec![Vector3D::new(0., 0., 0.), Vector3D::new(1., 0., 0.), Vector3D::new(0., 0., 1.)],
+        normals: vec![Vector3D::y(), Vector3D::y(), Vector3D::y()],
+        uvs: vec![Vector2D::new(0., 0.), Vector2D::new(0.0625, 0.), Vector2D::new(0., 0.25)],
+        uv2s: vec![Vector2D::zeros(), Vector2D::zeros(), Vector2D::zeros()],
+        indices: vec![0, 1, 2],
+    }
+}
+
+fn get_lines<'a>(obj: &'a str, prefix: &str) -> Vec<&'a str> {
+    obj.lines().filter(|line| line.starts_with(prefix)).collect()
+}
+
+#[test]
+fn writes_chunks() {
+    let obj = obj::write_obj(&[(Point::zeros(), get_triangle()), (Point::x(), get_triangle())], "map.mtl");
+
+    assert_eq!(obj.lines().next(), Some("mtllib map.mtl"));
+    assert_eq!(get_lines(&obj, "o "), vec!["o chunk_0_0_0", "o chunk_1_0_0"]);
+    assert_eq!(get_lines(&obj, "usemtl ").len(), 2);
+    assert_eq!(get_lines(&obj, "v ").len(), 6);
+    assert_eq!(get_lines(&obj, "vt ").len(), 6);
+    assert_eq!(get_lines(&obj, "vn ").len(), 6);
+
+    //indices count from 1 across the file and each triangle is flipped to counter clockwise
+    assert_eq!(get_lines(&obj, "f "), vec!["f 1/1/1 3/3/3 2/2/2", "f 4/4/4 6/6/6 5/5/5"]);
+}
+
+#[test]
+fn flips_v() {
+    let obj = obj::write_obj(&[(Point::zeros(), get_triangle())], "map.mtl");
+
+    assert_eq!(get_lines(&obj, "vt "), vec!["vt 0 1", "vt 0.0625 1", "vt 0 0.75"]);
+}
+
+#[test]
+fn material_uses_tile_sheet() {
+    let mtl = obj::write_mtl();
+
+    assert!(mtl.lines().any(|line| line == format!("newmtl {}", obj::MATERIAL_NAME)));
+    assert!(mtl.lines().any(|line| line == format!("map_Kd {}", obj::TEXTURE_FILE_NAME)));
+}
+
+#[test]
+fn meshes_every_chunk_in_order() {
+    let mut chunks = ChunkOctrees::new();
+
+    vec![Point::new(1, 0, 0), Point::new(0, 0, 0), Point::new(0, 1, 0)].into_iter().for_each(|chunk_point| {
+        let min = chunk_point * 4;
+        let mut octree = Octree::new(AABB::from_extents(min, min + Point::new(3, 3, 3)), octree::DEFAULT_MAX);
+
+        //leave the chunk above empty, which shouldn't get an object
+        if chunk_point.y == 0 {
+            octree.insert(TileData::new(0, min + Point::new(1, 0, 1))).unwrap();
+        }
+
+        chunks.insert(chunk_point, octree);
+    });
+
+    let meshed = obj::mesh_map(&chunks, &TileDefinitions::default());
+
+    assert_eq!(meshed.iter().map(|(chunk_point, _)| *chunk_point).collect::<Vec<Point>>(), vec![Point::zeros(), Point::x()]);
+    assert!(meshed.iter().all(|(_, vertex_data)| !vertex_data.indices.is_empty()));
+}
@@ -1,11 +1,15 @@
 use nalgebra::Vector3;
 
-use crate::collections::octree::Octree;
+use std::borrow::Cow;
+
+use crate::collections::octree::{Octree, PointData, Execution};
 use crate::collections::octree;
 use crate::geometry::aabb;
+use crate::geometry::ray::Ray;
 use crate::systems::level_map::TileData;
 
 type Point = Vector3<i32>;
+type Vector3D = Vector3<f32>;
 type AABB = aabb::AABB<i32>;
 
 #[test]
@@ -362,6 +366,689 @@ fn serialize_deserialize() {
 }
 
 
+#[test]
+fn query_ray_first() {
+    let aabb = AABB::from_extents(
+        Point::new(0,0,0), Point::new(7,7,7)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, 1
+    );
+
+    octree.insert(TileData::new(0, Point::new(5,2,3))).unwrap();
+    octree.insert(TileData::new(1, Point::new(2,2,3))).unwrap();
+    octree.insert(TileData::new(2, Point::new(6,6,6))).unwrap();
+
+    let ray = Ray::new(Vector3D::new(-1., 2.5, 3.5), Vector3D::x());
+
+    let (distance, hit) = octree.query_ray_first(ray, None).unwrap();
+    assert_eq!(hit.get_point(), Point::new(2,2,3));
+    assert!((distance - 3.).abs() < std::f32::EPSILON);
+
+    assert!(octree.query_ray_first(ray, Some(2.)).is_none());
+    assert!(octree.query_ray_first(Ray::new(Vector3D::new(-1., 0.5, 0.5), Vector3D::x()), None).is_none());
+}
+
+#[test]
+fn query_ray_ordered() {
+    let aabb = AABB::from_extents(
+        Point::new(0,0,0), Point::new(9,9,9)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, octree::DEFAULT_MAX
+    );
+
+    fill_octree(aabb, &mut octree, &mut 0);
+
+    //straight down a column from above the tree
+    let ray = Ray::new(Vector3D::new(4.5, 20., 4.5), -Vector3D::y());
+
+    let hits = octree.query_ray(ray, None);
+    assert_eq!(hits.len(), 10);
+    assert!(hits.iter().zip(hits.iter().skip(1)).all(|((a, _), (b, _))| a <= b));
+    assert_eq!(hits[0].1.get_point(), Point::new(4,9,4));
+
+    assert_eq!(octree.query_ray(ray, Some(12.)).len(), 3);
+}
+
+#[test]
+fn query_ray_first_matches_query_ray() {
+    let aabb = AABB::from_extents(
+        Point::new(-4,-4,-4), Point::new(4,4,4)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, 2
+    );
+
+    fill_octree(AABB::from_extents(Point::new(-4,-4,-4), Point::new(4,-1,4)), &mut octree, &mut 0);
+
+    let ray = Ray::from_points(Vector3D::new(-6., 6., -5.), Vector3D::new(3.2, -2.7, 2.1));
+
+    let first = octree.query_ray_first(ray, None).unwrap();
+    let all = octree.query_ray(ray, None);
+
+    assert!(!all.is_empty());
+    assert!((first.0 - all[0].0).abs() < std::f32::EPSILON);
+}
+
+#[test]
+fn nearest() {
+    let aabb = AABB::from_extents(
+        Point::new(-5,-5,-5), Point::new(5,5,5)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, 1
+    );
+
+    octree.insert(TileData::new(0, Point::new(4,4,4))).unwrap();
+    octree.insert(TileData::new(1, Point::new(-3,0,1))).unwrap();
+    octree.insert(TileData::new(2, Point::new(1,-1,0))).unwrap();
+
+    assert_eq!(octree.nearest(Point::zeros()).unwrap().get_point(), Point::new(1,-1,0));
+    assert_eq!(octree.nearest_by(Point::zeros(), |tile| tile.get_tile() == 0).unwrap().get_point(), Point::new(4,4,4));
+    assert!(octree.nearest_by(Point::zeros(), |tile| tile.get_tile() == 3).is_none());
+}
+
+#[test]
+fn k_nearest() {
+    let aabb = AABB::from_extents(
+        Point::new(0,0,0), Point::new(9,9,9)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, 4
+    );
+
+    fill_octree(aabb, &mut octree, &mut 0);
+
+    let point = Point::new(2,7,3);
+
+    let nearest = octree.k_nearest(point, 7);
+
+    assert_eq!(nearest.len(), 7);
+    assert_eq!(nearest[0].get_point(), point);
+    
+    //every other result is a direct neighbour, at a distance of 1
+    assert!(nearest.iter().skip(1).all(|tile| (tile.get_point() - point).abs().sum() == 1));
+
+    assert!(octree.k_nearest(point, 0).is_empty());
+    assert_eq!(octree.k_nearest(point, 2000).len(), 1000);
+}
+
+#[test]
+fn query_sphere() {
+    let aabb = AABB::from_extents(
+        Point::new(0,0,0), Point::new(9,9,9)
+    );
+
+    let mut octree = Octree::<i32, TileData>::new(
+        aabb, octree::DEFAULT_MAX
+    );
+
+    fill_octree(aabb, &mut octree, &mut 0);
+
+    let center = Point::new(5,5,5);
+    let radius = 3;
+
+    let expected = octree.clone().into_iter()
+        .filter(|tile| {
+            let diff = tile.get_point() - center;
+            diff.dot(&diff) <= radius * radius
+        })
+        .count();
+
+    assert_eq!(octree.query_sphere(center, radius).len(), expected);
+    assert_eq!(octree.query_sphere(Point::new(20,20,20), 2).len(), 0);
+}
+
+#[test]
+fn sequential_matches_parallel() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+
+    let mut sequential = Octree::<i32, TileData>::with_execution(aabb, octree::DEFAULT_MAX, Execution::Sequential);
+    let mut parallel = Octree::<i32, TileData>::with_execution(aabb, octree::DEFAULT_MAX, Execution::Parallel);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut sequential, &mut count);
+    fill_octree(aabb, &mut parallel, &mut count);
+
+    assert_eq!(sequential.count(), parallel.count());
+
+    let range = AABB::from_extents(Point::new(2, 2, 2), Point::new(6, 6, 6));
+
+    let mut from_sequential = sequential.query_range(range);
+    let mut from_parallel = parallel.query_range(range);
+    from_sequential.sort_by_key(|tile| (tile.get_point().x, tile.get_point().y, tile.get_point().z));
+    from_parallel.sort_by_key(|tile| (tile.get_point().x, tile.get_point().y, tile.get_point().z));
+
+    assert_eq!(from_sequential, from_parallel);
+
+    sequential.remove_range(range);
+    parallel.remove_range(range);
+
+    assert_eq!(sequential.count(), 1000 - 125);
+    assert_eq!(sequential.count(), parallel.count());
+    assert_eq!(sequential.query_point(Point::new(4, 4, 4)), None);
+    assert_eq!(sequential.query_point(Point::new(9, 9, 9)), parallel.query_point(Point::new(9, 9, 9)));
+}
+
+#[test]
+fn iter_borrows_every_element() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree, &mut count);
+
+    assert_eq!(octree.iter().count(), count);
+
+    let mut from_iter = octree.iter().map(|tile_data| *tile_data).collect::<Vec<TileData>>();
+    let mut from_into_iter = octree.clone().into_iter().collect::<Vec<TileData>>();
+    from_iter.sort_by_key(|tile| (tile.get_point().x, tile.get_point().y, tile.get_point().z));
+    from_into_iter.sort_by_key(|tile| (tile.get_point().x, tile.get_point().y, tile.get_point().z));
+
+    assert_eq!(from_iter, from_into_iter);
+
+    //stored elements are borrowed, only the points of uniform fills have to be made as they're reached
+    assert!(octree.iter().all(|tile_data| matches!(tile_data, Cow::Borrowed(_))));
+
+    let mut uniform = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+    uniform.fill(aabb, TileData::new(1, Point::zeros()));
+
+    assert_eq!(uniform.iter().count(), aabb.get_volume());
+    assert!(uniform.iter().all(|tile_data| matches!(tile_data, Cow::Owned(_))));
+}
+
+#[test]
+fn iter_range() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree, &mut count);
+
+    let range = AABB::from_extents(Point::new(-5, 2, 3), Point::new(4, 4, 4));
+
+    let in_range = octree.iter_range(range).map(|tile_data| *tile_data).collect::<Vec<TileData>>();
+
+    assert_eq!(in_range.len(), 5 * 3 * 2);
+    assert!(in_range.iter().all(|tile| range.contains_point(tile.get_point())));
+
+    let outside = AABB::from_extents(Point::new(20, 20, 20), Point::new(30, 30, 30));
+    assert_eq!(octree.iter_range(outside).count(), 0);
+}
+
+#[test]
+fn get_mut() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree, &mut count);
+
+    let point = Point::new(7, 8, 9);
+
+    *octree.get_mut(point).unwrap() = TileData::new(5, point);
+
+    assert_eq!(octree.query_point(point), Some(TileData::new(5, point)));
+    assert_eq!(octree.count(), count);
+    assert!(octree.get_mut(Point::new(10, 0, 0)).is_none());
+}
+
+#[test]
+fn update_point() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let from = Point::new(1, 1, 1);
+    let to = Point::new(8, 8, 8);
+
+    octree.insert(TileData::new(3, from)).unwrap();
+    octree.insert(TileData::new(1, to)).unwrap();
+
+    //same point only changes the element
+    octree.update_point(from, |tile| TileData::new(4, tile.get_point())).unwrap();
+    assert_eq!(octree.query_point(from), Some(TileData::new(4, from)));
+
+    //moving replaces whatever was at the destination
+    octree.update_point(from, |tile| TileData::new(tile.get_tile(), to)).unwrap();
+    assert_eq!(octree.query_point(from), None);
+    assert_eq!(octree.query_point(to), Some(TileData::new(4, to)));
+    assert_eq!(octree.count(), 1);
+
+    //out of bounds leaves the octree untouched
+    assert!(octree.update_point(to, |tile| TileData::new(tile.get_tile(), Point::new(10, 10, 10))).is_err());
+    assert_eq!(octree.query_point(to), Some(TileData::new(4, to)));
+
+    assert!(octree.update_point(from, |tile| tile).is_err());
+}
+
+#[test]
+fn insert_replaces_existing() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, 4);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree, &mut count);
+
+    //free up space in the root so that a naive insert would push a duplicate there
+    octree.remove_range(AABB::from_extents(Point::zeros(), Point::new(1, 1, 1)));
+    let remaining = octree.count();
+
+    let point = Point::new(9, 9, 9);
+    octree.insert(TileData::new(2, point)).unwrap();
+
+    assert_eq!(octree.count(), remaining);
+    assert_eq!(octree.query_point(point), Some(TileData::new(2, point)));
+}
+
+#[test]
+fn diff_and_apply() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree_a = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree_a, &mut count);
+
+    let mut octree_b = octree_a.clone();
+
+    assert!(octree_a.diff(&octree_b).is_empty());
+
+    let added = TileData::new(0, Point::new(0, 0, 0));
+    octree_a.remove_item(&added);
+    octree_b.remove_range(AABB::from_extents(Point::new(5, 5, 5), Point::new(6, 6, 6)));
+    octree_b.insert(TileData::new(7, Point::new(9, 9, 9))).unwrap();
+
+    let diff = octree_a.diff(&octree_b);
+
+    assert_eq!(diff.get_added(), &vec![added]);
+    assert_eq!(diff.get_removed().len(), 8);
+    assert_eq!(diff.get_changed(), &vec![(TileData::new(0, Point::new(9, 9, 9)), TileData::new(7, Point::new(9, 9, 9)))]);
+
+    let original = octree_a.clone();
+
+    octree_a.apply(&diff).unwrap();
+    assert!(octree_a.diff(&octree_b).is_empty());
+    assert_eq!(octree_a.count(), octree_b.count());
+
+    octree_a.apply(&diff.invert()).unwrap();
+    assert!(octree_a.diff(&original).is_empty());
+}
+
+#[test]
+fn diff_different_bounds() {
+    let octree_a = vec![
+        TileData::new(0, Point::new(0, 0, 0)),
+        TileData::new(1, Point::new(3, 3, 3)),
+    ].into_iter().collect::<Octree<i32, TileData>>();
+
+    let octree_b = vec![
+        TileData::new(2, Point::new(3, 3, 3)),
+        TileData::new(1, Point::new(-4, 8, 2)),
+    ].into_iter().collect::<Octree<i32, TileData>>();
+
+    let diff = octree_a.diff(&octree_b);
+
+    assert_eq!(diff.get_added(), &vec![TileData::new(1, Point::new(-4, 8, 2))]);
+    assert_eq!(diff.get_removed(), &vec![TileData::new(0, Point::new(0, 0, 0))]);
+    assert_eq!(diff.get_changed(), &vec![(TileData::new(1, Point::new(3, 3, 3)), TileData::new(2, Point::new(3, 3, 3)))]);
+}
+
+#[test]
+fn fill_is_uniform() {
+    let aabb = AABB::from_extents(Point::new(-10, -10, -10), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let tile = TileData::new(3, Point::zeros());
+
+    octree.fill(aabb, tile);
+
+    assert_eq!(octree.get_uniform(), Some(tile.with_point(aabb.get_min())));
+    assert_eq!(octree.count(), 20 * 20 * 20);
+    assert_eq!(octree.iter().count(), 20 * 20 * 20);
+    assert_eq!(octree.query_point(Point::new(4, -7, 2)), Some(TileData::new(3, Point::new(4, -7, 2))));
+
+    let range = AABB::from_extents(Point::new(-2, -2, -2), Point::new(1, 1, 1));
+    let in_range = octree.query_range(range);
+    assert_eq!(in_range.len(), 4 * 4 * 4);
+    assert!(in_range.iter().all(|tile| tile.get_tile() == 3 && range.contains_point(tile.get_point())));
+
+    let uncompressed = fill_octree_with(aabb, 3);
+
+    assert!(octree.diff(&uncompressed).is_empty());
+    assert!(bincode::serialize(&octree).unwrap().len() < bincode::serialize(&uncompressed).unwrap().len() / 100);
+
+    let deserialized: Octree<i32, TileData> = bincode::deserialize(&bincode::serialize(&octree).unwrap()).unwrap();
+    assert_eq!(deserialized, octree);
+}
+
+#[test]
+fn partial_fill() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let range = AABB::from_extents(Point::new(1, 0, 2), Point::new(7, 3, 9));
+    octree.fill(range, TileData::new(1, Point::zeros()));
+
+    assert_eq!(octree.count(), 7 * 4 * 8);
+    assert!(octree.iter().all(|tile| tile.get_tile() == 1 && range.contains_point(tile.get_point())));
+
+    //filling over an existing fill replaces it
+    octree.fill(aabb, TileData::new(2, Point::zeros()));
+    assert_eq!(octree.count(), 1000);
+    assert!(octree.iter().all(|tile| tile.get_tile() == 2));
+}
+
+#[test]
+fn uniform_expands_on_edit() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    octree.fill(aabb, TileData::new(0, Point::zeros()));
+
+    let point = Point::new(5, 6, 7);
+
+    octree.insert(TileData::new(0, point)).unwrap();
+    assert!(octree.get_uniform().is_some());
+
+    octree.insert(TileData::new(4, point)).unwrap();
+    assert!(octree.get_uniform().is_none());
+    assert_eq!(octree.count(), 1000);
+    assert_eq!(octree.query_point(point), Some(TileData::new(4, point)));
+    assert_eq!(octree.query_point(Point::new(5, 6, 6)), Some(TileData::new(0, Point::new(5, 6, 6))));
+
+    octree.remove_item(&TileData::new(0, Point::new(0, 0, 0)));
+    assert_eq!(octree.count(), 999);
+    assert_eq!(octree.query_point(Point::new(0, 0, 0)), None);
+
+    octree.remove_range(AABB::from_extents(Point::new(0, 0, 0), Point::new(9, 9, 4)));
+    assert_eq!(octree.count(), 500);
+    assert_eq!(octree.nearest(Point::new(0, 0, 0)), Some(TileData::new(0, Point::new(0, 0, 5))));
+}
+
+#[test]
+fn compress() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(9, 9, 9));
+    let mut octree = fill_octree_with(aabb, 6);
+
+    let uncompressed = octree.clone();
+
+    octree.compress();
+
+    assert_eq!(octree.get_uniform(), Some(TileData::new(6, Point::zeros())));
+    assert!(octree.diff(&uncompressed).is_empty());
+
+    let mut octree = uncompressed.clone();
+    octree.insert(TileData::new(1, Point::new(9, 9, 9))).unwrap();
+    octree.compress();
+
+    assert!(octree.get_uniform().is_none());
+    assert_eq!(octree.count(), 1000);
+    assert_eq!(octree.query_point(Point::new(9, 9, 9)), Some(TileData::new(1, Point::new(9, 9, 9))));
+    assert!(bincode::serialize(&octree).unwrap().len() < bincode::serialize(&uncompressed).unwrap().len());
+}
+
+#[test]
+fn uniform_queries_match_stored_elements() {
+    let aabb = AABB::from_extents(Point::new(-8, -8, -8), Point::new(7, 7, 7));
+
+    let mut uniform = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+    uniform.fill(aabb, TileData::new(2, Point::zeros()));
+
+    let stored = fill_octree_with(aabb, 2);
+
+    let rays = vec![
+        (Ray::new(Vector3D::new(-20., 0.5, 0.5), Vector3D::x()), None),
+        (Ray::new(Vector3D::new(-20., -19., -18.), Vector3D::new(1., 1., 1.)), None),
+        (Ray::new(Vector3D::new(0.3, 0.6, 0.2), Vector3D::new(-0.4, 1., 0.7)), Some(5.)),
+        (Ray::new(Vector3D::new(-8., -8., 2.), Vector3D::new(1., 1., 0.)), None),
+        (Ray::new(Vector3D::new(2.5, 30., -3.5), Vector3D::new(0.1, -1., 0.)), Some(25.)),
+    ];
+
+    for (ray, max_distance) in rays {
+        let mut from_uniform = uniform.query_ray(ray, max_distance);
+        let mut from_stored = stored.query_ray(ray, max_distance);
+
+        let by_point = |(_, a): &(f32, TileData), (_, b): &(f32, TileData)| (a.get_point().x, a.get_point().y, a.get_point().z).cmp(&(b.get_point().x, b.get_point().y, b.get_point().z));
+
+        from_uniform.sort_by(by_point);
+        from_stored.sort_by(by_point);
+
+        assert!(!from_stored.is_empty());
+        assert_eq!(from_uniform, from_stored);
+        assert_eq!(uniform.query_ray_first(ray, max_distance).map(|(distance, _)| distance), stored.query_ray_first(ray, max_distance).map(|(distance, _)| distance));
+    }
+
+    for point in vec![Point::new(1, -2, 3), Point::new(20, 0, -30), Point::new(-8, 7, -8)] {
+        let distance = |tile: &TileData| (tile.get_point() - point).map(|axis| axis * axis).sum();
+
+        let nearest = |octree: &Octree<i32, TileData>| octree.k_nearest(point, 30).iter().map(distance).collect::<Vec<i32>>();
+        assert_eq!(nearest(&uniform), nearest(&stored));
+
+        let odd = |tile: &TileData| tile.get_point().y % 2 != 0;
+        assert_eq!(uniform.nearest_by(point, odd).as_ref().map(distance), stored.nearest_by(point, odd).as_ref().map(distance));
+    }
+
+    //a move out of bounds is turned down before the fill gets expanded
+    assert!(uniform.update_point(Point::zeros(), |tile| tile.with_point(Point::new(8, 0, 0))).is_err());
+    assert!(uniform.get_uniform().is_some());
+}
+
+fn fill_octree_with(aabb: AABB, tile: u32) -> Octree<i32, TileData> {
+    let mut octree = Octree::<i32, TileData>::new(aabb, octree::DEFAULT_MAX);
+
+    let min = aabb.get_min();
+    let max = aabb.get_max();
+
+    for z in min.z..max.z+1 {
+        for y in min.y..max.y+1 {
+            for x in min.x..max.x+1 { 
+                octree.insert(TileData::new(tile, Point::new(x,y,z))).unwrap();
+            }
+        }
+    }
+
+    octree
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Box3D {
+    id: u32,
+    center: Vector3D,
+    half_extents: Vector3D,
+}
+
+impl PointData<f32> for Box3D {
+    fn get_point(&self) -> Vector3D {
+        self.center
+    }
+
+    fn with_point(&self, point: Vector3D) -> Self {
+        Box3D {
+            center: point,
+            ..*self
+        }
+    }
+
+    fn get_bounds(&self) -> aabb::AABB<f32> {
+        aabb::AABB::new(self.center, self.half_extents * 2.)
+    }
+}
+
+fn float_octree() -> Octree<f32, Box3D> {
+    let mut octree = Octree::new(aabb::AABB::new(Vector3D::zeros(), Vector3D::new(64., 64., 64.)), 4);
+
+    let mut id = 0;
+    for x in -10..10 {
+        for z in -10..10 {
+            octree.insert(Box3D {
+                id,
+                center: Vector3D::new(x as f32 * 1.5 + 0.25, 0.5, z as f32 * 1.5 + 0.25),
+                half_extents: Vector3D::new(0.25, 0.5, 0.25)
+            }).unwrap();
+            id += 1;
+        }
+    }
+
+    octree
+}
+
+#[test]
+fn float_insert_and_query() {
+    let octree = float_octree();
+
+    assert_eq!(octree.count(), 400);
+    assert_eq!(octree.iter().count(), 400);
+
+    let found = octree.query_point(Vector3D::new(1.75, 0.5, -2.75)).unwrap();
+    assert_eq!(found.center, Vector3D::new(1.75, 0.5, -2.75));
+
+    let range = aabb::AABB::from_extents(Vector3D::new(0., 0., 0.), Vector3D::new(3., 1., 3.));
+    assert_eq!(octree.query_range(range).len(), 4);
+
+    let nearest = octree.nearest(Vector3D::new(0.3, 0.4, 0.2)).unwrap();
+    assert_eq!(nearest.center, Vector3D::new(0.25, 0.5, 0.25));
+}
+
+#[test]
+fn query_intersecting() {
+    let octree = float_octree();
+
+    //only touches the edges of the boxes, none of their centers
+    let range = aabb::AABB::from_extents(Vector3D::new(0.45, 0., 0.45), Vector3D::new(1.55, 1., 1.55));
+
+    assert!(octree.query_range(range).is_empty());
+
+    let mut ids = octree.query_intersecting(range).into_iter().map(|item| item.id).collect::<Vec<u32>>();
+    ids.sort();
+
+    assert_eq!(ids.len(), 4);
+}
+
+#[test]
+fn float_push_shares_point() {
+    let mut octree = float_octree();
+
+    let item = Box3D {
+        id: 1000,
+        center: Vector3D::new(0.25, 0.5, 0.25),
+        half_extents: Vector3D::new(4., 4., 4.)
+    };
+
+    octree.push(item).unwrap();
+
+    assert_eq!(octree.count(), 401);
+    assert_eq!(octree.query_intersecting(aabb::AABB::new(Vector3D::new(3.5, 3.5, 3.5), Vector3D::new(0.5, 0.5, 0.5))), vec![item]);
+
+    let (distance, hit) = octree.query_ray_first(Ray::new(Vector3D::new(0.25, 10., 0.25), Vector3D::new(0., -1., 0.)), None).unwrap();
+    assert_eq!(hit, item);
+    assert!((distance - 5.5).abs() < 0.0001);
+
+    octree.remove_item(&item);
+    assert_eq!(octree.count(), 400);
+}
+
+#[test]
+fn growable() {
+    let aabb = AABB::from_extents(Point::zeros(), Point::new(3, 3, 3));
+    let mut octree = Octree::<i32, TileData>::growable(aabb, 4, None);
+
+    let mut count = 0;
+    fill_octree(aabb, &mut octree, &mut count);
+
+    let far = Point::new(-20, 37, 5);
+    octree.insert(TileData::new(1, far)).unwrap();
+
+    assert!(octree.get_aabb().contains_point(far));
+    assert!(octree.get_aabb().contains_point(Point::zeros()));
+    assert_eq!(octree.count(), count + 1);
+    assert_eq!(octree.query_point(far), Some(TileData::new(1, far)));
+    assert_eq!(octree.query_range(aabb).len(), count);
+
+    //fixed octrees still reject points outside of their bounds
+    let mut fixed = Octree::<i32, TileData>::new(aabb, 4);
+    assert!(fixed.insert(TileData::new(1, far)).is_err());
+}
+
+#[test]
+fn growable_with_limit() {
+    let limit = AABB::from_extents(Point::new(-10, -10, -10), Point::new(10, 10, 10));
+    let mut octree = Octree::<i32, TileData>::growable(AABB::from_extents(Point::zeros(), Point::new(1, 1, 1)), 2, Some(limit));
+
+    let mut count = 0;
+    fill_octree(AABB::from_extents(Point::zeros(), Point::new(1, 1, 1)), &mut octree, &mut count);
+
+    octree.insert(TileData::new(2, Point::new(-10, 9, 10))).unwrap();
+    octree.insert(TileData::new(2, Point::new(7, -3, 0))).unwrap();
+    assert!(octree.insert(TileData::new(2, Point::new(11, 0, 0))).is_err());
+
+    let bounds = octree.get_aabb();
+    assert!(limit.contains_point(bounds.get_min()) && limit.contains_point(bounds.get_max()));
+    assert_eq!(octree.count(), count + 2);
+    assert_eq!(octree.query_point(Point::new(7, -3, 0)), Some(TileData::new(2, Point::new(7, -3, 0))));
+}
+
+#[test]
+fn growable_fill() {
+    let mut octree = Octree::<i32, TileData>::growable(AABB::from_extents(Point::zeros(), Point::zeros()), octree::DEFAULT_MAX, None);
+
+    let range = AABB::from_extents(Point::new(-5, 0, 2), Point::new(12, 3, 8));
+    octree.fill(range, TileData::new(4, Point::zeros()));
+
+    assert_eq!(octree.count(), 18 * 4 * 7);
+    assert!(octree.iter().all(|tile| range.contains_point(tile.get_point())));
+}
+
+/// The layout octrees were saved with before uniform fills, loose bounds and growth
+#[derive(serde::Serialize)]
+struct SavedOctree {
+    aabb: AABB,
+    max_elements: usize,
+    elements: Vec<(u32, Point)>,
+    children: Vec<SavedOctree>,
+    paternity: SavedPaternity,
+}
+
+#[derive(serde::Serialize)]
+enum SavedPaternity {
+    ProudParent,
+    ChildFree,
+}
+
+#[test]
+fn legacy_octree() {
+    let leaf = |center: Point, elements: Vec<(u32, Point)>| SavedOctree {
+        aabb: AABB::new(center, Point::new(2, 2, 2)),
+        max_elements: 2,
+        elements,
+        children: Vec::new(),
+        paternity: SavedPaternity::ChildFree,
+    };
+
+    let saved = SavedOctree {
+        aabb: AABB::from_extents(Point::zeros(), Point::new(3, 3, 3)),
+        max_elements: 2,
+        elements: Vec::new(),
+        children: vec![
+            leaf(Point::new(1, 1, 1), vec![(1, Point::new(0, 0, 0)), (2, Point::new(1, 1, 1))]),
+            leaf(Point::new(3, 3, 3), vec![(3, Point::new(3, 3, 3))]),
+        ],
+        paternity: SavedPaternity::ProudParent,
+    };
+
+    let legacy: octree::LegacyOctree<i32, (u32, Point)> = bincode::deserialize(&bincode::serialize(&saved).unwrap()).unwrap();
+    let octree = legacy.into_octree(|(tile, point)| TileData::new(tile, point));
+
+    assert_eq!(octree.get_aabb(), AABB::from_extents(Point::zeros(), Point::new(3, 3, 3)));
+    assert_eq!(octree.count(), 3);
+    assert_eq!(octree.query_point(Point::new(1, 1, 1)), Some(TileData::new(2, Point::new(1, 1, 1))));
+    assert_eq!(octree.query_point(Point::new(3, 3, 3)), Some(TileData::new(3, Point::new(3, 3, 3))));
+}
+
 fn fill_octree(aabb: AABB, octree: &mut Octree<i32, TileData>, count: &mut usize) {
     let min = aabb.get_min();
     let max = aabb.get_max();
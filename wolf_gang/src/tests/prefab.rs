@@ -0,0 +1,186 @@
+use legion::*;
+use nalgebra::{Rotation3, Vector3};
+
+use std::collections::HashSet;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::{
+        actor,
+        actor::{Actor, ActorChange, ActorID, ActorSceneKey, Bounds},
+        history::{History, StepType},
+        level_map::{
+            CoordPos,
+            Map,
+            MapChunkIndex,
+            TileData,
+            prefab::{self, Prefab},
+        },
+        networking::ClientID,
+        transform::rotation::Rotation,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+const CLIENT_ID: u32 = 1;
+
+/// The tile that the actor inside of the saved range stands on
+const MARKED_TILE: u32 = 9;
+
+/// Where the marked tile and the actor standing on it are, relative to the minimum corner of the saved range
+const MARKED_POINT: (i32, i32, i32) = (1, 1, 2);
+
+fn get_actor(point: Point) -> (Actor, ActorID, ActorSceneKey, CoordPos, Bounds, Rotation) {
+    (
+        Actor("house".to_string()),
+        ActorID::new(),
+        ActorSceneKey("house".to_string()),
+        CoordPos::new(point),
+        Bounds(Vector3D::new(1., 0.25, 1.)),
+        Rotation { value: Rotation3::identity() }
+    )
+}
+
+/// Returns a world with a history for CLIENT_ID, two layers of tiles at the bottom of range with the marked tile among them, an actor
+/// standing on the marked tile and another one outside of range
+fn get_world(range: AABB) -> (World, Resources) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+
+    world.push((History::new(), ClientID::new(CLIENT_ID)));
+
+    resources.insert(map);
+    resources.insert(MapChunkIndex::default());
+
+    let min = range.get_min();
+    let max = range.get_max();
+    let marked = get_marked_point(min);
+
+    let octree = AABB::from_extents(min, Point::new(max.x, min.y + 1, max.z)).iter_points()
+        .map(|point| TileData::new(if point == marked { MARKED_TILE } else { 1 }, point))
+        .collect::<Octree>();
+
+    map.change(&mut world, &resources, octree, None);
+
+    world.push(get_actor(marked + Point::y()));
+    world.push(get_actor(max + Point::new(5, 0, 5)));
+
+    (world, resources)
+}
+
+fn get_marked_point(min: Point) -> Point {
+    min + Point::new(MARKED_POINT.0, MARKED_POINT.1, MARKED_POINT.2)
+}
+
+fn get_map_tiles(world: &mut World, resources: &Resources, range: AABB) -> Vec<TileData> {
+    let map = *resources.get::<Map>().unwrap();
+    let chunk_index = resources.get::<MapChunkIndex>().unwrap();
+
+    map.query_chunk_range(world, &chunk_index, range)
+}
+
+/// Returns the ActorID, point and rotation of every actor in world
+fn get_actors(world: &World) -> Vec<(u128, Point, Rotation3<f32>)> {
+    <(Read<ActorID>, Read<CoordPos>, Read<Rotation>)>::query().iter(world)
+        .map(|(actor_id, coord_pos, rotation)| (actor_id.val(), coord_pos.value, rotation.value))
+        .collect()
+}
+
+#[test]
+fn saving_a_prefab() {
+    let range = AABB::from_extents(Point::new(-3, 0, 2), Point::new(0, 2, 4));
+
+    let (mut world, mut resources) = get_world(range);
+
+    let prefab = Prefab::from_range(&mut world, &mut resources, "house", range, Rotation3::identity()).unwrap();
+
+    //prefabs are saved to file with bincode
+    let saved: Prefab = bincode::deserialize(&bincode::serialize(&prefab).unwrap()).unwrap();
+
+    assert_eq!(saved.get_name(), "house");
+    assert_eq!(saved.get_clipboard().get_octree(), prefab.get_clipboard().get_octree());
+
+    //the tiles are kept relative to the minimum corner of the range
+    let octree = saved.get_clipboard().get_octree();
+    let marked = get_marked_point(Point::zeros());
+
+    assert_eq!(octree.get_aabb(), AABB::from_extents(Point::zeros(), Point::new(3, 2, 2)));
+    assert_eq!(octree.count(), 4 * 2 * 3);
+    assert_eq!(octree.query_point(marked), Some(TileData::new(MARKED_TILE, marked)));
+
+    //only the actor inside of the range is saved, and stamping it gives it a new ActorID
+    let center = Point::new(10, 4, -10);
+    let stamped = actor::deserialize_actors(&saved.get_stamped_actors(center, Rotation3::identity()).unwrap()).unwrap();
+
+    let original_ids = get_actors(&world).into_iter().map(|(actor_id, _, _)| actor_id).collect::<HashSet<u128>>();
+    let stamped = get_actors(&stamped);
+
+    assert_eq!(stamped.len(), 1);
+    assert!(!original_ids.contains(&stamped[0].0));
+    assert_eq!(stamped[0].1, saved.get_clipboard().get_pasted_point(marked + Point::y(), center, Rotation3::identity()));
+}
+
+#[test]
+fn stamping_a_prefab() {
+    let range = AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 2, 2));
+
+    let (mut world, mut resources) = get_world(range);
+
+    let prefab = Prefab::from_range(&mut world, &mut resources, "house", range, Rotation3::identity()).unwrap();
+
+    let original_ids = get_actors(&world).into_iter().map(|(actor_id, _, _)| actor_id).collect::<HashSet<u128>>();
+
+    let center = Point::new(20, 3, -7);
+    let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), std::f32::consts::FRAC_PI_2);
+
+    let clipboard = prefab.get_clipboard();
+    let actors = prefab.get_stamped_actors(center, rotation).map(|serialized| ActorChange::ActorInsertion { serialized });
+
+    prefab::stamp(&mut world, &mut resources, clipboard, center, rotation, actors, Some(CLIENT_ID));
+
+    //the tiles land in the turned range around center, with the marked tile where the clipboard puts it
+    let pasted_range = clipboard.get_pasted_range(center, rotation);
+    let tiles = get_map_tiles(&mut world, &resources, pasted_range);
+
+    let marked = clipboard.get_pasted_point(get_marked_point(Point::zeros()), center, rotation);
+
+    assert_eq!(pasted_range.dimensions, Point::new(3, 3, 4));
+    assert_eq!(tiles.len(), 4 * 2 * 3);
+    assert_eq!(tiles.iter().filter(|tile_data| tile_data.get_tile() == MARKED_TILE).map(|tile_data| tile_data.get_point()).collect::<Vec<Point>>(), vec![marked]);
+
+    //the stamped actor still stands on the marked tile, turned along with it
+    let stamped = get_actors(&world).into_iter().filter(|(actor_id, _, _)| !original_ids.contains(actor_id)).collect::<Vec<(u128, Point, Rotation3<f32>)>>();
+
+    assert_eq!(stamped.len(), 1);
+
+    let (stamped_id, stamped_point, stamped_rotation) = stamped[0];
+
+    assert_eq!(stamped_point, marked + Point::y());
+    assert!(stamped_rotation.angle_to(&rotation) < 1e-4);
+
+    //the tiles and the actor are undone together, as a single step
+    let history = <Read<History>>::query().iter(&world).next().unwrap().clone();
+
+    match history.can_undo().unwrap() {
+        StepType::Group(steps) => {
+            assert_eq!(steps.len(), 2);
+
+            match &steps[0] {
+                StepType::MapChange((_, redo)) => assert_eq!(redo.get_aabb(), pasted_range),
+                _ => panic!("Expected a map change")
+            }
+
+            match &steps[1] {
+                StepType::ActorChange((ActorChange::ActorRemoval(actor_id), _)) => assert_eq!(*actor_id, stamped_id),
+                _ => panic!("Expected the removal of the stamped actor")
+            }
+        },
+        _ => panic!("Expected a group")
+    }
+}
\ No newline at end of file
@@ -0,0 +1,144 @@
+use legion::*;
+
+use std::collections::HashSet;
+
+use crate::{
+    collections::octree,
+    systems::level_map::{
+        Map,
+        MapChunkIndex,
+        TileData,
+        region::RegionTransform,
+        shape::TILE_SHAPES,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+/// Regions with odd and even sides, square and not
+fn get_regions() -> Vec<AABB> {
+    vec![
+        AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 1, 3)),
+        AABB::from_extents(Point::new(-2, 0, 1), Point::new(2, 0, 5)),
+        AABB::from_extents(Point::new(1, -1, -3), Point::new(3, 2, 2)),
+        AABB::from_extents(Point::new(-4, 0, 0), Point::new(1, 3, 0)),
+    ]
+}
+
+/// Returns a world holding tiles in chunks 4 tiles across
+fn get_world(tiles: &[TileData]) -> (World, Resources) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    let octree = tiles.iter().copied().collect::<Octree>();
+
+    map.get_chunked_octrees(&[octree]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    resources.insert(map);
+    resources.insert(chunk_index);
+
+    (world, resources)
+}
+
+#[test]
+fn rotating_four_times() {
+    get_regions().into_iter().for_each(|aabb| {
+        let turn = RegionTransform::Rotate(1);
+
+        aabb.iter_points().for_each(|point| {
+            let (turned_aabb, turned_point) = (0..4).fold((aabb, point), |(aabb, point), _| {
+                (turn.get_transformed_aabb(aabb), turn.transform_point(aabb, point))
+            });
+
+            assert_eq!(turned_aabb, aabb);
+            assert_eq!(turned_point, point, "{:?}", aabb);
+        });
+
+        (-4..8).for_each(|quarter_turns| {
+            assert_eq!(RegionTransform::Rotate(quarter_turns).get_transformed_aabb(aabb), RegionTransform::Rotate(quarter_turns + 4).get_transformed_aabb(aabb));
+        });
+    });
+}
+
+#[test]
+fn mirroring_twice() {
+    get_regions().into_iter().for_each(|aabb| {
+        [RegionTransform::MirrorX, RegionTransform::MirrorZ].iter().for_each(|mirror| {
+            assert_eq!(mirror.get_transformed_aabb(aabb), aabb);
+
+            aabb.iter_points().for_each(|point| {
+                assert_eq!(mirror.transform_point(aabb, mirror.transform_point(aabb, point)), point);
+            });
+        });
+    });
+}
+
+#[test]
+fn transforms_stay_in_range() {
+    get_regions().into_iter().for_each(|aabb| {
+        [
+            RegionTransform::Rotate(1), RegionTransform::Rotate(2), RegionTransform::Rotate(3), RegionTransform::Rotate(-1),
+            RegionTransform::MirrorX, RegionTransform::MirrorZ
+        ].iter().for_each(|transform| {
+            let transformed_aabb = transform.get_transformed_aabb(aabb);
+
+            let points = aabb.iter_points()
+                .map(|point| transform.transform_point(aabb, point))
+                .collect::<HashSet<Point>>();
+
+            //no two tiles end up in the same place, so every point in the range gets filled
+            assert_eq!(points.len(), aabb.get_volume(), "{:?}", transform);
+            assert!(points.iter().all(|point| transformed_aabb.contains_point(*point)), "{:?} {:?}", transform, aabb);
+
+            if aabb.dimensions.x == aabb.dimensions.z {
+                assert_eq!(transformed_aabb, aabb);
+            }
+        });
+    });
+}
+
+#[test]
+fn transforming_shapes() {
+    TILE_SHAPES.iter().for_each(|shape| {
+        let turn = RegionTransform::Rotate(1);
+
+        assert_eq!((0..4).fold(*shape, |shape, _| turn.transform_shape(shape)), *shape);
+        assert_eq!(RegionTransform::Rotate(2).transform_shape(*shape), turn.transform_shape(turn.transform_shape(*shape)));
+
+        [RegionTransform::MirrorX, RegionTransform::MirrorZ].iter().for_each(|mirror| {
+            assert_eq!(mirror.transform_shape(mirror.transform_shape(*shape)), *shape);
+        });
+    });
+}
+
+#[test]
+fn rotating_keeps_corners() {
+    //a selection 4 across and 2 deep turns into one 2 across and 4 deep, so the range of the change has corners that are in neither
+    let aabb = AABB::from_extents(Point::new(0, 0, 0), Point::new(3, 0, 1));
+    let turn = RegionTransform::Rotate(1);
+    let turned_aabb = turn.get_transformed_aabb(aabb);
+
+    let corners = aabb.union(turned_aabb).iter_points()
+        .filter(|point| !aabb.contains_point(*point) && !turned_aabb.contains_point(*point))
+        .collect::<Vec<Point>>();
+
+    assert_eq!(corners.len(), 4);
+
+    let mut tiles = aabb.iter_points().map(|point| TileData::new(1, point)).collect::<Vec<TileData>>();
+    tiles.push(TileData::new(2, corners[0]));
+
+    let (mut world, resources) = get_world(&tiles);
+
+    let octree = turn.get_transformed_octree(&mut world, &resources, aabb).unwrap();
+
+    assert_eq!(octree.query_point(corners[0]), Some(TileData::new(2, corners[0])));
+    assert_eq!(octree.count(), 9);
+    assert!(turned_aabb.iter_points().all(|point| octree.query_point(point).map(|tile_data| tile_data.get_tile()) == Some(1)));
+}
@@ -0,0 +1,126 @@
+use crate::systems::level_map::shape::{self, CORNERS, TILE_SHAPES, TileShape};
+
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+const SIDES: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Returns the height of the corner at x and z, which are each 0 or 1
+fn get_height(shape: TileShape, x: i32, z: i32) -> f32 {
+    shape.get_corner_heights()[CORNERS.iter().position(|corner| *corner == (x, z)).unwrap()]
+}
+
+#[test]
+fn rotating_moves_the_corners() {
+    TILE_SHAPES.iter().for_each(|shape| {
+        CORNERS.iter().for_each(|(x, z)| {
+            //the same turn as clipboard::rotate_point, about the middle of the tile
+            let (turned_x, turned_z) = (*z, 1 - x);
+
+            assert_eq!(get_height(shape.rotated(1), turned_x, turned_z), get_height(*shape, *x, *z), "{:?}", shape);
+        });
+
+        assert_eq!(shape.rotated(4), *shape);
+        assert_eq!(shape.rotated(-1), shape.rotated(3));
+    });
+}
+
+#[test]
+fn mirroring_moves_the_corners() {
+    TILE_SHAPES.iter().for_each(|shape| {
+        CORNERS.iter().for_each(|(x, z)| {
+            assert_eq!(get_height(shape.mirrored(true, false), 1 - x, *z), get_height(*shape, *x, *z), "{:?}", shape);
+            assert_eq!(get_height(shape.mirrored(false, true), *x, 1 - z), get_height(*shape, *x, *z), "{:?}", shape);
+        });
+
+        assert_eq!(shape.mirrored(true, true), shape.rotated(2));
+    });
+
+    assert_eq!(TileShape::Ramp(0).mirrored(true, false), TileShape::Ramp(2));
+    assert_eq!(TileShape::Ramp(0).mirrored(false, true), TileShape::Ramp(0));
+    assert_eq!(TileShape::Corner(0).mirrored(true, false), TileShape::Corner(3));
+}
+
+#[test]
+fn names_match_shapes() {
+    let mut names = TILE_SHAPES.iter().map(|shape| shape.get_name()).collect::<Vec<String>>();
+
+    assert_eq!(TileShape::Ramp(0).get_name(), "Ramp +x");
+    assert_eq!(TileShape::Ramp(1).get_name(), "Ramp -z");
+    assert_eq!(TileShape::Corner(0).get_name(), "Corner +x +z");
+    assert_eq!(TileShape::InnerCorner(0).get_name(), "Inner corner -x -z");
+
+    //the name of a corner is the corner that sticks out
+    assert_eq!(get_height(TileShape::Corner(1), 1, 0), 1.);
+    assert_eq!(TileShape::Corner(1).get_name(), "Corner +x -z");
+    assert_eq!(get_height(TileShape::InnerCorner(1), 0, 1), 0.);
+    assert_eq!(TileShape::InnerCorner(1).get_name(), "Inner corner -x +z");
+
+    names.sort();
+    names.dedup();
+
+    assert_eq!(names.len(), TILE_SHAPES.len());
+}
+
+#[test]
+fn faces_point_outwards() {
+    let min = Vector3D::new(2., 1., 3.);
+
+    TILE_SHAPES.iter().for_each(|shape| {
+        shape.get_top_triangles(min).iter().for_each(|triangle| {
+            assert!(shape::get_normal(triangle).y > 0., "{:?}", shape);
+        });
+
+        SIDES.iter().for_each(|(x, z)| {
+            let dir = Point::new(*x, 0, *z);
+            let outwards = Vector3D::new(*x as f32, 0., *z as f32);
+
+            shape.get_side_triangles(min, dir).iter().for_each(|triangle| {
+                assert!((shape::get_normal(triangle) - outwards).norm() < 0.0001, "{:?} {:?}", shape, dir);
+            });
+        });
+    });
+}
+
+#[test]
+fn top_follows_corners() {
+    let min = Vector3D::new(0., 1., 0.);
+
+    TILE_SHAPES.iter().for_each(|shape| {
+        let points = shape.get_top_triangles(min).iter().flat_map(|triangle| triangle.iter().copied()).collect::<Vec<Vector3D>>();
+
+        shape.get_corner_points(min).iter().for_each(|corner| {
+            assert!(points.iter().any(|point| (point - corner).norm() < 0.0001), "{:?}", shape);
+        });
+    });
+
+    //an outer corner only slopes on one triangle, leaving the other flat on the bottom
+    let flat = TileShape::Corner(0).get_top_triangles(min).iter()
+        .filter(|triangle| triangle.iter().all(|point| (point.y - 1.).abs() < 0.0001))
+        .count();
+
+    assert_eq!(flat, 1);
+}
+
+#[test]
+fn sides_with_no_height_are_left_out() {
+    let min = Vector3D::zeros();
+
+    assert_eq!(TileShape::Ramp(0).get_side_triangles(min, Point::x()).len(), 2);
+    assert_eq!(TileShape::Ramp(0).get_side_triangles(min, -Point::x()).len(), 0);
+    assert_eq!(TileShape::Ramp(0).get_side_triangles(min, Point::z()).len(), 1);
+    assert_eq!(TileShape::Half.get_side_triangles(min, Point::z()).len(), 2);
+}
+
+#[test]
+fn sides_are_covered_by_taller_neighbours() {
+    assert!(TileShape::Half.is_side_covered_by(Point::x(), TileShape::Full));
+    assert!(TileShape::Half.is_side_covered_by(Point::x(), TileShape::Half));
+    assert!(!TileShape::Full.is_side_covered_by(Point::x(), TileShape::Half));
+
+    //a ramp rising towards +x is covered by one falling away from it, but not by one that carries on rising
+    assert!(TileShape::Ramp(0).is_side_covered_by(Point::x(), TileShape::Ramp(2)));
+    assert!(!TileShape::Ramp(0).is_side_covered_by(Point::x(), TileShape::Ramp(0)));
+    assert!(TileShape::Ramp(0).is_side_covered_by(Point::z(), TileShape::Ramp(0)));
+    assert!(TileShape::Ramp(0).is_side_covered_by(-Point::x(), TileShape::Half));
+}
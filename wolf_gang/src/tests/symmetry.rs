@@ -0,0 +1,346 @@
+use legion::*;
+use nalgebra::{Rotation3, Vector3};
+
+use std::collections::HashSet;
+
+use crate::{
+    collections::octree::{self, PointData},
+    systems::{
+        actor,
+        actor::{Actor, ActorChange, ActorID, ActorSceneKey, Bounds},
+        level_map::{
+            CoordPos,
+            Map,
+            MapChange,
+            MapChunkIndex,
+            TileData,
+            flood::Connectivity,
+            region::RegionTransform,
+            shape::{CORNERS, TILE_SHAPES, TileShape},
+            symmetry::{Symmetry, SymmetryMode, SymmetryTransform},
+        },
+        transform::rotation::Rotation,
+    },
+};
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+type Vector3D = nalgebra::Vector3<f32>;
+
+const TRANSFORMS: [SymmetryTransform; 3] = [SymmetryTransform::MirrorX, SymmetryTransform::MirrorZ, SymmetryTransform::Point];
+
+/// Returns symmetry about the middle of range
+fn get_symmetry(mode: SymmetryMode, range: AABB) -> Symmetry {
+    let mut symmetry = Symmetry { mode, ..Symmetry::default() };
+    symmetry.set_axis_from_range(range);
+
+    symmetry
+}
+
+/// Returns the height of the corner at x and z, which are each 0 or 1
+fn get_height(shape: TileShape, x: i32, z: i32) -> f32 {
+    shape.get_corner_heights()[CORNERS.iter().position(|corner| *corner == (x, z)).unwrap()]
+}
+
+fn get_rotation(angle: f32) -> Rotation3<f32> {
+    Rotation3::from_axis_angle(&Vector3::y_axis(), angle)
+}
+
+/// Returns an actor of the given kind that takes up a single tile
+fn get_actor(key: &str, point: Point, rotation: Rotation3<f32>) -> (Actor, ActorID, ActorSceneKey, CoordPos, Bounds, Rotation) {
+    (
+        Actor(key.to_string()),
+        ActorID::new(),
+        ActorSceneKey(key.to_string()),
+        CoordPos::new(point),
+        Bounds(Vector3D::new(1., 0.25, 1.)),
+        Rotation { value: rotation }
+    )
+}
+
+/// Returns a world holding tiles in chunks 4 tiles across
+fn get_world(tiles: &[TileData]) -> (World, Resources) {
+    let map = Map::new(Point::new(4, 4, 4), None);
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let mut chunk_index = MapChunkIndex::default();
+
+    let octree = tiles.iter().copied().collect::<Octree>();
+
+    map.get_chunked_octrees(&[octree]).values().for_each(|octree| {
+        map.insert_mapchunk_with_octree(octree, &mut world, &mut chunk_index, false);
+    });
+
+    resources.insert(map);
+    resources.insert(chunk_index);
+
+    (world, resources)
+}
+
+#[test]
+fn odd_axis() {
+    //5 tiles across, so the axis lies on the middle tile
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(4, 0, 4)));
+
+    let point = Point::new(0, 3, 1);
+
+    assert_eq!(symmetry.transform_point(SymmetryTransform::MirrorX, point), Point::new(4, 3, 1));
+    assert_eq!(symmetry.transform_point(SymmetryTransform::MirrorZ, point), Point::new(0, 3, 3));
+    assert_eq!(symmetry.transform_point(SymmetryTransform::Point, point), Point::new(4, 3, 3));
+
+    //the middle tile is its own mirror image
+    assert_eq!(symmetry.transform_point(SymmetryTransform::Point, Point::new(2, 0, 2)), Point::new(2, 0, 2));
+}
+
+#[test]
+fn even_axis() {
+    //4 tiles across, so the axis lies between the middle two tiles
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(3, 0, 3)));
+
+    assert_eq!(symmetry.transform_point(SymmetryTransform::MirrorX, Point::new(1, 0, 0)), Point::new(2, 0, 0));
+    assert_eq!(symmetry.transform_point(SymmetryTransform::MirrorZ, Point::new(0, 0, 0)), Point::new(0, 0, 3));
+    assert_eq!(symmetry.transform_point(SymmetryTransform::Point, Point::new(1, 0, 2)), Point::new(2, 0, 1));
+}
+
+#[test]
+fn transforming_twice() {
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::new(-3, 0, 2), Point::new(6, 0, 5)));
+
+    let aabb = AABB::from_extents(Point::new(-1, 2, 0), Point::new(1, 4, 7));
+
+    TRANSFORMS.iter().for_each(|transform| {
+        AABB::from_extents(Point::new(-2, 0, -2), Point::new(2, 1, 2)).iter_points().for_each(|point| {
+            assert_eq!(symmetry.transform_point(*transform, symmetry.transform_point(*transform, point)), point);
+        });
+
+        assert_eq!(symmetry.transform_aabb(*transform, symmetry.transform_aabb(*transform, aabb)), aabb);
+    });
+}
+
+#[test]
+fn transforming_aabbs() {
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(3, 0, 3)));
+
+    let aabb = AABB::from_extents(Point::new(0, 1, 0), Point::new(1, 2, 0));
+
+    let expected = [
+        (SymmetryTransform::MirrorX, AABB::from_extents(Point::new(2, 1, 0), Point::new(3, 2, 0))),
+        (SymmetryTransform::MirrorZ, AABB::from_extents(Point::new(0, 1, 3), Point::new(1, 2, 3))),
+        (SymmetryTransform::Point, AABB::from_extents(Point::new(2, 1, 3), Point::new(3, 2, 3))),
+    ];
+
+    expected.iter().for_each(|(transform, transformed)| {
+        assert_eq!(symmetry.transform_aabb(*transform, aabb), *transformed, "{:?}", transform);
+
+        //every point of the aabb lands in the transformed aabb
+        aabb.iter_points().for_each(|point| {
+            assert!(transformed.contains_point(symmetry.transform_point(*transform, point)));
+        });
+    });
+}
+
+#[test]
+fn four_way_makes_three_copies() {
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(5, 0, 5)));
+
+    let transforms = symmetry.mode.get_transforms();
+
+    assert_eq!(transforms.len(), 3);
+
+    let mut octree = Octree::new(AABB::from_extents(Point::new(0, 0, 1), Point::new(0, 0, 1)), octree::DEFAULT_MAX);
+    octree.insert(TileData::new(1, Point::new(0, 0, 1))).unwrap();
+
+    let mut points = transforms.iter()
+        .flat_map(|transform| symmetry.transform_octree(*transform, &octree).iter().map(|tile_data| tile_data.get_point()).collect::<Vec<Point>>())
+        .collect::<HashSet<Point>>();
+
+    assert_eq!(points.len(), 3);
+
+    points.insert(Point::new(0, 0, 1));
+
+    assert_eq!(points, vec![Point::new(0, 0, 1), Point::new(5, 0, 1), Point::new(0, 0, 4), Point::new(5, 0, 4)].into_iter().collect());
+
+    assert_eq!(SymmetryMode::Off.get_transforms().len(), 0);
+    assert_eq!(SymmetryMode::MirrorX.get_transforms().len(), 1);
+    assert_eq!(SymmetryMode::Point.get_transforms().len(), 1);
+}
+
+#[test]
+fn mirroring_shapes() {
+    let symmetry = Symmetry::default();
+
+    TILE_SHAPES.iter().for_each(|shape| {
+        CORNERS.iter().for_each(|(x, z)| {
+            assert_eq!(get_height(symmetry.transform_shape(SymmetryTransform::MirrorX, *shape), 1 - x, *z), get_height(*shape, *x, *z), "{:?}", shape);
+            assert_eq!(get_height(symmetry.transform_shape(SymmetryTransform::MirrorZ, *shape), *x, 1 - z), get_height(*shape, *x, *z), "{:?}", shape);
+            assert_eq!(get_height(symmetry.transform_shape(SymmetryTransform::Point, *shape), 1 - x, 1 - z), get_height(*shape, *x, *z), "{:?}", shape);
+        });
+    });
+
+    //the shapes are mirrored along with the points when transforming an octree
+    let mut octree = Octree::new(AABB::from_extents(Point::zeros(), Point::zeros()), octree::DEFAULT_MAX);
+    octree.insert(TileData::new(1, Point::zeros()).with_shape(TileShape::Ramp(0))).unwrap();
+
+    let symmetry = get_symmetry(SymmetryMode::MirrorX, AABB::from_extents(Point::zeros(), Point::new(1, 0, 0)));
+    let mirrored = symmetry.transform_octree(SymmetryTransform::MirrorX, &octree);
+
+    assert_eq!(mirrored.query_point(Point::new(1, 0, 0)).map(|tile_data| tile_data.get_shape()), Some(TileShape::Ramp(0).mirrored(true, false)));
+}
+
+#[test]
+fn mirroring_rotations() {
+    let symmetry = Symmetry::default();
+
+    //including turns close to and right on a half turn, where the rotation's axis can't be relied on
+    let angles = [0., 0.3, std::f32::consts::FRAC_PI_2, 2., std::f32::consts::PI - 0.001, std::f32::consts::PI, -std::f32::consts::PI + 0.001, -1.];
+
+    angles.iter().for_each(|angle| {
+        //made out of two turns like the rotations pasting works with, which can lose the axis close to a half turn
+        let rotation = get_rotation(angle + std::f32::consts::FRAC_PI_2) * get_rotation(std::f32::consts::FRAC_PI_2).inverse();
+        let forward = rotation * Vector3D::z();
+
+        let mirrored = [
+            (SymmetryTransform::MirrorX, Vector3D::new(-forward.x, forward.y, forward.z)),
+            (SymmetryTransform::MirrorZ, Vector3D::new(forward.x, forward.y, -forward.z)),
+            (SymmetryTransform::Point, Vector3D::new(-forward.x, forward.y, -forward.z)),
+        ];
+
+        mirrored.iter().for_each(|(transform, expected)| {
+            let transformed = symmetry.transform_rotation(*transform, rotation) * Vector3D::z();
+
+            assert!((transformed - expected).norm() < 0.001, "{} {:?} {:?} {:?}", angle, transform, transformed, expected);
+        });
+    });
+}
+
+#[test]
+fn mirroring_inserted_actors() {
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(4, 0, 4)));
+
+    let rotation = get_rotation(0.5);
+
+    let mut actor_world = World::default();
+    let entity = actor_world.push(get_actor("tree", Point::new(0, 1, 1), rotation));
+    let actor_id = actor_world.entry(entity).unwrap().get_component::<ActorID>().unwrap().val();
+
+    let change = ActorChange::ActorInsertion { serialized: actor::serialize_actors_in_world(&mut actor_world).unwrap() };
+
+    let changes = symmetry.get_mirrored_actor_changes(&mut World::default(), &change);
+
+    assert_eq!(changes.len(), 3);
+
+    let mut points = HashSet::new();
+    let mut actor_ids = HashSet::new();
+
+    changes.iter().zip(symmetry.mode.get_transforms()).for_each(|(change, transform)| {
+        let serialized = match change {
+            ActorChange::ActorInsertion { serialized } => serialized,
+            _ => panic!("Expected an insertion")
+        };
+
+        let copy_world = actor::deserialize_actors(serialized).unwrap();
+        let mut query = <(Read<ActorID>, Read<CoordPos>, Read<Rotation>)>::query();
+
+        let copies = query.iter(&copy_world).collect::<Vec<(&ActorID, &CoordPos, &Rotation)>>();
+
+        assert_eq!(copies.len(), 1);
+
+        let (copy_id, coord_pos, copy_rotation) = copies[0];
+
+        assert_eq!(coord_pos.value, symmetry.transform_point(transform, Point::new(0, 1, 1)));
+        assert!((copy_rotation.value * Vector3D::z() - symmetry.transform_rotation(transform, rotation) * Vector3D::z()).norm() < 0.001);
+
+        points.insert(coord_pos.value);
+        actor_ids.insert(copy_id.val());
+    });
+
+    assert_eq!(points.len(), 3);
+
+    //every copy gets an ActorID of its own
+    assert_eq!(actor_ids.len(), 3);
+    assert!(!actor_ids.contains(&actor_id));
+}
+
+#[test]
+fn mirroring_removed_actors() {
+    let symmetry = get_symmetry(SymmetryMode::FourWay, AABB::from_extents(Point::zeros(), Point::new(4, 0, 4)));
+
+    let mut world = World::default();
+
+    let removed = world.push(get_actor("tree", Point::new(0, 1, 1), Rotation3::identity()));
+    let mirrored_x = world.push(get_actor("tree", Point::new(4, 1, 1), Rotation3::identity()));
+    let mirrored_z = world.push(get_actor("tree", Point::new(0, 1, 3), Rotation3::identity()));
+
+    //a different kind of actor where the last copy would be doesn't get removed
+    world.push(get_actor("rock", Point::new(4, 1, 3), Rotation3::identity()));
+
+    let get_id = |world: &World, entity: Entity| world.entry_ref(entity).unwrap().get_component::<ActorID>().unwrap().val();
+
+    let removed_id = get_id(&world, removed);
+    let expected = vec![get_id(&world, mirrored_x), get_id(&world, mirrored_z)];
+
+    let changes = symmetry.get_mirrored_actor_changes(&mut world, &ActorChange::ActorRemoval(removed_id));
+
+    let removed_ids = changes.iter().map(|change| match change {
+        ActorChange::ActorRemoval(actor_id) => *actor_id,
+        _ => panic!("Expected a removal")
+    }).collect::<Vec<u128>>();
+
+    assert_eq!(removed_ids, expected);
+
+    //nothing is removed for actors that don't exist
+    assert!(symmetry.get_mirrored_actor_changes(&mut world, &ActorChange::ActorRemoval(0)).is_empty());
+}
+
+#[test]
+fn mirroring_changes() {
+    //8 tiles across, with two full tiles on the near side, and a single ramp of another tile on the far side
+    let symmetry = get_symmetry(SymmetryMode::MirrorX, AABB::from_extents(Point::zeros(), Point::new(7, 0, 0)));
+
+    let (mut world, resources) = get_world(&[
+        TileData::new(1, Point::new(0, 0, 0)),
+        TileData::new(1, Point::new(1, 0, 0)),
+        TileData::new(2, Point::new(6, 0, 0)).with_shape(TileShape::Ramp(0)),
+    ]);
+
+    let far_side = |octree: &Octree| AABB::from_extents(Point::new(6, 0, 0), Point::new(7, 0, 0)).iter_points()
+        .map(|point| octree.query_point(point))
+        .collect::<Vec<Option<TileData>>>();
+
+    //repainting the far side keeps its own shapes and empty cells, instead of copying the near side's
+    let repaint = MapChange::MapRepaint { aabb: AABB::from_extents(Point::new(0, 0, 0), Point::new(1, 0, 0)), tile: 5 };
+    let mirrored = symmetry.transform_change(SymmetryTransform::MirrorX, &repaint).unwrap();
+    let octree = mirrored.get_octree(&mut world, &resources).unwrap();
+
+    assert_eq!(far_side(&octree), vec![Some(TileData::new(5, Point::new(6, 0, 0)).with_shape(TileShape::Ramp(0))), None]);
+
+    //flood fills start from the mirror image of the start, and fill whatever tile is there
+    let fill = MapChange::MapFloodFill { start: Point::new(1, 0, 0), tile: 5, connectivity: Connectivity::Four, max_volume: 4096 };
+    let mirrored = symmetry.transform_change(SymmetryTransform::MirrorX, &fill).unwrap();
+    let octree = mirrored.get_octree(&mut world, &resources).unwrap();
+
+    assert_eq!(octree.query_point(Point::new(6, 0, 0)), Some(TileData::new(5, Point::new(6, 0, 0)).with_shape(TileShape::Ramp(0))));
+
+    //insertions put down the mirror image of the tile
+    let insertion = MapChange::MapInsertion { aabb: AABB::from_extents(Point::new(0, 0, 0), Point::new(0, 0, 0)), tile_data: TileData::new(3, Point::zeros()).with_shape(TileShape::Ramp(0)) };
+    let mirrored = symmetry.transform_change(SymmetryTransform::MirrorX, &insertion).unwrap();
+    let octree = mirrored.get_octree(&mut world, &resources).unwrap();
+
+    assert_eq!(octree.get_aabb(), AABB::from_extents(Point::new(7, 0, 0), Point::new(7, 0, 0)));
+    assert_eq!(octree.query_point(Point::new(7, 0, 0)).map(|tile_data| tile_data.get_shape()), Some(TileShape::Ramp(0).mirrored(true, false)));
+
+    //region transforms turn the other way in a mirror, but not when turned halfway around
+    let rotation = MapChange::MapTransform { aabb: AABB::from_extents(Point::new(0, 0, 0), Point::new(1, 0, 0)), transform: RegionTransform::Rotate(1) };
+
+    match symmetry.transform_change(SymmetryTransform::MirrorX, &rotation) {
+        Some(MapChange::MapTransform { aabb, transform }) => {
+            assert_eq!(aabb, AABB::from_extents(Point::new(6, 0, 0), Point::new(7, 0, 0)));
+            assert_eq!(transform, RegionTransform::Rotate(-1));
+        },
+        change => panic!("Expected a region transform, got {:?}", change)
+    }
+
+    assert_eq!(symmetry.transform_region_transform(SymmetryTransform::Point, RegionTransform::Rotate(1)), RegionTransform::Rotate(1));
+}
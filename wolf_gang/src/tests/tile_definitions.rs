@@ -0,0 +1,78 @@
+use crate::systems::level_map::tile_definitions::{DEFAULT_SIDE_CELL, TileDefinitions, WallRepeat};
+
+const CONFIG: &str = include_str!("../../../config/tiles.ron");
+
+#[test]
+fn config_parses() {
+    let definitions = TileDefinitions::from_ron(CONFIG).unwrap();
+
+    assert_eq!(definitions.get_ids(), (0..64).collect::<Vec<u32>>());
+    assert_eq!(definitions.sheet, TileDefinitions::default().sheet);
+    assert!((definitions.get_tile_size() - 1. / 16.).abs() < std::f32::EPSILON);
+}
+
+#[test]
+fn config_matches_undefined_tiles() {
+    //the tiles in the config look the same as they did before they were defined, so that existing maps don't change
+    let definitions = TileDefinitions::from_ron(CONFIG).unwrap();
+    let undefined = TileDefinitions { tiles: Default::default(), ..definitions.clone() };
+
+    definitions.get_ids().into_iter().for_each(|id| {
+        assert_eq!(definitions.get_top(id), undefined.get_top(id));
+        assert_eq!(definitions.get_side(id), undefined.get_side(id));
+        assert_eq!(definitions.get_bottom(id), undefined.get_bottom(id));
+        assert_eq!(definitions.get_icon(id), undefined.get_icon(id));
+        assert_eq!(definitions.get_wall_repeat(id), undefined.get_wall_repeat(id));
+        assert_eq!(definitions.get_material(id), undefined.get_material(id));
+    });
+}
+
+#[test]
+fn undefined_tiles_use_their_index() {
+    let definitions = TileDefinitions::default();
+
+    assert_eq!(definitions.get_top(17), (1, 1));
+    assert_eq!(definitions.get_icon(35), (3, 2));
+    assert_eq!(definitions.get_side(17), DEFAULT_SIDE_CELL);
+    assert_eq!(definitions.get_wall_repeat(17), WallRepeat::default());
+    assert_eq!(definitions.get_name(17), "Tile 17");
+}
+
+#[test]
+fn material_falls_back_to_the_sheet() {
+    let definitions = TileDefinitions::from_ron(r#"(
+        sheet: "res://images/rocks.png",
+        material: "res://materials/rocks.material",
+        tile_pixels: 32.0,
+        sheet_pixels: 256.0,
+        tiles: {
+            2: (
+                name: "Lava",
+                top: (4, 0),
+                side: (4, 1),
+                bottom: (4, 2),
+                wall_repeat: (start_above: 1.0, repeat_above: 1.0, start_below: 0.0, repeat_below: 1.0),
+                material: Some("res://materials/lava.material"),
+                icon: (4, 0),
+            ),
+            3: (
+                name: "Rock",
+                top: (5, 0),
+                side: (5, 1),
+                bottom: (5, 2),
+                wall_repeat: (start_above: 2.0, repeat_above: 2.0, start_below: 0.0, repeat_below: 1.0),
+                icon: (5, 0),
+            ),
+        },
+    )"#).unwrap();
+
+    assert_eq!(definitions.get_ids(), vec![2, 3]);
+    assert_eq!(definitions.get_name(2), "Lava");
+    assert_eq!(definitions.get_material(2), "res://materials/lava.material");
+    assert_eq!(definitions.get_material(3), "res://materials/rocks.material");
+    assert_eq!(definitions.get_material(4), "res://materials/rocks.material");
+
+    //8 cells to a row on this sheet
+    assert_eq!(definitions.get_top(9), (1, 1));
+    assert_eq!(definitions.get_uv_offset((4, 1)), nalgebra::Vector2::new(0.5, 0.125));
+}
@@ -0,0 +1,148 @@
+use crate::{
+    collections::octree,
+    systems::level_map::{
+        TileData,
+        vox::{Vox, VoxModel, VoxTileMapping},
+    },
+};
+
+use std::collections::HashSet;
+
+type AABB = crate::geometry::aabb::AABB<i32>;
+type Octree = octree::Octree<i32, TileData>;
+type Point = nalgebra::Vector3<i32>;
+
+const CONFIG: &str = include_str!("../../../config/vox_tiles.ron");
+
+fn get_model() -> VoxModel {
+    VoxModel {
+        dimensions: Point::new(3, 2, 4),
+        voxels: vec![
+            (Point::new(0, 0, 0), 1),
+            (Point::new(2, 0, 3), 3),
+            (Point::new(1, 1, 2), 8),
+        ]
+    }
+}
+
+#[test]
+fn round_trip() {
+    let vox = Vox {
+        models: vec![get_model()],
+        palette: Some((0..255).map(|i| [i as u8, 0, 255 - i as u8, 255]).collect())
+    };
+
+    let bytes = vox.to_bytes().unwrap();
+
+    assert_eq!(&bytes[0..4], b"VOX ");
+    assert_eq!(Vox::from_bytes(&bytes).unwrap(), vox);
+}
+
+#[test]
+fn swaps_up_axis() {
+    let vox = Vox { models: vec![get_model()], palette: None };
+    let bytes = vox.to_bytes().unwrap();
+
+    //the SIZE chunk comes straight after the MAIN chunk's header, with z going up
+    let size = &bytes[32..44];
+    assert_eq!(size, &[3, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0]);
+
+    //the first voxel comes after the SIZE chunk, the XYZI chunk's header and the voxel count
+    let voxel = &bytes[44 + 12 + 4..44 + 12 + 8];
+    assert_eq!(voxel, &[0, 0, 0, 1]);
+
+    let voxel = &bytes[44 + 12 + 8..44 + 12 + 12];
+    assert_eq!(voxel, &[2, 3, 0, 3]);
+}
+
+#[test]
+fn skips_unknown_chunks() {
+    let mut bytes = Vox { models: vec![get_model()], palette: None }.to_bytes().unwrap();
+
+    //add a chunk this reader doesn't know about to the end of MAIN's children
+    let unknown = [b'n', b'T', b'R', b'N', 4, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 4];
+    bytes.extend_from_slice(&unknown);
+
+    let children_size = i32::from_le_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]) + unknown.len() as i32;
+    bytes[16..20].copy_from_slice(&children_size.to_le_bytes());
+
+    assert_eq!(Vox::from_bytes(&bytes).unwrap().models, vec![get_model()]);
+}
+
+#[test]
+fn rejects_bad_files() {
+    assert!(Vox::from_bytes(b"NOPE").is_err());
+
+    let bytes = Vox { models: vec![get_model()], palette: None }.to_bytes().unwrap();
+    assert!(Vox::from_bytes(&bytes[..bytes.len() - 2]).is_err());
+
+    //a voxel count far bigger than the chunk is an error rather than an allocation of that many voxels
+    let mut too_many = bytes.clone();
+    let count = too_many.windows(4).position(|id| id == b"XYZI").unwrap() + 12;
+    too_many[count..count + 4].copy_from_slice(&i32::MAX.to_le_bytes());
+    assert!(Vox::from_bytes(&too_many).is_err());
+
+    let too_big = VoxModel { dimensions: Point::new(257, 1, 1), voxels: Vec::new() };
+    assert!(Vox { models: vec![too_big], palette: None }.to_bytes().is_err());
+}
+
+#[test]
+fn tile_mapping() {
+    let mut mapping = VoxTileMapping::default();
+
+    assert_eq!(mapping.get_tile(1), 0);
+    assert_eq!(mapping.get_index(7), Some(8));
+    assert_eq!(mapping.get_index(254), Some(255));
+    assert_eq!(mapping.get_index(255), None);
+
+    mapping.tiles.insert(20, 3);
+    mapping.tiles.insert(2, 300);
+
+    assert_eq!(mapping.get_tile(20), 3);
+    assert_eq!(mapping.get_index(3), Some(20));
+    assert_eq!(mapping.get_index(300), Some(2));
+}
+
+#[test]
+fn tile_mapping_config() {
+    assert_eq!(VoxTileMapping::from_ron(CONFIG).unwrap(), VoxTileMapping::default());
+
+    let mapping = VoxTileMapping::from_ron("(tiles: {20: 3, 21: 3})").unwrap();
+
+    assert_eq!(mapping.get_tile(21), 3);
+    assert_eq!(mapping.get_index(3), Some(20));
+
+    assert_eq!(VoxTileMapping::from_ron("()").unwrap(), VoxTileMapping::default());
+    assert!(VoxTileMapping::from_ron("(tiles: {300: 1})").is_err());
+}
+
+#[test]
+fn octree_round_trip() {
+    let mapping = VoxTileMapping::default();
+    let min = Point::new(-4, 2, 10);
+
+    let octree = get_model().to_octree(min, &mapping);
+
+    assert_eq!(octree.get_aabb(), AABB::from_extents(min, min + Point::new(2, 1, 3)));
+    assert_eq!(octree.query_point(min + Point::new(1, 1, 2)).map(|tile_data| tile_data.get_tile()), Some(7));
+
+    let model = VoxModel::from_octree(&octree, &mapping).unwrap();
+
+    assert_eq!(model.dimensions, get_model().dimensions);
+    assert_eq!(
+        model.voxels.into_iter().collect::<HashSet<(Point, u8)>>(),
+        get_model().voxels.into_iter().collect::<HashSet<(Point, u8)>>()
+    );
+
+    let mut too_big = Octree::new(AABB::from_extents(Point::zeros(), Point::new(300, 0, 0)), octree::DEFAULT_MAX);
+    too_big.insert(TileData::new(0, Point::zeros())).ok();
+
+    assert!(VoxModel::from_octree(&too_big, &mapping).is_err());
+
+    //tiles past the last palette index can't be exported without being merged into another tile
+    let mut unmappable = Octree::new(AABB::from_extents(Point::zeros(), Point::new(1, 0, 0)), octree::DEFAULT_MAX);
+    unmappable.insert(TileData::new(0, Point::zeros())).ok();
+    unmappable.insert(TileData::new(400, Point::new(1, 0, 0))).ok();
+
+    assert!(VoxModel::from_octree(&unmappable, &mapping).is_err());
+}